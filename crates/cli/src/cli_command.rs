@@ -0,0 +1,8 @@
+use crate::merge_args::MergeArgs;
+
+/// Operations other than splitting, which is what klyv does without one
+#[derive(Debug, clap::Subcommand)]
+pub enum CliCommand {
+    /// Join the files of a split module back into its module file, the inverse of splitting
+    Merge(MergeArgs),
+}
@@ -0,0 +1,38 @@
+use klyv_core::prelude::*;
+use std::process::ExitCode;
+
+/// Any failure without a code of its own
+pub(crate) const FAILURE: u8 = 1;
+/// The git repository has changes which are not allowed
+pub(crate) const GIT_DIRTY: u8 = 3;
+/// Files could not be parsed, they were skipped or aborted the split
+pub(crate) const PARSE_FAILURE: u8 = 4;
+/// Reading, writing or deleting a file failed
+pub(crate) const IO_FAILURE: u8 = 5;
+/// A verified split introduced compile errors and was rolled back
+pub(crate) const ROLLED_BACK: u8 = 6;
+
+/// Describes the exit codes for `--help`, clap itself exits with 2 on
+/// invalid arguments
+pub(crate) const EXIT_CODES_HELP: &str = "Exit codes:
+  0  success
+  1  failure
+  2  invalid arguments
+  3  the git repository has changes which are not allowed
+  4  files could not be parsed
+  5  reading, writing or deleting a file failed
+  6  the split introduced compile errors and was rolled back (--verify)";
+
+/// The exit code of a run which failed with `error`
+pub(crate) fn exit_code(error: &Error) -> ExitCode {
+    let code = match error {
+        Error::GitDirty { .. } => GIT_DIRTY,
+        Error::Parse { .. } | Error::UnsupportedItem { .. } | Error::SkippedPaths { .. } => {
+            PARSE_FAILURE
+        }
+        Error::Io { .. } => IO_FAILURE,
+        Error::RolledBack { .. } => ROLLED_BACK,
+        _ => FAILURE,
+    };
+    ExitCode::from(code)
+}
@@ -0,0 +1,9 @@
+pub enum Foo {
+    B,
+}
+
+pub struct Banana;
+
+pub fn banana() -> Banana {
+    Banana
+}
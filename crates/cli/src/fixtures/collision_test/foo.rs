@@ -0,0 +1,9 @@
+pub enum Foo {
+    A,
+}
+
+pub struct Apple;
+
+pub fn apple() -> Apple {
+    Apple
+}
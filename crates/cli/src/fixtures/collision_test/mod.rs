@@ -0,0 +1,5 @@
+mod bar;
+mod foo;
+
+pub use bar::*;
+pub use foo::*;
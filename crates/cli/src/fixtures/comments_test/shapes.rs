@@ -0,0 +1,23 @@
+use std::fmt;
+
+// Section: shapes
+// (kept together on purpose)
+
+/// A shape with a name
+pub struct Shape {
+    // TODO: support colors
+    name: String, // never empty
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /* delegate to the name */
+        write!(f, "{}", self.name)
+    }
+}
+
+// Helper used by the CLI
+pub fn describe(shape: &Shape) -> String {
+    // Display does the heavy lifting
+    shape.to_string()
+}
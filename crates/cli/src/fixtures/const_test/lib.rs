@@ -0,0 +1,4 @@
+//! Crate with constants and statics
+mod shapes;
+
+pub use shapes::*;
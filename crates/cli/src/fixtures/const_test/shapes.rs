@@ -0,0 +1,39 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Largest radius a circle may have
+#[allow(dead_code)]
+const MAX_RADIUS: f64 = 10.0;
+
+/// Counts the created circles
+static CREATED: AtomicUsize = AtomicUsize::new(0);
+
+/// Used by more than one item
+const SIDES: u8 = 4;
+
+pub struct Circle {
+    pub radius: f64,
+}
+
+impl Circle {
+    pub fn new(radius: f64) -> Self {
+        Self {
+            radius: radius.min(MAX_RADIUS),
+        }
+    }
+}
+
+pub struct Square;
+
+impl Square {
+    pub fn sides(&self) -> u8 {
+        SIDES
+    }
+}
+
+pub fn created() -> usize {
+    CREATED.load(Ordering::Relaxed)
+}
+
+pub fn corners() -> u8 {
+    SIDES
+}
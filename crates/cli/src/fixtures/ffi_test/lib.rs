@@ -0,0 +1,7 @@
+//! Crate wrapping C functions
+#[macro_use]
+extern crate log;
+
+mod wrappers;
+
+pub use wrappers::*;
@@ -0,0 +1,40 @@
+use std::os::raw::c_int;
+
+extern "C" {
+    fn abs(x: c_int) -> c_int;
+}
+
+/// Math from libm
+#[link(name = "m")]
+extern "C" {
+    fn sqrt(x: f64) -> f64;
+    fn cbrt(x: f64) -> f64;
+}
+
+pub trait Measure {
+    fn measure(&self) -> f64;
+}
+
+pub trait Measurable = Measure + Clone;
+
+#[derive(Clone)]
+pub struct Distance(pub i32);
+
+impl Distance {
+    pub fn absolute(&self) -> i32 {
+        unsafe { abs(self.0) }
+    }
+}
+
+#[derive(Clone)]
+pub struct Area(pub f64);
+
+impl Measure for Area {
+    fn measure(&self) -> f64 {
+        unsafe { sqrt(self.0) }
+    }
+}
+
+pub fn cube_root(x: f64) -> f64 {
+    unsafe { cbrt(x) }
+}
@@ -0,0 +1,5 @@
+mod render;
+mod shapes;
+
+pub use render::*;
+pub use shapes::*;
@@ -0,0 +1,25 @@
+use std::fmt;
+
+use super::Circle;
+
+/// Renders shapes as text
+pub trait Render {
+    fn render(&self) -> String;
+}
+
+fn outline(radius: f64) -> String {
+    format!("({})", radius)
+}
+
+// Circles render as their outline
+impl Render for Circle {
+    fn render(&self) -> String {
+        outline(self.radius)
+    }
+}
+
+impl fmt::Display for Circle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render())
+    }
+}
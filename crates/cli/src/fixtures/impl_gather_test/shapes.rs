@@ -0,0 +1,3 @@
+pub struct Circle {
+    pub(crate) radius: f64,
+}
@@ -0,0 +1,41 @@
+use std::fmt;
+
+pub struct Circle {
+    pub radius: f64,
+}
+
+pub struct Wrapper<T>(pub T);
+
+pub trait Area {
+    fn area(&self) -> f64;
+}
+
+impl Area for &Circle {
+    fn area(&self) -> f64 {
+        std::f64::consts::PI * self.radius * self.radius
+    }
+}
+
+impl Area for Box<Circle> {
+    fn area(&self) -> f64 {
+        self.as_ref().area()
+    }
+}
+
+impl<T: Area> Area for Wrapper<T> {
+    fn area(&self) -> f64 {
+        self.0.area()
+    }
+}
+
+impl fmt::Display for self::Circle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "circle of radius {}", self.radius)
+    }
+}
+
+impl<T: Area> Area for Vec<T> {
+    fn area(&self) -> f64 {
+        self.iter().map(Area::area).sum()
+    }
+}
@@ -0,0 +1,3 @@
+pub struct Bar;
+
+pub struct Baz;
@@ -0,0 +1,21 @@
+use std::fmt::Display;
+
+pub const LIMIT: u8 = 3;
+
+/// The first letter
+pub struct Alpha(pub u8);
+
+impl Display for Alpha {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.min(LIMIT))
+    }
+}
+
+pub enum Beta {
+    A,
+    B,
+}
+
+pub fn make_alpha() -> Alpha {
+    Alpha(LIMIT)
+}
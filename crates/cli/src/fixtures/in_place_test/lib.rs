@@ -0,0 +1,11 @@
+//! Crate used to verify splitting files in place
+mod bar;
+mod foo;
+pub mod nested;
+
+pub use bar::*;
+pub use foo::*;
+
+pub mod prelude {
+    pub use crate::*;
+}
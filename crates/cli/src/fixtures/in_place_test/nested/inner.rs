@@ -0,0 +1,3 @@
+pub struct InnerA;
+
+pub struct InnerB;
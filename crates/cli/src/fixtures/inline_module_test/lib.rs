@@ -0,0 +1,4 @@
+//! Crate with inline modules
+mod shapes;
+
+pub use shapes::*;
@@ -0,0 +1,36 @@
+pub struct Circle;
+
+/// Geometry helpers
+#[allow(dead_code)]
+pub mod geometry {
+    use super::Circle;
+
+    // A point
+    pub struct Point {
+        pub x: f64,
+    }
+
+    pub struct Line(pub Point, pub Point);
+
+    pub fn origin(_circle: &Circle) -> Point {
+        Point { x: 0.0 }
+    }
+
+    pub(crate) fn grid() -> crate::shapes::geometry::nested::Grid {
+        crate::shapes::geometry::nested::Grid
+    }
+
+    pub(crate) mod nested {
+        pub enum Axis {
+            X,
+        }
+
+        pub struct Grid;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn it_works() {}
+}
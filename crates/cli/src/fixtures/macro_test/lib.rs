@@ -0,0 +1,22 @@
+//! Crate defining and invoking macros
+macro_rules! define_id {
+    (pub struct $name:ident;) => {
+        #[derive(Debug, Clone, Copy)]
+        pub struct $name(pub u32);
+    };
+}
+
+macro_rules! impl_describe {
+    ($name:ident) => {
+        impl $name {
+            pub fn describe(&self) -> String {
+                stringify!($name).to_owned()
+            }
+        }
+    };
+}
+
+#[macro_use]
+mod shapes;
+
+pub use shapes::*;
@@ -0,0 +1,52 @@
+use std::cell::Cell;
+
+macro_rules! square {
+    ($e:expr) => {
+        $e * $e
+    };
+}
+
+/// Creates a circle of the given radius
+#[macro_export]
+macro_rules! new_circle {
+    ($radius:expr) => {
+        $crate::Circle { radius: $radius }
+    };
+}
+
+thread_local! {
+    static COUNT: Cell<u32> = Cell::new(0);
+}
+
+define_id! {
+    pub struct ShapeId;
+}
+
+impl ShapeId {
+    pub fn next(self) -> Self {
+        ShapeId(self.0 + 1)
+    }
+}
+
+pub struct Circle {
+    pub radius: f64,
+}
+
+impl_describe!(Circle);
+
+impl Circle {
+    pub fn area(&self) -> f64 {
+        3.14 * square!(self.radius)
+    }
+}
+
+pub fn count() -> u32 {
+    COUNT.with(|count| {
+        count.set(count.get() + 1);
+        square!(count.get())
+    })
+}
+
+pub fn unit_circle() -> Circle {
+    new_circle!(1.0)
+}
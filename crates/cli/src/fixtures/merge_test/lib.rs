@@ -0,0 +1,4 @@
+//! Crate with a split module to merge
+mod shapes;
+
+pub use shapes::*;
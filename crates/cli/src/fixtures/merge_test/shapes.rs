@@ -0,0 +1,13 @@
+//! Shapes, split into one file per type
+mod circle;
+mod kind;
+mod square;
+
+pub use circle::*;
+pub use kind::*;
+pub use square::*;
+
+/// Names every shape
+pub fn describe(circle: &Circle) -> String {
+    format!("{} {:?}", circle.radius, circle.kind())
+}
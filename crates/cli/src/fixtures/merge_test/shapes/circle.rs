@@ -0,0 +1,30 @@
+use std::fmt;
+
+use super::kind::Kind;
+use crate::shapes::kind::DEFAULT_KIND;
+
+impl Circle {
+    pub fn new(radius: f64) -> Self {
+        Self {
+            radius,
+            kind: DEFAULT_KIND,
+        }
+    }
+
+    pub(super) fn kind(&self) -> &Kind {
+        &self.kind
+    }
+}
+
+/// A circle
+pub struct Circle {
+    pub(super) radius: f64,
+    kind: Kind,
+}
+
+impl fmt::Display for Circle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Only the radius matters
+        write!(f, "circle {}", self.radius)
+    }
+}
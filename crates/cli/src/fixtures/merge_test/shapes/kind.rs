@@ -0,0 +1,15 @@
+use std::fmt;
+
+pub(super) const DEFAULT_KIND: Kind = Kind::Round;
+
+/// Kinds of shapes
+#[derive(Debug)]
+pub enum Kind {
+    Round,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
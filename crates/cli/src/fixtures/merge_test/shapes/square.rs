@@ -0,0 +1,4 @@
+use crate::Circle;
+
+/// A square around a circle
+pub struct Square(pub Circle);
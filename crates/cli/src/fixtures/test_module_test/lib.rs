@@ -0,0 +1,5 @@
+mod shapes;
+mod units;
+
+pub use shapes::*;
+pub use units::*;
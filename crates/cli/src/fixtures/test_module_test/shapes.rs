@@ -0,0 +1,42 @@
+pub struct Circle {
+    radius: f64,
+}
+
+impl Circle {
+    pub fn new(radius: f64) -> Self {
+        Self { radius }
+    }
+
+    fn area(&self) -> f64 {
+        self.radius * self.radius * 3.0
+    }
+}
+
+pub struct Square {
+    side: f64,
+}
+
+impl Square {
+    pub fn new(side: f64) -> Self {
+        Self { side }
+    }
+}
+
+pub fn describe(circle: &Circle) -> String {
+    format!("{}", circle.area())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_area() {
+        assert_eq!(Circle::new(1.0).area(), 3.0);
+    }
+
+    #[test]
+    fn test_sizes() {
+        assert_eq!(Circle::new(2.0).radius, Square::new(2.0).side);
+    }
+}
@@ -0,0 +1,23 @@
+pub struct Meter(f64);
+
+pub struct Foot(f64);
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+
+    fn one() -> f64 {
+        1.0
+    }
+
+    #[test]
+    fn test_meter() {
+        assert_eq!(Meter(one()).0, 1.0);
+    }
+
+    #[test]
+    fn test_foot() {
+        assert_eq!(Foot(one()).0, 1.0);
+    }
+}
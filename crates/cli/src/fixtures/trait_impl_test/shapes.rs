@@ -0,0 +1,35 @@
+use std::fmt;
+
+pub trait Shape {
+    fn area(&self) -> f64;
+}
+
+pub struct Circle {
+    radius: f64,
+}
+
+pub struct Square(f64);
+
+impl Shape for Circle {
+    fn area(&self) -> f64 {
+        std::f64::consts::PI * self.radius * self.radius
+    }
+}
+
+impl fmt::Display for Circle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "circle {}", self.radius)
+    }
+}
+
+impl From<f64> for Square {
+    fn from(side: f64) -> Self {
+        Square(side)
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "square {}", self.0)
+    }
+}
@@ -0,0 +1,5 @@
+//! Crate used to verify that splitting keeps the public API
+mod shapes;
+pub mod units;
+
+pub use shapes::*;
@@ -0,0 +1,24 @@
+/// A circle
+pub struct Circle {
+    radius: f64,
+}
+
+// Counts how often circles are described
+struct Cache {
+    hits: u32,
+}
+
+impl Circle {
+    pub fn new(radius: f64) -> Self {
+        Self { radius }
+    }
+
+    fn area(&self) -> f64 {
+        self.radius * self.radius * 3.0
+    }
+}
+
+pub(crate) fn describe(circle: &Circle) -> String {
+    let cache = Cache { hits: 1 };
+    format!("{} of {} after {}", circle.area(), circle.radius, cache.hits)
+}
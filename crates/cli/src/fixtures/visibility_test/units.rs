@@ -0,0 +1,7 @@
+pub struct Meter(f64);
+
+pub struct Foot(pub f64);
+
+pub fn to_feet(meter: Meter) -> Foot {
+    Foot(meter.0 * 3.28)
+}
@@ -31,7 +31,8 @@ fn configure_fern_dispatcher(log_level: log::LevelFilter) {
             out.finish(format_args!("{time} {color} > {message}"));
         })
         .level(log_level)
-        .chain(std::io::stdout())
+        // Logs go to stderr, so that stdout only holds the plan or the report
+        .chain(std::io::stderr())
         .apply()
         .inspect_err(|e| eprintln!("💥 Failed to initialize logging with level `{log_level}`: {e}"))
         .unwrap();
@@ -1,16 +1,30 @@
+mod cli_command;
+mod exit_code;
 mod fixtures;
 mod init_logging;
+mod merge_args;
+mod render_error;
+mod report_format;
 mod test;
 
 use clap::Parser;
+use cli_command::CliCommand;
+use exit_code::{EXIT_CODES_HELP, exit_code};
 use init_logging::init_logging;
 use klyv_core::prelude::*;
+use render_error::{render_error, render_scan_failure};
+use report_format::ReportFormat;
 use std::path::PathBuf;
+use std::process::ExitCode;
 
 #[derive(Debug, Parser)]
 #[command(name = BINARY_NAME, about = "Splitting files with multiple types into separate files")]
 #[command(version = env!("CARGO_PKG_VERSION"))]
+#[command(after_help = EXIT_CODES_HELP)]
 pub struct CliArgs {
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+
     /// Source directory or file to split, if None is provided, it will default to the current directory
     #[arg(long, short)]
     source: Option<PathBuf>,
@@ -26,6 +40,30 @@ pub struct CliArgs {
     /// Allow git staged state
     #[arg(long, default_value = "false")]
     allow_staged: bool,
+
+    /// Print the planned files and their contents without writing anything to disk
+    #[arg(long, default_value = "false")]
+    dry_run: bool,
+
+    /// Run `cargo check` before and after writing and roll the split back if it introduced errors
+    #[arg(long, default_value = "false")]
+    verify: bool,
+
+    /// Move impl blocks into the file defining their type anywhere in the crate, not only within a file
+    #[arg(long, default_value = "false")]
+    gather_impls: bool,
+
+    /// Skip files and directories matching this glob, relative to the source directory. Hidden, gitignored and `target/` directories are always skipped
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Abort at the first file which can not be parsed, instead of skipping it and reporting it at the end
+    #[arg(long, default_value = "false")]
+    fail_fast: bool,
+
+    /// Print a summary of the created, modified and deleted files, the moved items and the warnings, instead of the human readable output
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    report: Option<ReportFormat>,
 }
 
 fn get_working_dir() -> PathBuf {
@@ -37,11 +75,20 @@ impl TryFrom<CliArgs> for Input {
     type Error = Error;
 
     fn try_from(args: CliArgs) -> Result<Self, Self::Error> {
+        let source = args.source.unwrap_or_else(get_working_dir);
+        let config = load_config().source(&source).call()?;
+        trace!("Config: {:?}", config);
         Ok(Input::builder()
-            .source(args.source.unwrap_or_else(get_working_dir))
+            .source(source)
             .maybe_out(args.out)
+            .config(config)
             .allow_git_dirty(args.allow_dirty)
             .allow_git_staged(args.allow_staged)
+            .dry_run(args.dry_run)
+            .verify(args.verify)
+            .gather_impls(args.gather_impls)
+            .exclude(args.exclude)
+            .fail_fast(args.fail_fast)
             .build())
     }
 }
@@ -56,24 +103,84 @@ impl<T, E> ResultExt<T, E> for Result<T, E> {
     }
 }
 
-pub fn run(input: Input) -> Result<FileSystemNode> {
+pub fn run(input: Input) -> Result<SplitOutcome> {
     info!("Splitting files at {}", input.source().display());
     split().input(input).call()
 }
 
+/// Merges the module described by `args`, printing the plan on a dry run
+fn run_merge(input: MergeInput) -> Result<()> {
+    info!("Merging module at {}", input.dir().display());
+    let dry_run = *input.dry_run();
+    let plan = merge().input(input).call()?;
+    if dry_run {
+        println!("{}", plan);
+    }
+    Ok(())
+}
+
 fn run_cli() -> Result<()> {
-    let args = CliArgs::parse();
+    let mut args = CliArgs::parse();
     trace!("Found CLI args: {:?}", args);
+    if let Some(CliCommand::Merge(merge_args)) = args.command.take() {
+        return run_merge(merge_args.into());
+    }
+    let report = args.report;
     let input = Input::try_from(args)?;
     trace!("Input: {:?}", input);
-    run(input).map_to_void()
+    let dry_run = *input.dry_run();
+    let outcome = run(input)?;
+    match report {
+        Some(ReportFormat::Json) => println!("{}", SplitReport::new(&outcome).to_json()),
+        None => {
+            if dry_run {
+                println!("{}", outcome.plan());
+            }
+            if let Some(verification) = outcome.verification() {
+                print!("{}", verification);
+            }
+        }
+    }
+    ensure_split_applied(&outcome)
+}
+
+/// Fails if the split of `outcome` was rolled back or left out paths which
+/// could not be parsed, after printing those
+fn ensure_split_applied(outcome: &SplitOutcome) -> Result<()> {
+    let scan_report = outcome.scan_report();
+    for failure in scan_report.failures() {
+        eprint!("{}", render_scan_failure(failure));
+    }
+    if let Some(verification) = outcome.verification()
+        && *verification.rolled_back()
+    {
+        return Err(Error::RolledBack {
+            package_dir: verification.package_dir().clone(),
+            errors: verification
+                .after()
+                .error_count()
+                .saturating_sub(verification.baseline().error_count()),
+        });
+    }
+    if !scan_report.is_clean() {
+        return Err(Error::SkippedPaths {
+            count: scan_report.failures().len(),
+        });
+    }
+    Ok(())
 }
 
-fn main() {
+fn main() -> ExitCode {
     init_logging();
     info!("Starting klyv");
     match run_cli() {
-        Ok(_) => debug!("Run completed successfully."),
-        Err(e) => error!("Error: {}", e),
+        Ok(_) => {
+            debug!("Run completed successfully.");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprint!("{}", render_error(&e));
+            exit_code(&e)
+        }
     }
 }
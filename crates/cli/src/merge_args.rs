@@ -0,0 +1,33 @@
+use klyv_core::prelude::*;
+use std::path::PathBuf;
+
+/// Arguments of `klyv merge`
+#[derive(Debug, clap::Args)]
+pub struct MergeArgs {
+    /// Directory of the module to merge, e.g. `src/shapes` for `src/shapes/mod.rs` or `src/shapes.rs`.
+    /// A `mod.rs` left alone in the directory becomes `src/shapes.rs`
+    dir: PathBuf,
+
+    /// Allow git dirty state
+    #[arg(long, default_value = "false")]
+    allow_dirty: bool,
+
+    /// Allow git staged state
+    #[arg(long, default_value = "false")]
+    allow_staged: bool,
+
+    /// Print the planned files and their contents without writing anything to disk
+    #[arg(long, default_value = "false")]
+    dry_run: bool,
+}
+
+impl From<MergeArgs> for MergeInput {
+    fn from(args: MergeArgs) -> Self {
+        MergeInput::builder()
+            .dir(args.dir)
+            .allow_git_dirty(args.allow_dirty)
+            .allow_git_staged(args.allow_staged)
+            .dry_run(args.dry_run)
+            .build()
+    }
+}
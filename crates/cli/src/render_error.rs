@@ -0,0 +1,59 @@
+use klyv_core::prelude::*;
+use std::error::Error as _;
+
+/// Renders `error` for the terminal like rustc does: the message, the code
+/// it points at, if any, and the chain of errors which caused it
+pub(crate) fn render_error(error: &Error) -> String {
+    let message = error.to_string();
+    let mut rendered = match error.location() {
+        Some((path, line, column)) => {
+            // The location is shown above the snippet instead
+            let prefix = format!("{}:{}:{}: ", path.display(), line, column);
+            let message = message.strip_prefix(&prefix).unwrap_or(&message);
+            format!("error: {}\n{}", message, render_snippet(path, line, column))
+        }
+        None => format!("error: {}\n", message),
+    };
+
+    let mut source = error.source();
+    while let Some(cause) = source {
+        rendered.push_str(&format!("caused by: {}\n", cause));
+        source = cause.source();
+    }
+    rendered
+}
+
+/// Renders a path which was skipped while scanning, like `render_error`
+pub(crate) fn render_scan_failure(failure: &ScanFailure) -> String {
+    match (failure.line(), failure.column()) {
+        (Some(line), Some(column)) => format!(
+            "warning: skipped, {}\n{}",
+            failure.message(),
+            render_snippet(failure.path(), *line, *column)
+        ),
+        _ => format!("warning: skipped {}\n", failure),
+    }
+}
+
+/// Renders the `line` of the file at `path` with a caret under `column`,
+/// both starting at 1. Only the location if the file can not be read.
+fn render_snippet(path: &Path, line: usize, column: usize) -> String {
+    let gutter = " ".repeat(line.to_string().len());
+    let location = format!("{gutter}--> {}:{}:{}\n", path.display(), line, column);
+    let Some(code) = fs::read_to_string(path).ok().and_then(|content| {
+        content
+            .lines()
+            .nth(line.saturating_sub(1))
+            .map(str::to_owned)
+    }) else {
+        // No code to show
+        return location;
+    };
+
+    let caret_offset: String = code
+        .chars()
+        .take(column.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    format!("{location}{gutter} |\n{line} | {code}\n{gutter} | {caret_offset}^\n")
+}
@@ -0,0 +1,6 @@
+/// The format of the summary printed by `--report`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// A `SplitReport` as JSON
+    Json,
+}
@@ -1,10 +1,19 @@
 #![cfg(test)]
 
-use std::{env, fs};
+use std::{env, fs, path::PathBuf, process::ExitCode};
 use tempfile::TempDir;
 use test_log::test;
 
-use crate::{Input, run};
+use crate::{
+    Input, ensure_split_applied,
+    exit_code::{GIT_DIRTY, IO_FAILURE, PARSE_FAILURE, ROLLED_BACK, exit_code},
+    render_error::render_error,
+    run,
+};
+use klyv_core::prelude::{
+    Error, IoAction, MergeInput, SplitPlan, SplitReport, ensure_git_status_clean, load_config,
+    merge,
+};
 
 #[test]
 fn test() {
@@ -22,7 +31,8 @@ fn test() {
         .allow_git_staged(true)
         .build();
 
-    let tree = run(input).unwrap();
+    let outcome = run(input).unwrap();
+    let tree = outcome.node();
 
     // Verify the tree contains expected items (quick smoke test)
     let debug = format!("{:#?}", tree);
@@ -49,7 +59,11 @@ fn test() {
     );
     // d2_a.rs is now split into separate files - test each part
     assert_generated_file_snapshot(&out_path, "d0_a/d1_b/magic.rs", "magic_type_file");
-    assert_generated_file_snapshot(&out_path, "d0_a/d1_b/ab_astruct_a.rs", "ab_astruct_a_file");
+    assert_generated_file_snapshot(
+        &out_path,
+        "d0_a/d1_b/ab_a_struct_a.rs",
+        "ab_a_struct_a_file",
+    );
     assert_generated_file_snapshot(&out_path, "d0_a/d1_b/functions.rs", "d2_a_mixed_content");
     assert_generated_file_snapshot(&out_path, "d0_a/d1_a/d2_b.rs", "d2_b_global_magic");
 
@@ -120,10 +134,12 @@ fn test_lib_rs_special_case_handling() {
         .allow_git_staged(true)
         .build();
 
-    let tree = run(input).unwrap();
+    let outcome = run(input).unwrap();
+    let tree = outcome.node();
 
-    // Verify the tree structure
-    let debug = format!("{:#?}", tree);
+    // Verify the tree structure, with the machine specific crate path redacted
+    let crate_dir = env::current_dir().unwrap().display().to_string();
+    let debug = format!("{:#?}", tree).replace(&crate_dir, "[CRATE_DIR]");
     insta::assert_snapshot!("lib_rs_tree_structure", debug);
 
     // Collect all generated files and their contents
@@ -370,3 +386,987 @@ fn test_nested_file_splitting() {
     let mod_content = fs::read_to_string(&mod_file).expect("Should be able to read mod.rs");
     insta::assert_snapshot!("nested_file_splitting_mod", mod_content);
 }
+
+#[test]
+fn test_dry_run_plans_without_writing() {
+    let mut source_path = env::current_dir().unwrap();
+    source_path.push("src/fixtures/fixt0");
+
+    // Use a temporary directory that will be cleaned up automatically
+    let temp_dir = TempDir::new().unwrap();
+    let out_path = temp_dir.path().to_path_buf();
+
+    let input = Input::builder()
+        .source(source_path.to_path_buf())
+        .maybe_out(Some(out_path.clone()))
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .dry_run(true)
+        .build();
+
+    let outcome = run(input).unwrap();
+    let plan = outcome.plan();
+
+    // Nothing may be written during a dry run
+    assert!(
+        collect_directory_structure(&out_path).is_empty(),
+        "dry run should not write any files"
+    );
+
+    // The plan should contain the same files a real run writes, all of them new
+    let planned_files: Vec<String> = plan
+        .files()
+        .keys()
+        .map(|path| {
+            path.strip_prefix(&out_path)
+                .unwrap()
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+    assert!(planned_files.contains(&"d0_a/d1_b/functions.rs".to_string()));
+    assert!(
+        plan.files()
+            .values()
+            .all(|file| *file.action() == klyv_core::prelude::FileAction::Create)
+    );
+
+    // Executing the plan afterwards produces exactly the planned files
+    plan.execute().unwrap();
+    let mut written_files = collect_directory_structure(&out_path);
+    let mut planned_files = planned_files;
+    written_files.sort();
+    planned_files.sort();
+    assert_eq!(written_files, planned_files);
+    assert_generated_file_snapshot(&out_path, "d0_a/d1_b/functions.rs", "d2_a_mixed_content");
+}
+
+#[test]
+fn test_regular_comments_are_preserved() {
+    let mut source_path = env::current_dir().unwrap();
+    source_path.push("src/fixtures/comments_test/shapes.rs");
+
+    // Use a temporary directory that will be cleaned up automatically
+    let temp_dir = TempDir::new().unwrap();
+    let out_path = temp_dir.path().to_path_buf();
+
+    let input = Input::builder()
+        .source(source_path.to_path_buf())
+        .maybe_out(Some(out_path.clone()))
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .build();
+
+    run(input).unwrap();
+
+    // Comments above, inside and trailing items must survive the split
+    assert_generated_file_snapshot(&out_path, "shape.rs", "comments_shape_file");
+    assert_generated_file_snapshot(&out_path, "functions.rs", "comments_functions_file");
+}
+
+/// Copies a fixture directory into a temporary directory, so that it can be
+/// split in place
+fn copy_fixture_to_temp_dir(fixture: &str) -> TempDir {
+    let mut source_path = env::current_dir().unwrap();
+    source_path.push("src/fixtures");
+    source_path.push(fixture);
+
+    let temp_dir = TempDir::new().unwrap();
+    for relative in collect_directory_structure(&source_path) {
+        let target = temp_dir.path().join(&relative);
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::copy(source_path.join(&relative), target).unwrap();
+    }
+    temp_dir
+}
+
+#[test]
+fn test_in_place_split_replaces_original_files() {
+    let temp_dir = copy_fixture_to_temp_dir("in_place_test");
+    let crate_path = temp_dir.path().to_path_buf();
+
+    let input = Input::builder()
+        .source(crate_path.clone())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .build();
+
+    run(input).unwrap();
+
+    // foo.rs keeps only its constant used by two items, inner.rs had nothing
+    // left and is deleted
+    assert_generated_file_snapshot(&crate_path, "foo.rs", "in_place_reduced_original_file");
+    assert!(!crate_path.join("nested/inner.rs").exists());
+
+    // Parent module files declare the new modules instead of deleted ones
+    assert_generated_file_snapshot(&crate_path, "lib.rs", "in_place_lib_file");
+    assert_generated_file_snapshot(&crate_path, "nested.rs", "in_place_nested_module_file");
+
+    let dir_structure = collect_directory_structure(&crate_path);
+    insta::assert_yaml_snapshot!("in_place_directory_structure", dir_structure);
+}
+
+#[test]
+fn test_prelude_is_imported_only_where_it_is_used() {
+    // The crate declares a prelude re-exporting everything, files which
+    // reference no item split out to another file don't import it
+    assert_split_introduces_no_diagnostics("in_place_test", false);
+}
+
+#[test]
+fn test_colliding_file_names_are_renamed() {
+    let temp_dir = copy_fixture_to_temp_dir("collision_test");
+    let crate_path = temp_dir.path().to_path_buf();
+
+    let input = Input::builder()
+        .source(crate_path.clone())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .build();
+
+    let outcome = run(input).unwrap();
+
+    // Both files define `enum Foo` and functions, foo.rs keeps its own name
+    let renames = outcome
+        .plan()
+        .renames()
+        .iter()
+        .map(|rename| format!("{} -> {}", rename.original_name(), rename.renamed()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        renames,
+        vec![
+            "foo.rs -> foo_0.rs",
+            "functions.rs -> functions_0.rs",
+            "functions.rs -> functions_1.rs",
+        ]
+    );
+
+    assert_generated_file_snapshot(&crate_path, "foo_0.rs", "collision_renamed_type_file");
+    assert_generated_file_snapshot(&crate_path, "mod.rs", "collision_mod_file");
+    let dir_structure = collect_directory_structure(&crate_path);
+    insta::assert_yaml_snapshot!("collision_directory_structure", dir_structure);
+}
+
+#[test]
+fn test_config_file_sets_folders_and_prelude() {
+    let temp_dir = copy_fixture_to_temp_dir("lib_rs_special");
+    let crate_path = temp_dir.path().to_path_buf();
+    fs::write(
+        crate_path.join("klyv.toml"),
+        "types-folder = \"models\"\nlogic-folder = \"ops\"\nfunctions-module = \"free\"\nprelude = false\n",
+    )
+    .unwrap();
+
+    let input = Input::builder()
+        .source(crate_path.join("lib.rs"))
+        .config(load_config().source(&crate_path).call().unwrap())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .build();
+
+    run(input).unwrap();
+
+    // No prelude module is generated, the folders are re-exported instead
+    assert_generated_file_snapshot(&crate_path, "lib.rs", "config_lib_file");
+    let user = fs::read_to_string(crate_path.join("models/user.rs")).unwrap();
+    assert!(!user.contains("prelude"));
+
+    let dir_structure = collect_directory_structure(&crate_path);
+    insta::assert_yaml_snapshot!("config_directory_structure", dir_structure);
+}
+
+#[test]
+fn test_split_keeps_item_visibility() {
+    let temp_dir = copy_fixture_to_temp_dir("visibility_test");
+    let crate_path = temp_dir.path().to_path_buf();
+
+    let input = Input::builder()
+        .source(crate_path.clone())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .build();
+
+    run(input).unwrap();
+
+    // Private items used by other split files are widened to the parent only
+    assert_generated_file_snapshot(&crate_path, "cache.rs", "visibility_widened_type_file");
+    assert_generated_file_snapshot(&crate_path, "circle.rs", "visibility_widened_members_file");
+
+    // The private `shapes` module was glob re-exported, `units` was a public
+    // module without a re-export and stays reachable as `units::*`
+    assert_generated_file_snapshot(&crate_path, "lib.rs", "visibility_lib_file");
+    assert_generated_file_snapshot(&crate_path, "units.rs", "visibility_facade_file");
+}
+
+/// Turns the fixture copied to `temp_dir` into a library package, so that it
+/// can be checked with cargo
+fn write_package_manifest(temp_dir: &TempDir) {
+    fs::write(
+        temp_dir.path().join("Cargo.toml"),
+        "[package]\nname = \"fixture\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\n[lib]\npath = \"lib.rs\"\n",
+    )
+    .unwrap();
+}
+
+/// Splits `fixture` in place as a package checked before and after the
+/// split, and asserts that the split introduced no errors nor warnings
+fn assert_split_introduces_no_diagnostics(fixture: &str, gather_impls: bool) {
+    let temp_dir = copy_fixture_to_temp_dir(fixture);
+    assert_split_of_dir_introduces_no_diagnostics(&temp_dir, gather_impls);
+}
+
+/// Like `assert_split_introduces_no_diagnostics`, for a fixture already
+/// copied to `temp_dir`
+fn assert_split_of_dir_introduces_no_diagnostics(temp_dir: &TempDir, gather_impls: bool) {
+    write_package_manifest(temp_dir);
+
+    let input = Input::builder()
+        .source(temp_dir.path().to_path_buf())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .gather_impls(gather_impls)
+        .verify(true)
+        .build();
+    assert_verified_split_introduces_no_diagnostics(input);
+}
+
+/// Runs the split of `input`, which must be verified, and asserts that it
+/// introduced no diagnostics
+fn assert_verified_split_introduces_no_diagnostics(input: Input) {
+    let outcome = run(input).unwrap();
+
+    let verification = outcome.verification().as_ref().unwrap();
+    assert!(!verification.rolled_back(), "{}", verification);
+    assert!(
+        verification.introduced_diagnostics().is_empty(),
+        "{}",
+        verification
+    );
+}
+
+#[test]
+fn test_items_used_outside_of_the_split_stay_re_exported() {
+    let crate_with_other_module = || {
+        let temp_dir = copy_fixture_to_temp_dir("visibility_test");
+        write_package_manifest(&temp_dir);
+        let lib_path = temp_dir.path().join("lib.rs");
+        let lib = fs::read_to_string(&lib_path)
+            .unwrap()
+            .replace("pub use shapes::*;\n", "");
+        fs::write(&lib_path, format!("{}pub mod other;\n", lib)).unwrap();
+        fs::write(
+            temp_dir.path().join("other.rs"),
+            "use crate::shapes::Circle;\n\npub fn describe_unit() -> String {\n    crate::shapes::describe(&Circle::new(1.0))\n}\n",
+        )
+        .unwrap();
+        temp_dir
+    };
+    let input = |source: PathBuf, exclude: Vec<String>| {
+        Input::builder()
+            .source(source)
+            .allow_git_dirty(true)
+            .allow_git_staged(true)
+            .exclude(exclude)
+            .verify(true)
+            .build()
+    };
+
+    // Splitting only `shapes.rs` still finds `describe` used by `other.rs`
+    // through the facade `shapes.rs` is kept as
+    let temp_dir = crate_with_other_module();
+    assert_verified_split_introduces_no_diagnostics(input(
+        temp_dir.path().join("shapes.rs"),
+        Vec::new(),
+    ));
+
+    // As does splitting the crate with `other.rs` excluded
+    let temp_dir = crate_with_other_module();
+    assert_verified_split_introduces_no_diagnostics(input(
+        temp_dir.path().to_path_buf(),
+        vec!["other.rs".to_owned()],
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extension_trait_imports_are_kept_where_their_methods_are_called() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        "mod shapes;\n\npub use shapes::*;\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("shapes.rs"),
+        "use std::fs::Permissions;\nuse std::os::unix::fs::PermissionsExt;\n\npub struct Circle {\n    pub permissions: Permissions,\n}\n\nimpl Circle {\n    pub fn new() -> Self {\n        Self {\n            permissions: Permissions::from_mode(0o644),\n        }\n    }\n}\n\npub fn mode(circle: &Circle) -> u32 {\n    circle.permissions.mode()\n}\n",
+    )
+    .unwrap();
+
+    // `PermissionsExt` is never named, both files call its methods
+    assert_split_of_dir_introduces_no_diagnostics(&temp_dir, false);
+    for file in ["circle.rs", "functions.rs"] {
+        let content = fs::read_to_string(temp_dir.path().join(file)).unwrap();
+        assert!(
+            content.contains("use std::os::unix::fs::PermissionsExt;"),
+            "{}",
+            content
+        );
+    }
+}
+
+#[test]
+fn test_modules_are_re_exported_as_far_as_they_are_reached() {
+    // `cache` and `functions_0` hold no public item, they are not re-exported
+    assert_split_introduces_no_diagnostics("visibility_test", false);
+
+    // Once another module uses `describe` through the crate root,
+    // `functions_0` is re-exported to the crate
+    let temp_dir = copy_fixture_to_temp_dir("visibility_test");
+    let lib_path = temp_dir.path().join("lib.rs");
+    let lib = fs::read_to_string(&lib_path).unwrap();
+    fs::write(&lib_path, format!("{}pub mod other;\n", lib)).unwrap();
+    fs::write(
+        temp_dir.path().join("other.rs"),
+        "use crate::{Circle, describe};\n\npub fn describe_unit() -> String {\n    describe(&Circle::new(1.0))\n}\n",
+    )
+    .unwrap();
+    assert_split_of_dir_introduces_no_diagnostics(&temp_dir, false);
+    let lib = fs::read_to_string(&lib_path).unwrap();
+    assert!(lib.contains("pub(crate) use functions_0::*;"), "{}", lib);
+    assert!(!lib.contains("use cache::*;"), "{}", lib);
+}
+
+#[test]
+fn test_verified_split_is_kept_when_it_compiles() {
+    let temp_dir = copy_fixture_to_temp_dir("visibility_test");
+    write_package_manifest(&temp_dir);
+    let crate_path = temp_dir.path().to_path_buf();
+
+    let input = Input::builder()
+        .source(crate_path.clone())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .verify(true)
+        .build();
+
+    let outcome = run(input).unwrap();
+
+    let verification = outcome.verification().as_ref().unwrap();
+    assert_eq!(verification.after().error_count(), 0);
+    assert!(!verification.rolled_back());
+    assert!(crate_path.join("circle.rs").exists());
+    assert!(!crate_path.join("shapes.rs").exists());
+}
+
+#[test]
+fn test_verified_split_is_rolled_back_when_it_introduces_errors() {
+    let temp_dir = copy_fixture_to_temp_dir("visibility_test");
+    write_package_manifest(&temp_dir);
+    let crate_path = temp_dir.path().to_path_buf();
+
+    // `shapes` is deleted by the split, breaking this path
+    let lib = fs::read_to_string(crate_path.join("lib.rs")).unwrap();
+    fs::write(crate_path.join("lib.rs"), format!("{}mod other;\n", lib)).unwrap();
+    fs::write(
+        crate_path.join("other.rs"),
+        "pub fn unit() -> crate::shapes::Circle {\n    crate::shapes::Circle::new(1.0)\n}\n",
+    )
+    .unwrap();
+    let before = collect_directory_structure(&crate_path);
+
+    let input = Input::builder()
+        .source(crate_path.clone())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .verify(true)
+        .build();
+
+    let outcome = run(input).unwrap();
+
+    let verification = outcome.verification().as_ref().unwrap();
+    assert!(verification.rolled_back());
+    let introduced = verification.introduced_diagnostics();
+    let other_errors = &introduced[&Some(PathBuf::from("other.rs"))];
+    assert_eq!(other_errors.len(), 2);
+    assert!(
+        other_errors
+            .iter()
+            .all(|diagnostic| diagnostic.code().as_deref() == Some("E0433"))
+    );
+
+    // Every written and deleted file is back, build output aside
+    let after: Vec<String> = collect_directory_structure(&crate_path)
+        .into_iter()
+        .filter(|path| !path.starts_with("target") && path != "Cargo.lock")
+        .collect();
+    assert_eq!(after, before);
+    assert_eq!(
+        fs::read_to_string(crate_path.join("lib.rs")).unwrap(),
+        format!("{}mod other;\n", lib)
+    );
+
+    // The run fails with an exit code of its own
+    let error = ensure_split_applied(&outcome).unwrap_err();
+    assert!(
+        matches!(error, Error::RolledBack { errors: 2, .. }),
+        "{:?}",
+        error
+    );
+    assert_eq!(exit_code(&error), ExitCode::from(ROLLED_BACK));
+}
+
+#[test]
+fn test_inline_modules_become_directories() {
+    let temp_dir = copy_fixture_to_temp_dir("inline_module_test");
+    let crate_path = temp_dir.path().to_path_buf();
+
+    let input = Input::builder()
+        .source(crate_path.clone())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .build();
+
+    run(input).unwrap();
+
+    // The inline module is declared with its attributes and visibility, the
+    // test module stays inline
+    assert_generated_file_snapshot(&crate_path, "shapes.rs", "inline_module_declaring_file");
+    assert_generated_file_snapshot(
+        &crate_path,
+        "shapes/geometry/mod.rs",
+        "inline_module_mod_file",
+    );
+    assert_generated_file_snapshot(
+        &crate_path,
+        "shapes/geometry/functions.rs",
+        "inline_module_functions_file",
+    );
+
+    // Items of the crate private `nested` are re-exported only as far as
+    // paths of the crate use them
+    assert_generated_file_snapshot(
+        &crate_path,
+        "shapes/geometry/nested/mod.rs",
+        "inline_module_nested_mod_file",
+    );
+
+    let dir_structure = collect_directory_structure(&crate_path);
+    insta::assert_yaml_snapshot!("inline_module_directory_structure", dir_structure);
+
+    assert_split_introduces_no_diagnostics("inline_module_test", false);
+}
+
+#[test]
+fn test_test_modules_follow_the_items_they_test() {
+    let temp_dir = copy_fixture_to_temp_dir("test_module_test");
+    let crate_path = temp_dir.path().to_path_buf();
+
+    let input = Input::builder()
+        .source(crate_path.clone())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .build();
+
+    run(input).unwrap();
+
+    // Tests of `Circle` move into its file, keeping private access, the
+    // field of `Square` one of them reads is widened for it
+    assert_generated_file_snapshot(&crate_path, "circle.rs", "test_module_moved_into_type_file");
+    assert_generated_file_snapshot(&crate_path, "square.rs", "test_module_widened_type_file");
+
+    // Tests of `Meter` and `Foot` are split up into the files of each, with
+    // the helper they share, without widening the private fields
+    assert_generated_file_snapshot(&crate_path, "meter.rs", "test_module_part_meter_file");
+    assert_generated_file_snapshot(&crate_path, "foot.rs", "test_module_part_foot_file");
+    assert_generated_file_snapshot(&crate_path, "lib.rs", "test_module_lib_file");
+    assert!(!crate_path.join("tests.rs").exists());
+
+    assert_split_introduces_no_diagnostics("test_module_test", false);
+}
+
+#[test]
+fn test_impl_blocks_follow_the_local_type_of_their_self_type() {
+    let temp_dir = copy_fixture_to_temp_dir("impl_target_test");
+    let crate_path = temp_dir.path().to_path_buf();
+
+    let input = Input::builder()
+        .source(crate_path.clone())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .build();
+
+    run(input).unwrap();
+
+    // Impls for `&Circle`, `Box<Circle>` and `self::Circle` move with `Circle`
+    assert_generated_file_snapshot(&crate_path, "circle.rs", "impl_target_reference_file");
+    assert_generated_file_snapshot(&crate_path, "wrapper.rs", "impl_target_wrapper_file");
+
+    // The blanket impl over `Vec<T>` moves with the trait
+    assert_generated_file_snapshot(&crate_path, "area.rs", "impl_target_trait_file");
+}
+
+#[test]
+fn test_gathered_impls_move_to_the_file_of_their_type() {
+    let temp_dir = copy_fixture_to_temp_dir("impl_gather_test");
+    let crate_path = temp_dir.path().to_path_buf();
+
+    let input = Input::builder()
+        .source(crate_path.clone())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .gather_impls(true)
+        .build();
+
+    run(input).unwrap();
+
+    // The impls written in `render.rs` join `Circle`, importing what they use
+    assert_generated_file_snapshot(&crate_path, "circle.rs", "impl_gather_type_file");
+
+    // Imports only the impls needed are removed from where they were written
+    assert_generated_file_snapshot(&crate_path, "render.rs", "impl_gather_original_file");
+}
+
+#[test]
+fn test_gathered_impls_add_no_warnings() {
+    // `outline` is widened for the gathered impl, which reaches it through
+    // the crate root, re-exporting it only to the crate
+    assert_split_introduces_no_diagnostics("impl_gather_test", true);
+}
+
+#[test]
+fn test_warnings_are_recorded_in_the_report() {
+    // A `#[macro_use]` module declared after macros no longer sees them
+    // once the macros move to the logic folder
+    let temp_dir = copy_fixture_to_temp_dir("macro_test");
+    let crate_path = temp_dir.path().to_path_buf();
+    let lib = fs::read_to_string(crate_path.join("lib.rs")).unwrap();
+    fs::write(
+        crate_path.join("lib.rs"),
+        format!("{}\npub fn unit() -> u32 {{\n    1\n}}\n", lib),
+    )
+    .unwrap();
+    fs::write(crate_path.join("klyv.toml"), "strategy = \"organized\"\n").unwrap();
+    let input = Input::builder()
+        .source(crate_path.join("lib.rs"))
+        .config(load_config().source(&crate_path).call().unwrap())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .dry_run(true)
+        .build();
+    let outcome = run(input).unwrap();
+    let warnings = SplitReport::new(&outcome).warnings().clone();
+    assert_eq!(warnings, *outcome.plan().warnings());
+    assert_eq!(
+        warnings,
+        vec![format!(
+            "Module shapes in {} is declared before the logic folder now, it can no longer use define_id!, impl_describe!",
+            crate_path.join("lib.rs").display()
+        )]
+    );
+
+    // Without a crate root, the `super::` import of the `Render` impl can
+    // not be made absolute for it to move
+    let temp_dir = copy_fixture_to_temp_dir("impl_gather_test");
+    let crate_path = temp_dir.path().to_path_buf();
+    fs::remove_file(crate_path.join("lib.rs")).unwrap();
+    let input = Input::builder()
+        .source(crate_path.clone())
+        .gather_impls(true)
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .dry_run(true)
+        .build();
+    let outcome = run(input).unwrap();
+    let warnings = SplitReport::new(&outcome).warnings().clone();
+    assert_eq!(
+        warnings,
+        vec![format!(
+            "Keeping an impl block in {}, its imports can not be written in {}",
+            crate_path.join("render.rs").display(),
+            crate_path.join("shapes.rs").display()
+        )]
+    );
+}
+
+#[test]
+fn test_trait_impls_are_placed_by_policy() {
+    let temp_dir = copy_fixture_to_temp_dir("trait_impl_test");
+    let crate_path = temp_dir.path().to_path_buf();
+    fs::write(
+        crate_path.join("klyv.toml"),
+        "[trait-impls]\ntraits = { Display = \"dedicated\", Shape = \"with-trait\" }\n",
+    )
+    .unwrap();
+
+    let input = Input::builder()
+        .source(crate_path.clone())
+        .config(load_config().source(&crate_path).call().unwrap())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .build();
+
+    run(input).unwrap();
+
+    // `Shape` is defined in the same file, its impls go with it
+    assert_generated_file_snapshot(&crate_path, "shape.rs", "trait_impl_with_trait_file");
+
+    // `From` impls stay with their type by default
+    assert_generated_file_snapshot(&crate_path, "square.rs", "trait_impl_with_type_file");
+
+    // `Display` impls get files of their own, declared in `impls/mod.rs`
+    assert_generated_file_snapshot(
+        &crate_path,
+        "impls/display_for_circle.rs",
+        "trait_impl_dedicated_file",
+    );
+    assert_generated_file_snapshot(&crate_path, "impls/mod.rs", "trait_impl_impls_mod_file");
+    assert_generated_file_snapshot(&crate_path, "lib.rs", "trait_impl_lib_file");
+}
+
+/// Splits the `const_test` fixture with the constant placement `placement`
+fn split_constants(placement: &str) -> TempDir {
+    let temp_dir = copy_fixture_to_temp_dir("const_test");
+    let crate_path = temp_dir.path().to_path_buf();
+    fs::write(
+        crate_path.join("klyv.toml"),
+        format!("constants = \"{}\"\n", placement),
+    )
+    .unwrap();
+
+    let input = Input::builder()
+        .source(crate_path.clone())
+        .config(load_config().source(&crate_path).call().unwrap())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .build();
+
+    run(input).unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_constants_are_placed_by_policy() {
+    // By default a constant goes with its only user and shared ones stay
+    let with_user = split_constants("with-user");
+    let crate_path = with_user.path();
+    assert_generated_file_snapshot(crate_path, "circle.rs", "const_with_user_type_file");
+    assert_generated_file_snapshot(crate_path, "functions.rs", "const_with_user_functions_file");
+    assert_generated_file_snapshot(crate_path, "shapes.rs", "const_with_user_original_file");
+
+    // All in one file, keeping their docs and attributes
+    let shared = split_constants("shared");
+    assert_generated_file_snapshot(shared.path(), "constants.rs", "const_shared_file");
+
+    // One file each, named after the constant
+    let dedicated = split_constants("dedicated");
+    insta::assert_yaml_snapshot!(
+        "const_dedicated_directory_structure",
+        collect_directory_structure(dedicated.path())
+    );
+}
+
+#[test]
+fn test_kept_file_without_public_items_is_no_longer_re_exported() {
+    // Only the shared `SIDES` stays in `shapes.rs`, which the crate root
+    // re-exported publicly
+    assert_split_introduces_no_diagnostics("const_test", false);
+}
+
+#[test]
+fn test_foreign_blocks_go_next_to_their_wrappers() {
+    let temp_dir = copy_fixture_to_temp_dir("ffi_test");
+    let crate_path = temp_dir.path().to_path_buf();
+
+    let input = Input::builder()
+        .source(crate_path.clone())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .build();
+
+    run(input).unwrap();
+
+    // A block with a single wrapping type moves along with it
+    assert_generated_file_snapshot(&crate_path, "distance.rs", "ffi_wrapper_type_file");
+    // A block used by several files gets its own, with widened visibility
+    assert_generated_file_snapshot(&crate_path, "ffi.rs", "ffi_shared_file");
+    // The trait alias follows the trait it aliases
+    assert_generated_file_snapshot(&crate_path, "measure.rs", "ffi_trait_alias_file");
+    // `extern crate` stays at the crate root, above the declarations
+    assert_generated_file_snapshot(&crate_path, "lib.rs", "ffi_crate_root_file");
+}
+
+#[test]
+fn test_macro_definitions_and_invocations_are_placed_apart() {
+    let temp_dir = copy_fixture_to_temp_dir("macro_test");
+    let crate_path = temp_dir.path().to_path_buf();
+
+    let input = Input::builder()
+        .source(crate_path.join("shapes.rs"))
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .build();
+
+    run(input).unwrap();
+
+    // Macro modules are declared first, where the original module was
+    assert_generated_file_snapshot(&crate_path, "lib.rs", "macro_crate_root_file");
+    // An invocation declaring a type goes with its impls
+    assert_generated_file_snapshot(&crate_path, "shape_id.rs", "macro_declared_type_file");
+    // An invocation naming a type follows it
+    assert_generated_file_snapshot(&crate_path, "circle.rs", "macro_invocation_type_file");
+    // An invocation declaring items goes with their only user
+    assert_generated_file_snapshot(&crate_path, "functions.rs", "macro_item_user_file");
+    // Definitions keep their attributes, private macros stay private
+    assert_generated_file_snapshot(&crate_path, "square.rs", "macro_private_definition_file");
+    assert_generated_file_snapshot(
+        &crate_path,
+        "new_circle.rs",
+        "macro_exported_definition_file",
+    );
+}
+
+#[test]
+fn test_parse_errors_are_rendered_with_the_code_they_point_at() {
+    let temp_dir = TempDir::new().unwrap();
+    let crate_path = temp_dir.path().to_path_buf();
+    fs::write(crate_path.join("good.rs"), "pub struct Good;\n").unwrap();
+    fs::write(
+        crate_path.join("broken.rs"),
+        "pub struct Broken;\n\nfn broken(a: u8 {}\n",
+    )
+    .unwrap();
+
+    let input = Input::builder()
+        .source(crate_path.clone())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .fail_fast(true)
+        .build();
+
+    let error = run(input).unwrap_err();
+    assert!(matches!(error, Error::Parse { line: 3, .. }), "{:?}", error);
+
+    let rendered = render_error(&error).replace(&crate_path.display().to_string(), "<crate>");
+    insta::assert_snapshot!("parse_error_rendering", rendered);
+}
+
+#[test]
+fn test_json_report_lists_files_and_moved_items() {
+    let temp_dir = TempDir::new().unwrap();
+    let crate_path = temp_dir.path().to_path_buf();
+    fs::write(crate_path.join("lib.rs"), "mod shapes;\n").unwrap();
+    fs::write(
+        crate_path.join("shapes.rs"),
+        "pub struct Circle;\n\npub enum Kind {\n    Round,\n}\n\npub fn area() -> u8 {\n    2\n}\n",
+    )
+    .unwrap();
+
+    let input = Input::builder()
+        .source(crate_path.clone())
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .build();
+
+    let outcome = run(input).unwrap();
+    let report = SplitReport::new(&outcome)
+        .to_json()
+        .replace(&crate_path.display().to_string(), "<crate>");
+    insta::assert_snapshot!("json_report", report);
+}
+
+#[test]
+fn test_exit_codes_tell_failures_apart() {
+    let git_dirty = Error::GitDirty {
+        staged: true,
+        unstaged: false,
+        paths: vec![PathBuf::from("src/lib.rs")],
+    };
+    let io = Error::io(
+        IoAction::Write,
+        "src/lib.rs",
+        std::io::Error::from(std::io::ErrorKind::PermissionDenied),
+    );
+
+    assert_eq!(exit_code(&git_dirty), ExitCode::from(GIT_DIRTY));
+    assert_eq!(
+        exit_code(&Error::SkippedPaths { count: 1 }),
+        ExitCode::from(PARSE_FAILURE)
+    );
+    assert_eq!(exit_code(&io), ExitCode::from(IO_FAILURE));
+}
+
+/// Runs `git` with `args` in `dir`, failing the test if it fails
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args(["-c", "user.name=klyv", "-c", "user.email=klyv@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+#[test]
+fn test_git_check_only_looks_at_the_files_to_touch() {
+    let temp_dir = TempDir::new().unwrap();
+    let repo = temp_dir.path().to_path_buf();
+    let crate_path = repo.join("src");
+    fs::create_dir(&crate_path).unwrap();
+    fs::write(crate_path.join("lib.rs"), "mod shapes;\n").unwrap();
+    fs::write(
+        crate_path.join("shapes.rs"),
+        "pub struct Circle;\n\npub struct Square;\n",
+    )
+    .unwrap();
+    fs::write(repo.join("notes.txt"), "before\n").unwrap();
+    git(&repo, &["init", "--quiet"]);
+    git(&repo, &["add", "--all"]);
+    git(&repo, &["commit", "--quiet", "--message", "initial"]);
+
+    let input = || {
+        Input::builder()
+            .source(crate_path.clone())
+            .allow_git_dirty(false)
+            .allow_git_staged(false)
+            .build()
+    };
+
+    // Only the change to a file the split touches makes it fail
+    fs::write(repo.join("notes.txt"), "after\n").unwrap();
+    fs::write(
+        crate_path.join("shapes.rs"),
+        "pub struct Circle;\n\npub struct Square;\n\npub struct Triangle;\n",
+    )
+    .unwrap();
+    let error = run(input()).unwrap_err();
+    let Error::GitDirty {
+        staged,
+        unstaged,
+        paths,
+    } = error
+    else {
+        panic!("Expected a dirty repository, got {:?}", error);
+    };
+    assert!(!staged && unstaged);
+    assert_eq!(paths, vec![PathBuf::from("src/shapes.rs")]);
+    assert!(crate_path.join("shapes.rs").exists());
+
+    git(
+        &repo,
+        &["commit", "--quiet", "--all", "--message", "triangle"],
+    );
+    fs::write(repo.join("notes.txt"), "dirty again\n").unwrap();
+    run(input()).unwrap();
+    assert!(crate_path.join("triangle.rs").exists());
+}
+
+#[test]
+fn test_git_check_groups_the_files_by_their_repository() {
+    let temp_dir = TempDir::new().unwrap();
+    let repo = temp_dir.path().to_path_buf();
+    fs::write(repo.join("lib.rs"), "pub struct Circle;\n").unwrap();
+    git(&repo, &["init", "--quiet"]);
+    git(&repo, &["add", "--all"]);
+    git(&repo, &["commit", "--quiet", "--message", "initial"]);
+    fs::write(repo.join("lib.rs"), "pub struct Square;\n").unwrap();
+    let outside = TempDir::new().unwrap();
+
+    // Files in directories which do not exist yet, in the repository or
+    // outside of any, are checked or skipped without making git fail
+    let mut plan = SplitPlan::default();
+    plan.add_file(repo.join("lib.rs"), "");
+    plan.add_file(repo.join("missing").join("out").join("types.rs"), "");
+    plan.add_file(outside.path().join("out").join("types.rs"), "");
+    let error = ensure_git_status_clean()
+        .plan(&plan)
+        .allow_git_staged(false)
+        .allow_git_dirty(false)
+        .call()
+        .unwrap_err();
+    let Error::GitDirty {
+        staged,
+        unstaged,
+        paths,
+    } = error
+    else {
+        panic!("Expected a dirty repository, got {:?}", error);
+    };
+    assert!(!staged && unstaged);
+    assert_eq!(paths, vec![PathBuf::from("lib.rs")]);
+}
+
+#[test]
+fn test_git_check_allows_an_out_dir_outside_of_the_repository() {
+    let temp_dir = TempDir::new().unwrap();
+    let repo = temp_dir.path().to_path_buf();
+    fs::write(repo.join("lib.rs"), "mod shapes;\n").unwrap();
+    fs::write(
+        repo.join("shapes.rs"),
+        "pub struct Circle;\n\npub struct Square;\n",
+    )
+    .unwrap();
+    git(&repo, &["init", "--quiet"]);
+    git(&repo, &["add", "--all"]);
+    git(&repo, &["commit", "--quiet", "--message", "initial"]);
+    let outside = TempDir::new().unwrap();
+    let out = outside.path().join("out");
+
+    let input = Input::builder()
+        .source(repo.clone())
+        .out(out.clone())
+        .allow_git_dirty(false)
+        .allow_git_staged(false)
+        .build();
+    run(input).unwrap();
+    assert!(out.join("circle.rs").exists());
+}
+
+#[test]
+fn test_merge_turns_a_mod_rs_left_alone_into_a_file_beside_its_dir() {
+    let temp_dir = copy_fixture_to_temp_dir("merge_test");
+    let crate_path = temp_dir.path().to_path_buf();
+    fs::rename(
+        crate_path.join("shapes.rs"),
+        crate_path.join("shapes").join("mod.rs"),
+    )
+    .unwrap();
+
+    let input = MergeInput::builder()
+        .dir(crate_path.join("shapes"))
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .build();
+
+    merge().input(input).call().unwrap();
+
+    // `mod shapes;` in `lib.rs` finds `shapes.rs` as it found `shapes/mod.rs`
+    assert_generated_file_snapshot(&crate_path, "shapes.rs", "merged_module_file");
+    assert_eq!(
+        collect_directory_structure(&crate_path),
+        vec!["lib.rs".to_owned(), "shapes.rs".to_owned()]
+    );
+}
+
+#[test]
+fn test_merge_joins_split_files_into_their_module_file() {
+    let temp_dir = copy_fixture_to_temp_dir("merge_test");
+    let crate_path = temp_dir.path().to_path_buf();
+
+    let input = MergeInput::builder()
+        .dir(crate_path.join("shapes"))
+        .allow_git_dirty(true)
+        .allow_git_staged(true)
+        .build();
+
+    merge().input(input).call().unwrap();
+
+    // Types come before impls, `pub(super)` becomes private and the imports
+    // of the merged files are deduplicated, `use crate::Circle;` is dropped
+    assert_generated_file_snapshot(&crate_path, "shapes.rs", "merged_module_file");
+    assert!(!crate_path.join("shapes").exists());
+    assert_eq!(
+        collect_directory_structure(&crate_path),
+        vec!["lib.rs".to_owned(), "shapes.rs".to_owned()]
+    );
+}
@@ -16,7 +16,7 @@ pub mod prelude {
         path::{Path, PathBuf},
     };
     pub use syn::{
-        ItemEnum, ItemFn as ItemFunction, ItemImpl, ItemMacro, ItemStruct, ItemTrait, ItemType,
-        ItemUnion, ItemUse,
+        ItemConst, ItemEnum, ItemExternCrate, ItemFn as ItemFunction, ItemForeignMod, ItemImpl,
+        ItemMacro, ItemStatic, ItemStruct, ItemTrait, ItemTraitAlias, ItemType, ItemUnion, ItemUse,
     };
 }
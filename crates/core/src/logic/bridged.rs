@@ -5,10 +5,12 @@ use bon::builder;
 
 #[builder]
 pub fn read_dir(path: impl AsRef<Path>) -> Result<ReadDir> {
-    fs::read_dir(path).map_err(Error::from)
+    let path = path.as_ref();
+    fs::read_dir(path).map_err(|e| Error::io(IoAction::ReadDirectory, path, e))
 }
 
 #[builder]
 pub fn read_to_string(path: impl AsRef<Path>) -> Result<String> {
-    fs::read_to_string(path).map_err(Error::from)
+    let path = path.as_ref();
+    fs::read_to_string(path).map_err(|e| Error::io(IoAction::Read, path, e))
 }
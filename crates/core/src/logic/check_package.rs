@@ -0,0 +1,43 @@
+use crate::prelude::*;
+use std::process::Command;
+
+/// Returns the directory of the package `path` belongs to, the closest
+/// directory in `path` or its ancestors containing a `Cargo.toml`
+pub fn find_package_dir(path: &Path) -> Option<PathBuf> {
+    let path = fs::canonicalize(path).ok()?;
+    path.ancestors()
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Runs `cargo check` on the package in `package_dir` with the local
+/// toolchain, without accessing the network, and collects its diagnostics.
+///
+/// A package which fails to compile is not an error, its errors are part of
+/// the report. Only failing to run cargo at all is.
+#[bon::builder]
+pub fn check_package(package_dir: &Path) -> Result<CompileReport> {
+    debug!("Running cargo check in {}", package_dir.display());
+    let output = Command::new("cargo")
+        .args([
+            "check",
+            "--all-targets",
+            "--offline",
+            "--message-format=short",
+        ])
+        .current_dir(package_dir)
+        .output()
+        .map_err(|source| Error::Command {
+            command: "cargo check".to_owned(),
+            source,
+        })?;
+
+    // Diagnostics are printed to stderr, next to cargo's progress
+    let report = CompileReport::parse(&String::from_utf8_lossy(&output.stderr));
+    debug!(
+        "cargo check found {} error(s) and {} diagnostic(s) in total",
+        report.error_count(),
+        report.diagnostics().len()
+    );
+    Ok(report)
+}
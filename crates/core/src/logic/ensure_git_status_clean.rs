@@ -0,0 +1,185 @@
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::process::Command;
+
+/// Fails if any file `plan` touches has changes in git which are not
+/// allowed, so that executing the plan can not lose uncommitted work.
+///
+/// The planned files are grouped by the git repository containing them, or
+/// their nearest existing ancestor, and `git status` only looks at those in
+/// each repository. Changes to other files are fine, as are planned files
+/// outside of any repository, like in a new `--out` directory. Dirty is a
+/// superset of staged, so allowing dirty files also allows staged ones.
+/// Without git installed, there is nothing to check.
+#[bon::builder]
+pub fn ensure_git_status_clean(
+    plan: &SplitPlan,
+    allow_git_staged: bool,
+    allow_git_dirty: bool,
+) -> Result<()> {
+    if allow_git_dirty {
+        // Every change is allowed
+        return Ok(());
+    }
+
+    // The planned paths relative to the root of the repository containing
+    // them, with the repository of every existing directory looked up once
+    let mut repositories: IndexMap<PathBuf, Vec<PathBuf>> = IndexMap::new();
+    let mut roots: HashMap<PathBuf, Option<PathBuf>> = HashMap::new();
+    for path in plan.files().keys() {
+        let path = std::path::absolute(path).map_err(|e| Error::io(IoAction::Read, path, e))?;
+        let Some(existing) = path.ancestors().skip(1).find(|ancestor| ancestor.is_dir()) else {
+            // Nothing of it exists, so there is nothing to lose
+            continue;
+        };
+        let missing = path.strip_prefix(existing).unwrap_or(&path);
+        let existing = existing
+            .canonicalize()
+            .map_err(|e| Error::io(IoAction::Read, existing, e))?;
+
+        let root = match roots.get(&existing) {
+            Some(root) => root.clone(),
+            None => {
+                let root = match repository_root(&existing) {
+                    Ok(root) => root,
+                    Err(source) if source.kind() == std::io::ErrorKind::NotFound => {
+                        // Git is not available, proceed without checking
+                        warn!("Git is not available, skipping repository status check");
+                        return Ok(());
+                    }
+                    Err(source) => {
+                        return Err(Error::Command {
+                            command: "git rev-parse".to_owned(),
+                            source,
+                        });
+                    }
+                };
+                roots.insert(existing.clone(), root.clone());
+                root
+            }
+        };
+        let Some(root) = root else {
+            // Outside of a git repository, that's fine
+            continue;
+        };
+
+        let path = existing.join(missing);
+        let relative = path
+            .strip_prefix(&root)
+            .map(Path::to_path_buf)
+            .unwrap_or(path);
+        repositories.entry(root).or_default().push(relative);
+    }
+
+    let mut changes = Vec::new();
+    for (root, paths) in &repositories {
+        changes.extend(git_status(root, paths)?);
+    }
+
+    // Fail on the changes which are not allowed
+    let mut staged = false;
+    let mut unstaged = false;
+    let mut changed_paths = Vec::new();
+    for (index_status, worktree_status, path) in changes {
+        // Staged changes have an index status other than ' ' or '?', unstaged
+        // ones a worktree status other than ' '
+        let is_staged = index_status != ' ' && index_status != '?' && !allow_git_staged;
+        let is_unstaged = worktree_status != ' ' && !allow_git_dirty;
+        if is_staged || is_unstaged {
+            staged |= is_staged;
+            unstaged |= is_unstaged;
+            changed_paths.push(path);
+        }
+    }
+
+    if staged || unstaged {
+        return Err(Error::GitDirty {
+            staged,
+            unstaged,
+            paths: changed_paths,
+        });
+    }
+
+    Ok(())
+}
+
+/// The root directory of the git repository containing `dir`, `None` if it
+/// is not in one. Fails if git can not be run.
+fn repository_root(dir: &Path) -> std::io::Result<Option<PathBuf>> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .current_dir(dir)
+        .output()?;
+    if !output.status.success() {
+        // Not in a git repository
+        return Ok(None);
+    }
+
+    let root = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    Ok(Some(root.canonicalize().unwrap_or(root)))
+}
+
+/// Runs `git status` in the repository at `root` for `paths`, relative to
+/// it, see [`git_changes`]
+fn git_status(root: &Path, paths: &[PathBuf]) -> Result<Vec<(char, char, PathBuf)>> {
+    let output = Command::new("git")
+        .args(["status", "--porcelain", "-z", "--"])
+        .args(paths)
+        .current_dir(root)
+        .output()
+        .map_err(|source| Error::Command {
+            command: "git status".to_owned(),
+            source,
+        })?;
+
+    if !output.status.success() {
+        return Err(Error::Command {
+            command: "git status".to_owned(),
+            source: std::io::Error::other(String::from_utf8_lossy(&output.stderr).trim()),
+        });
+    }
+    Ok(git_changes(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parses the output of `git status --porcelain -z` into the index status,
+/// the worktree status and the path, relative to the repository root, of
+/// every changed file
+fn git_changes(status: &str) -> Vec<(char, char, PathBuf)> {
+    let mut changes = Vec::new();
+    let mut entries = status.split('\0').filter(|entry| !entry.is_empty());
+    while let Some(entry) = entries.next() {
+        let mut chars = entry.chars();
+        let (Some(index_status), Some(worktree_status)) = (chars.next(), chars.next()) else {
+            // Not a status entry
+            continue;
+        };
+        if matches!(index_status, 'R' | 'C') {
+            // Renames and copies are followed by the path they came from
+            entries.next();
+        }
+        changes.push((
+            index_status,
+            worktree_status,
+            PathBuf::from(entry.get(3..).unwrap_or_default()),
+        ));
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_changes_are_parsed_from_porcelain_output() {
+        let changes = git_changes("M  src/lib.rs\0R  src/new.rs\0src/old.rs\0?? src/foo.rs\0");
+        assert_eq!(
+            changes,
+            vec![
+                ('M', ' ', PathBuf::from("src/lib.rs")),
+                ('R', ' ', PathBuf::from("src/new.rs")),
+                ('?', '?', PathBuf::from("src/foo.rs")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use crate::prelude::*;
+use quote::ToTokens;
+
+/// Moves every impl block written in another file than the type it is for
+/// into the file defining the type, wherever it is in the tree, see
+/// [`TypeIndex::resolve`].
+///
+/// The moved impl takes the imports it needs along, with relative paths made
+/// absolute, and imports the items of its original file it uses, which are
+/// widened to `pub(crate)`. Imports only the impl needed are removed from the
+/// original file.
+///
+/// Impl blocks whose imports can not be written in the file of their type
+/// stay where they are, with a warning pushed to `warnings`.
+///
+/// Returns `node` with the edited files re-parsed from their new text, which
+/// is split as usual afterwards.
+#[bon::builder]
+pub fn gather_impls(
+    mut node: FileSystemNode,
+    warnings: &mut Vec<String>,
+) -> Result<FileSystemNode> {
+    let index = TypeIndex::new(&node);
+    let mut edits: IndexMap<PathBuf, TextEdits> = IndexMap::new();
+    let mut gathered: IndexMap<PathBuf, (Vec<ItemUse>, Vec<String>)> = IndexMap::new();
+
+    for file in node.rust_file_contents() {
+        let mut moved = Vec::new();
+        let mut widened = Vec::new();
+
+        for (item, target_path) in file.foreign_impls(&index) {
+            let Some(target) = node.find_rust_file(&target_path) else {
+                // Indexed files are part of the tree
+                continue;
+            };
+            let Some((imports, used_items)) = file.moved_impl_imports(item, target, &index) else {
+                warnings.push(format!(
+                    "Keeping an impl block in {}, its imports can not be written in {}",
+                    file.path().display(),
+                    target_path.display()
+                ));
+                continue;
+            };
+
+            info!(
+                "Moving an impl block from {} to {}",
+                file.path().display(),
+                target_path.display()
+            );
+            let (target_imports, impls) = gathered.entry(target_path).or_default();
+            for import in imports {
+                let tokens = import.to_token_stream().to_string();
+                if !target_imports
+                    .iter()
+                    .any(|existing| existing.to_token_stream().to_string() == tokens)
+                {
+                    target_imports.push(import);
+                }
+            }
+            impls.push(file.moved_impl_text(item));
+            moved.push(item);
+            widened.extend(used_items);
+        }
+
+        if moved.is_empty() {
+            // Every impl is where its type is
+            continue;
+        }
+        edits
+            .entry(file.path().clone())
+            .or_default()
+            .extend(file.moved_impls_removal(&moved, &widened));
+    }
+
+    for (target_path, (imports, impls)) in &gathered {
+        let Some(target) = node.find_rust_file(target_path) else {
+            // Indexed files are part of the tree
+            continue;
+        };
+        edits
+            .entry(target_path.clone())
+            .or_default()
+            .extend(target.gathered_impls_insertion(imports, impls));
+    }
+
+    for (path, edits) in edits {
+        let Some(file) = node.find_rust_file(&path) else {
+            // Edited files are part of the tree
+            continue;
+        };
+        let source = edits.apply_to(file.content().source());
+        let moved_modules = file.content().moved_modules().clone();
+        node.replace_rust_file(RustFileContent::parsed(path, source, moved_modules)?);
+    }
+    Ok(node)
+}
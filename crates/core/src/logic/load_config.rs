@@ -0,0 +1,115 @@
+use crate::prelude::*;
+
+/// Loads the configuration used for splitting `source`.
+///
+/// Starting at `source` (or its directory if it is a file), every ancestor
+/// directory is searched for a `klyv.toml` file or a `Cargo.toml` containing
+/// a `[package.metadata.klyv]` or `[workspace.metadata.klyv]` table, the
+/// closest one wins. The search stops at the workspace root, i.e. the first
+/// `Cargo.toml` with a `[workspace]` table. If no configuration is found the
+/// default configuration is returned.
+#[bon::builder]
+pub fn load_config(source: impl AsRef<Path>) -> Result<Config> {
+    let source = source.as_ref();
+    let start = if source.is_file() {
+        source.parent().unwrap_or(Path::new("."))
+    } else {
+        source
+    };
+
+    for dir in start.ancestors() {
+        let config_file = dir.join(Config::FILE_NAME);
+        if config_file.is_file() {
+            debug!("Loading config from {}", config_file.display());
+            let content = read_to_string().path(&config_file).call()?;
+            return Config::from_toml(&content).map_err(|source| Error::Config {
+                path: config_file,
+                source,
+            });
+        }
+
+        let manifest = dir.join("Cargo.toml");
+        if !manifest.is_file() {
+            // Not a package or workspace root, keep searching
+            continue;
+        }
+
+        let content = read_to_string().path(&manifest).call()?;
+        let manifest_table: toml::Table =
+            toml::from_str(&content).map_err(|source| Error::Config {
+                path: manifest.clone(),
+                source,
+            })?;
+
+        if let Some(table) = klyv_metadata(&manifest_table) {
+            debug!("Loading config from metadata in {}", manifest.display());
+            return table.clone().try_into().map_err(|source| Error::Config {
+                path: manifest,
+                source,
+            });
+        }
+
+        if manifest_table.contains_key("workspace") {
+            // Workspace root reached without finding a config
+            break;
+        }
+    }
+
+    debug!("No config found, using defaults");
+    Ok(Config::default())
+}
+
+/// Returns the `klyv` table in the package or workspace metadata of a manifest
+fn klyv_metadata(manifest: &toml::Table) -> Option<&toml::Value> {
+    ["package", "workspace"]
+        .into_iter()
+        .find_map(|section| manifest.get(section)?.get("metadata")?.get(BINARY_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_closest_config_wins() {
+        let dir = tempdir().unwrap();
+        let package = dir.path().join("crates/foo");
+        fs::create_dir_all(package.join("src")).unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/foo\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(Config::FILE_NAME),
+            "types-folder = \"models\"\n",
+        )
+        .unwrap();
+        fs::write(
+            package.join("Cargo.toml"),
+            "[package]\nname = \"foo\"\n\n[package.metadata.klyv]\nlogic-folder = \"ops\"\n",
+        )
+        .unwrap();
+
+        let config = load_config().source(package.join("src")).call().unwrap();
+        assert_eq!(config.logic_folder(), "ops");
+        assert_eq!(config.types_folder(), Config::DEFAULT_TYPES_FOLDER);
+
+        fs::write(package.join("Cargo.toml"), "[package]\nname = \"foo\"\n").unwrap();
+        let config = load_config().source(package.join("src")).call().unwrap();
+        assert_eq!(config.types_folder(), "models");
+    }
+
+    #[test]
+    fn test_search_stops_at_workspace_root() {
+        let dir = tempdir().unwrap();
+        let workspace = dir.path().join("workspace");
+        fs::create_dir_all(workspace.join("src")).unwrap();
+        fs::write(dir.path().join(Config::FILE_NAME), "prelude = false\n").unwrap();
+        fs::write(workspace.join("Cargo.toml"), "[workspace]\n").unwrap();
+
+        let config = load_config().source(workspace.join("src")).call().unwrap();
+        assert_eq!(config, Config::default());
+    }
+}
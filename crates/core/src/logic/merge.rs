@@ -0,0 +1,180 @@
+use crate::prelude::*;
+
+/// Input for merging the files of a module directory back into its module
+/// file, the inverse of splitting
+#[derive(Clone, Debug, Builder, Getters)]
+pub struct MergeInput {
+    /// The directory of the module, e.g. `src/shapes` for `src/shapes/mod.rs`
+    /// or `src/shapes.rs`, or `src` for `src/lib.rs`
+    #[getset(get = "pub")]
+    dir: PathBuf,
+    #[getset(get = "pub")]
+    allow_git_staged: bool,
+    #[getset(get = "pub")]
+    allow_git_dirty: bool,
+    /// If true, the merge is only planned and nothing is written to disk
+    #[builder(default)]
+    #[getset(get = "pub")]
+    dry_run: bool,
+}
+
+/// Merges the child modules of the module in `input.dir` into its module
+/// file and removes their files, see [`plan_merge`]
+#[bon::builder]
+pub fn merge(input: MergeInput) -> Result<SplitPlan> {
+    let plan = plan_merge().dir(input.dir()).call()?;
+    if *input.dry_run() {
+        info!("Dry run, not writing {} planned file(s)", plan.len());
+        return Ok(plan);
+    }
+
+    ensure_git_status_clean()
+        .plan(&plan)
+        .allow_git_staged(*input.allow_git_staged())
+        .allow_git_dirty(*input.allow_git_dirty())
+        .call()?;
+
+    plan.execute()?;
+    remove_dir_if_empty(input.dir())?;
+    Ok(plan)
+}
+
+/// Plans merging the child modules of the module in `dir` into its module
+/// file: every `mod child;` with a glob re-export `pub use child::*;`
+/// whose file `dir/child.rs` has no child modules of its own.
+///
+/// The module file gets the deduplicated use statements of all files, its
+/// own items and then the items of the children with types before impls,
+/// the child files are planned for deletion. Children with inner attributes,
+/// like `#![allow(..)]`, are left alone since these apply to the whole file.
+///
+/// A `dir/mod.rs` left alone in its directory becomes the `dir.rs` next to
+/// it, see [`merged_module_file`].
+#[bon::builder]
+pub fn plan_merge(dir: &Path) -> Result<SplitPlan> {
+    let module_file = module_file(dir)?;
+    let source =
+        fs::read_to_string(&module_file).map_err(|e| Error::io(IoAction::Read, &module_file, e))?;
+    let module = RustFileContent::parsed(module_file.clone(), source, IndexMap::new())?;
+
+    let mut children = Vec::new();
+    for name in module.mergeable_children(dir) {
+        let path = dir.join(format!("{}{}", name, RustFileContent::RS_EXTENSION));
+        let source = fs::read_to_string(&path).map_err(|e| Error::io(IoAction::Read, &path, e))?;
+        if syn::parse_file(&source).is_ok_and(|file| !file.attrs.is_empty()) {
+            warn!(
+                "Not merging {}, its inner attributes apply to the whole file",
+                path.display()
+            );
+            continue;
+        }
+        children.push(RustFileContent::parsed(path, source, IndexMap::new())?);
+    }
+
+    let mut plan = SplitPlan::default();
+    if children.is_empty() {
+        info!("Nothing to merge into {}", module_file.display());
+        return Ok(plan);
+    }
+
+    let merged_file = merged_module_file(dir, &module_file, &children);
+    plan.add_file(&merged_file, module.merged_content(&children)?);
+    if merged_file != module_file {
+        plan.remove_file(&module_file);
+    }
+    for child in &children {
+        plan.remove_file(child.path());
+    }
+    Ok(plan)
+}
+
+/// The file of the module in `dir`: its `mod.rs`, `lib.rs` or `main.rs`, or
+/// the `dir.rs` next to it
+fn module_file(dir: &Path) -> Result<PathBuf> {
+    if !dir.is_dir() {
+        return Err(Error::PathNotFound {
+            path: dir.to_path_buf(),
+        });
+    }
+
+    let inside = [
+        RustFileContent::MOD_RS,
+        RustFileContent::LIB_RS,
+        RustFileContent::MAIN_RS,
+    ]
+    .into_iter()
+    .map(|name| dir.join(name));
+    let beside = dir
+        .file_name()
+        .map(|name| dir.with_file_name(format!("{}.rs", name.to_string_lossy())));
+
+    inside
+        .chain(beside)
+        .find(|path| path.is_file())
+        .ok_or_else(|| Error::NotAModule {
+            path: dir.to_path_buf(),
+        })
+}
+
+/// The file the module in `dir` is merged into: the `dir.rs` next to the
+/// directory instead of its `mod.rs` if merging `children` leaves nothing
+/// else in it, otherwise `module_file`.
+///
+/// The `mod dir;` declaration of the parent module finds either file, so it
+/// stays as it is. Declarations with a `#[path]` attribute name the `mod.rs`
+/// and keep it.
+fn merged_module_file(dir: &Path, module_file: &Path, children: &[RustFileContent]) -> PathBuf {
+    let beside = dir.with_extension("rs");
+    if module_file.file_name() != Some(RustFileContent::MOD_RS.as_ref()) || beside.exists() {
+        // Not a `mod.rs`, or `dir.rs` is taken
+        return module_file.to_path_buf();
+    }
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        // Can not tell what else is in the directory
+        return module_file.to_path_buf();
+    };
+    let is_emptied = entries.flatten().all(|entry| {
+        let path = entry.path();
+        path == module_file || children.iter().any(|child| child.path() == &path)
+    });
+    if !is_emptied || is_declared_with_path(dir) {
+        // The directory or the declaration still needs the `mod.rs`
+        return module_file.to_path_buf();
+    }
+    beside
+}
+
+/// Checks if the module in `dir` is declared with a `#[path]` attribute in
+/// the module file of its parent directory
+fn is_declared_with_path(dir: &Path) -> bool {
+    let Some(name) = dir.file_name().map(|name| name.to_string_lossy()) else {
+        // No parent to declare it
+        return false;
+    };
+    let parent_file = dir.parent().and_then(|parent| module_file(parent).ok());
+    let Some(file) = parent_file
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|source| syn::parse_file(&source).ok())
+    else {
+        // No readable parent module file
+        return false;
+    };
+
+    file.items.iter().any(|item| {
+        matches!(item, syn::Item::Mod(module)
+            if module.ident == name.as_ref()
+                && module.attrs.iter().any(|attr| attr.path().is_ident("path")))
+    })
+}
+
+/// Removes `dir` if merging left nothing in it, like when the module file
+/// is the `dir.rs` next to it
+fn remove_dir_if_empty(dir: &Path) -> Result<()> {
+    let mut entries = fs::read_dir(dir).map_err(|e| Error::io(IoAction::ReadDirectory, dir, e))?;
+    if entries.next().is_some() {
+        // Still holds the module file or other files
+        return Ok(());
+    }
+    fs::remove_dir(dir).map_err(|e| Error::io(IoAction::RemoveDirectory, dir, e))
+}
@@ -1,5 +1,17 @@
 mod bridged;
+mod check_package;
+mod ensure_git_status_clean;
+mod gather_impls;
+mod load_config;
+mod merge;
 mod recursive_find_syn_items;
+mod resolve_prelude;
 
 pub use bridged::*;
+pub use check_package::*;
+pub use ensure_git_status_clean::*;
+pub use gather_impls::*;
+pub use load_config::*;
+pub use merge::*;
 pub use recursive_find_syn_items::*;
+pub use resolve_prelude::*;
@@ -1,9 +1,6 @@
 use crate::prelude::*;
 use log::warn;
 
-#[allow(unused_imports)]
-use std::process::Command;
-
 /// Input for the CLI, containing the source path and optional output path
 #[derive(Clone, Debug, Builder, Getters)]
 pub struct Input {
@@ -16,210 +13,287 @@ pub struct Input {
     allow_git_staged: bool,
     #[getset(get = "pub")]
     allow_git_dirty: bool,
+    /// If true, the split is only planned and nothing is written to disk
+    #[builder(default)]
+    #[getset(get = "pub")]
+    dry_run: bool,
+    /// How files are split, see `load_config`
+    #[builder(default)]
+    #[getset(get = "pub")]
+    config: Config,
+    /// If true, the package is checked with `cargo check` before and after
+    /// writing, and the split is rolled back if it introduced errors
+    #[builder(default)]
+    #[getset(get = "pub")]
+    verify: bool,
+    /// If true, impl blocks are first moved into the file defining their
+    /// type anywhere in the tree, see `gather_impls`
+    #[builder(default)]
+    #[getset(get = "pub")]
+    gather_impls: bool,
+    /// Globs, relative to `source`, of files and directories which are not
+    /// split, in addition to the ones skipped by default, see `ScanFilter`
+    #[builder(default)]
+    #[getset(get = "pub")]
+    exclude: Vec<String>,
+    /// If true, splitting is aborted at the first file which can not be
+    /// parsed, instead of skipping it and reporting it in the `ScanReport`
+    #[builder(default)]
+    #[getset(get = "pub")]
+    fail_fast: bool,
 }
 
 #[bon::builder]
-pub fn split(input: Input) -> Result<FileSystemNode> {
-    #[cfg(not(debug_assertions))]
-    ensure_git_status_clean(*input.allow_git_staged(), *input.allow_git_dirty())?;
+pub fn split(input: Input) -> Result<SplitOutcome> {
     let out = input.out().as_ref().unwrap_or(input.source());
-    do_split().source(input.source()).out(out).call()
+    do_split()
+        .source(input.source())
+        .out(out)
+        .dry_run(*input.dry_run())
+        .verify(*input.verify())
+        .gather(*input.gather_impls())
+        .exclude(input.exclude())
+        .fail_fast(*input.fail_fast())
+        .allow_git_staged(*input.allow_git_staged())
+        .allow_git_dirty(*input.allow_git_dirty())
+        .config(input.config())
+        .call()
 }
 
-#[allow(dead_code)]
-fn ensure_git_status_clean(allow_git_staged: bool, allow_git_dirty: bool) -> Result<()> {
-    // Dirty is a superset of staged, so if we allow dirty, we also allow staged
-    let allow_git_staged = allow_git_staged || allow_git_dirty;
-
-    // Check if git is available and we're in a git repository
-    let git_dir_check = Command::new("git")
-        .args(["rev-parse", "--git-dir"])
-        .output();
-
-    let git_dir_output = match git_dir_check {
-        Ok(output) => output,
-        Err(_) => {
-            // Git is not available, proceed without checking
-            warn!("Git is not available, skipping repository status check");
-            return Ok(());
-        }
-    };
-
-    if !git_dir_output.status.success() {
-        // Not in a git repository, that's fine
-        return Ok(());
-    }
-
-    // We're in a git repository, check for uncommitted changes
-    let status_output = Command::new("git")
-        .args(["status", "--porcelain"])
-        .output()
-        .map_err(|e| Error::bail(format!("Failed to execute git status: {}", e)))?;
-
-    if !status_output.status.success() {
-        return Err(Error::bail("Git status command failed"));
+#[bon::builder]
+fn do_split(
+    source: impl AsRef<Path>,
+    out: impl AsRef<Path>,
+    dry_run: bool,
+    verify: bool,
+    gather: bool,
+    exclude: &[String],
+    fail_fast: bool,
+    allow_git_staged: bool,
+    allow_git_dirty: bool,
+    config: &Config,
+) -> Result<SplitOutcome> {
+    let (mut node, scan_report) = find_in()
+        .path(source.as_ref())
+        .exclude(exclude)
+        .fail_fast(fail_fast)
+        .call()?;
+    let mut warnings = Vec::new();
+    if gather {
+        node = gather_impls().node(node).warnings(&mut warnings).call()?;
     }
-
-    let status_stdout = String::from_utf8_lossy(&status_output.stdout);
-
-    if status_stdout.trim().is_empty() {
-        // Working tree is clean
-        return Ok(());
+    let plan = plan()
+        .node(&node)
+        .out(out.as_ref())
+        .config(config)
+        .warnings(warnings)
+        .call()?;
+
+    for rename in plan.renames() {
+        warn!("Renamed to avoid a name collision: {}", rename);
     }
 
-    // Parse git status output to distinguish between staged and unstaged changes
-    let mut has_staged_changes = false;
-    let mut has_unstaged_changes = false;
-
-    for line in status_stdout.lines() {
-        if line.len() < 2 {
-            continue;
-        }
-
-        let index_status = line.chars().next().unwrap_or(' ');
-        let worktree_status = line.chars().nth(1).unwrap_or(' ');
-
-        // Check for staged changes (index status is not ' ' or '?')
-        if index_status != ' ' && index_status != '?' {
-            has_staged_changes = true;
-        }
-
-        // Check for unstaged changes (worktree status is not ' ')
-        if worktree_status != ' ' {
-            has_unstaged_changes = true;
-        }
+    if !dry_run {
+        ensure_git_status_clean()
+            .plan(&plan)
+            .allow_git_staged(allow_git_staged)
+            .allow_git_dirty(allow_git_dirty)
+            .call()?;
     }
 
-    // Build error message based on what changes exist and what's allowed
-    let mut error_parts = Vec::new();
-
-    if has_staged_changes && !allow_git_staged {
-        error_parts.push("staged changes");
+    let mut verification = None;
+    if dry_run {
+        info!("Dry run, not writing {} planned file(s)", plan.len());
+    } else if verify {
+        verification = write_verified().plan(&plan).out(out.as_ref()).call()?;
+    } else {
+        write().plan(&plan).call()?;
     }
 
-    if has_unstaged_changes && !allow_git_dirty {
-        error_parts.push("unstaged changes");
-    }
+    Ok(SplitOutcome::builder()
+        .node(node)
+        .out(out.as_ref())
+        .scan_report(scan_report)
+        .plan(plan)
+        .maybe_verification(verification)
+        .build())
+}
 
-    if !error_parts.is_empty() {
-        let changes_desc = error_parts.join(" and ");
-        return Err(Error::bail(format!(
-            "The git repository has {}. Please commit or stash your changes before running this command.",
-            changes_desc
-        )));
+/// This function plans splitting all the Rust types identified in the given path into separate files if the type is supported - see `enum SourceItem` for list of supported types.
+///
+/// If several files in one directory would produce a file with the same name,
+/// e.g. `enum Foo` in both `foo.rs` and `bar.rs`, the file keeping its own name
+/// (`foo.rs`) gets it and the others are suffixed with an index in directory
+/// order (`foo_0.rs`, `foo_1.rs` etc). Every rename is recorded in the plan.
+///
+/// For types which has `impl` blocks the impl blocks will be moved to the same
+/// file as the type they implement. With `gather`, impl blocks written in
+/// another file than their type are moved to it first, see `gather_impls`.
+///
+/// Generated files referencing items split out to other files glob import the
+/// configured prelude if the crate declares it, see `resolve_prelude`,
+/// otherwise they import those items explicitly. Files referencing none of
+/// them import nothing.
+///
+/// The plan starts out with `warnings` found before planning, like those of
+/// `gather_impls`.
+///
+/// Nothing is written to disk, the returned plan is executed by `write`.
+#[bon::builder]
+fn plan(
+    node: &FileSystemNode,
+    out: impl AsRef<Path>,
+    config: &Config,
+    warnings: Vec<String>,
+) -> Result<SplitPlan> {
+    let config = resolve_prelude().node(node).config(config).call();
+    let mut plan = SplitPlan::default();
+    for warning in warnings {
+        plan.add_warning(warning);
     }
-
-    Ok(())
+    plan.record_references(node);
+    node.plan_write_to(out.as_ref(), &config, &mut plan)?;
+    plan.apply_module_edits()?;
+    Ok(plan)
 }
 
+/// Executes a plan produced by `plan`, writing every planned file to disk.
 #[bon::builder]
-fn do_split(source: impl AsRef<Path>, out: impl AsRef<Path>) -> Result<FileSystemNode> {
-    let node = find_in().path(source).call()?;
-    write().node(node.clone()).out(out).call()?;
-    Ok(node)
+fn write(plan: &SplitPlan) -> Result<()> {
+    plan.execute()
 }
 
-/// This function splits all the Rust types identified in the given path into separate files if the type is supported - see `enum SourceItem` for list of supported types.
-/// The `Unsplittable` and `Verbatim` types are not split, as they do not have
-/// a corresponding file type - they will be put in files named `unsplittable_0.rs`,
-/// `unsplittable_1.rs` and `verbatim_0.rs`, `verbatim_1.rs` etc, where the index
-/// is the order in which they were found according to the files in that folder.
-/// I.e. if `foo.rs`, `bar.rs` and `baz.rs` are found in the same directory and
-///  each of these three files contain any unsplittable items, they will be put in `unsplittable_0.rs`, `unsplittable_1.rs` and `unsplittable_2.rs` respectively.
-/// If two types with the same name are found e.g. `enum Foo` in `foo.rs` and
-/// `bar.rs`, the first one will be put in `foo_0.rs` and the second one in `bar_0.rs`.
+/// Executes a plan like `write`, but runs `cargo check` on the package the
+/// files are written to before and after. If the split increased the number
+/// of errors every written and deleted file is restored.
 ///
-/// For types which has `impl` blocks the impl blocks will be moved to the same
-/// file as the type they implement.
+/// Returns `None` if `out` is not part of a package, in which case the plan
+/// is written without verification.
 #[bon::builder]
-fn write(node: FileSystemNode, out: impl AsRef<Path>) -> Result<()> {
-    node.write_to(out.as_ref())
+fn write_verified(plan: &SplitPlan, out: &Path) -> Result<Option<Verification>> {
+    let Some(package_dir) = find_package_dir(out) else {
+        warn!(
+            "{} is not part of a cargo package, writing without verification",
+            out.display()
+        );
+        write().plan(plan).call()?;
+        return Ok(None);
+    };
+
+    let baseline = check_package().package_dir(&package_dir).call()?;
+    let backup = plan.backup()?;
+    if let Err(error) = write().plan(plan).call() {
+        backup.restore()?;
+        return Err(error);
+    }
+    let after = check_package().package_dir(&package_dir).call()?;
+
+    let rolled_back = after.error_count() > baseline.error_count();
+    if rolled_back {
+        warn!("The split introduced compile errors, rolling it back");
+        backup.restore()?;
+    }
+
+    Ok(Some(
+        Verification::builder()
+            .package_dir(package_dir)
+            .baseline(baseline)
+            .after(after)
+            .rolled_back(rolled_back)
+            .build(),
+    ))
 }
 
+/// Parses the Rust file at `path`, or every Rust file below the directory at
+/// `path` which is not skipped, see `ScanFilter`.
+///
+/// Files and directories below `path` which can not be read or parsed are
+/// left out of the tree and reported in the returned `ScanReport`, unless
+/// `fail_fast` is set in which case the first one aborts the scan.
 #[bon::builder]
-fn find_in(path: impl AsRef<std::path::Path>) -> Result<FileSystemNode> {
+fn find_in(
+    path: impl AsRef<std::path::Path>,
+    #[builder(default)] exclude: &[String],
+    #[builder(default)] fail_fast: bool,
+) -> Result<(FileSystemNode, ScanReport)> {
     let path = path.as_ref().to_path_buf();
 
     validate_path_exists(&path)?;
-    determine_path_type_and_parse(path)
+    determine_path_type_and_parse(path, exclude, fail_fast)
 }
 
 /// Validates that the given path exists
 fn validate_path_exists(path: &Path) -> Result<()> {
     if !path.exists() {
-        return Err(Error::bail(format!(
-            "Path does not exist: {}",
-            path.display()
-        )));
+        return Err(Error::PathNotFound {
+            path: path.to_path_buf(),
+        });
     }
     Ok(())
 }
 
 /// Determines path type (file/directory) and calls appropriate parser
 /// Using early returns to handle different path types immediately
-fn determine_path_type_and_parse(path: PathBuf) -> Result<FileSystemNode> {
+fn determine_path_type_and_parse(
+    path: PathBuf,
+    exclude: &[String],
+    fail_fast: bool,
+) -> Result<(FileSystemNode, ScanReport)> {
     // Early return for files
     if path.is_file() {
-        return parse_rust_file().path(path).call();
+        validate_rust_file_extension(&path)?;
+        let node = parse_rust_file().path(path).call()?;
+        return Ok((node, ScanReport::default()));
     }
 
     // Early return for directories
     if path.is_dir() {
-        return scan_directory().path(path).call();
+        let filter = ScanFilter::new(&path, exclude)?;
+        let mut report = ScanReport::default();
+        let node = scan_directory()
+            .path(path)
+            .filter(&filter)
+            .fail_fast(fail_fast)
+            .report(&mut report)
+            .call()?;
+        return Ok((node, report));
     }
 
     // Neither file nor directory - return error
-    Err(Error::bail(format!(
-        "Invalid path type: {}",
-        path.display()
-    )))
+    Err(Error::NotRustSource { path })
 }
 
 /// Parse a single Rust file
 #[bon::builder]
 fn parse_rust_file(path: PathBuf) -> Result<FileSystemNode> {
-    let name = extract_file_name(&path)?;
-    validate_rust_file_extension(&path)?;
-
-    let content = read_file_content(&path)?;
-    let items = parse_file().content(content).call()?;
-
-    create_rust_file_node(name, path, items)
-}
+    let name = extract_node_name(&path);
+    let content = read_to_string().path(&path).call()?;
+    let items = parse_file().path(&path).content(&content).call()?;
 
-/// Extracts the file name from a path
-fn extract_file_name(path: &Path) -> Result<String> {
-    let name = path
-        .file_name()
-        .ok_or_else(|| Error::bail("Invalid file name"))?
-        .to_string_lossy()
-        .to_string();
-    Ok(name)
+    Ok(create_rust_file_node(name, path, items, content))
 }
 
 /// Validates that the file has a .rs extension
 fn validate_rust_file_extension(path: &Path) -> Result<()> {
     if path.extension().is_none_or(|ext| ext != "rs") {
-        return Err(Error::bail(format!("Not a Rust file: {}", path.display())));
+        return Err(Error::NotRustSource {
+            path: path.to_path_buf(),
+        });
     }
     Ok(())
 }
 
-/// Reads the content of a file as a string
-fn read_file_content(path: &Path) -> Result<String> {
-    fs::read_to_string(path)
-        .map_err(|e| Error::bail(format!("Failed to read file {}: {}", path.display(), e)))
-}
-
 /// Creates a FileSystemNode::RustFile from parsed components
 fn create_rust_file_node(
     name: String,
     path: PathBuf,
     items: Vec<SourceItem>,
-) -> Result<FileSystemNode> {
+    source: String,
+) -> FileSystemNode {
     let named_items = NamedSourceItems::builder()
         .name(name.clone())
         .items(items)
+        .source(source)
         .build();
 
     let rust_file_content = NodeContent::builder()
@@ -228,23 +302,28 @@ fn create_rust_file_node(
         .content(named_items)
         .build();
 
-    Ok(FileSystemNode::RustFile(rust_file_content))
+    FileSystemNode::RustFile(rust_file_content)
 }
 
 /// Scan a directory recursively using DFS
 #[bon::builder]
-fn scan_directory(path: PathBuf) -> Result<FileSystemNode> {
-    let name = extract_directory_name(&path);
+fn scan_directory(
+    path: PathBuf,
+    filter: &ScanFilter,
+    fail_fast: bool,
+    report: &mut ScanReport,
+) -> Result<FileSystemNode> {
+    let name = extract_node_name(&path);
     let entries = read_directory_entries(&path)?;
 
-    let mut children = process_directory_entries(entries);
+    let mut children = process_directory_entries(entries, filter, fail_fast, report)?;
     children.sort();
 
     create_directory_node(name, path, children)
 }
 
-/// Extracts the directory name from a path
-fn extract_directory_name(path: &Path) -> String {
+/// Extracts the file or directory name from a path
+fn extract_node_name(path: &Path) -> String {
     path.file_name()
         .unwrap_or_default()
         .to_string_lossy()
@@ -253,30 +332,46 @@ fn extract_directory_name(path: &Path) -> String {
 
 /// Reads directory entries with error handling
 fn read_directory_entries(path: &Path) -> Result<fs::ReadDir> {
-    fs::read_dir(path).map_err(|e| {
-        Error::bail(format!(
-            "Failed to read directory {}: {}",
-            path.display(),
-            e
-        ))
-    })
+    read_dir().path(path).call()
 }
 
 /// Processes directory entries and filters for valid Rust files and subdirectories
-fn process_directory_entries(entries: fs::ReadDir) -> Vec<FileSystemNode> {
-    entries.filter_map(process_single_directory_entry).collect()
+fn process_directory_entries(
+    entries: fs::ReadDir,
+    filter: &ScanFilter,
+    fail_fast: bool,
+    report: &mut ScanReport,
+) -> Result<Vec<FileSystemNode>> {
+    let mut children = Vec::new();
+    for entry in entries {
+        if let Some(child) = process_single_directory_entry(entry, filter, fail_fast, report)? {
+            children.push(child);
+        }
+    }
+    Ok(children)
 }
 
 /// Processes a single directory entry
 /// Using early return to avoid nesting when handling directory entry errors
 fn process_single_directory_entry(
     entry: Result<fs::DirEntry, std::io::Error>,
-) -> Option<FileSystemNode> {
+    filter: &ScanFilter,
+    fail_fast: bool,
+    report: &mut ScanReport,
+) -> Result<Option<FileSystemNode>> {
     // Early return if directory entry reading failed
-    let entry = handle_directory_entry_error(entry)?;
+    let Some(entry) = handle_directory_entry_error(entry) else {
+        return Ok(None);
+    };
     let entry_path = entry.path();
 
-    classify_and_parse_path(entry_path)
+    // Early return for skipped paths
+    if !filter.includes(&entry_path) {
+        debug!("Skipping {}", entry_path.display());
+        return Ok(None);
+    }
+
+    classify_and_parse_path(entry_path, filter, fail_fast, report)
 }
 
 /// Handles errors when reading directory entries
@@ -298,19 +393,52 @@ fn handle_directory_entry_error(
 
 /// Classifies path type and calls appropriate parser
 /// Using early returns to handle different path types immediately
-fn classify_and_parse_path(entry_path: PathBuf) -> Option<FileSystemNode> {
+fn classify_and_parse_path(
+    entry_path: PathBuf,
+    filter: &ScanFilter,
+    fail_fast: bool,
+    report: &mut ScanReport,
+) -> Result<Option<FileSystemNode>> {
     // Early return for directories
     if entry_path.is_dir() {
-        return scan_directory().path(entry_path).call().ok();
+        let directory = scan_directory()
+            .path(entry_path.clone())
+            .filter(filter)
+            .fail_fast(fail_fast)
+            .report(report)
+            .call();
+        return skip_or_abort(&entry_path, directory, fail_fast, report);
     }
 
     // Early return for Rust files
     if is_rust_file(&entry_path) {
-        return parse_rust_file().path(entry_path).call().ok();
+        let file = parse_rust_file().path(entry_path.clone()).call();
+        return skip_or_abort(&entry_path, file, fail_fast, report);
     }
 
     // Default: not a supported file type
-    None
+    Ok(None)
+}
+
+/// Records `path` in `report` if scanning it failed, or aborts the scan with
+/// the error if `fail_fast` is set. Errors of nested paths only reach here
+/// when aborting, the others are recorded where they occur.
+fn skip_or_abort(
+    path: &Path,
+    scanned: Result<FileSystemNode>,
+    fail_fast: bool,
+    report: &mut ScanReport,
+) -> Result<Option<FileSystemNode>> {
+    let error = match scanned {
+        Ok(node) => return Ok(Some(node)),
+        Err(error) if fail_fast => return Err(error),
+        Err(error) => error,
+    };
+
+    let failure = ScanFailure::from_error(path, &error);
+    warn!("Skipping {}", failure);
+    report.push(failure);
+    Ok(None)
 }
 
 /// Checks if a path is a Rust file
@@ -333,16 +461,16 @@ fn create_directory_node(
     Ok(FileSystemNode::Directory(directory_content))
 }
 
-/// Parse a file content string into SourceItems
+/// Parse the content of the file at `path` into SourceItems, pointing at the
+/// syntax error or the first item klyv does not support on failure
 #[bon::builder]
-fn parse_file(content: String) -> Result<Vec<SourceItem>> {
-    let parsed_file = syn::parse_file(&content)
-        .map_err(|e| Error::bail(format!("Failed to parse Rust syntax: {}", e)))?;
+pub(crate) fn parse_file(path: &Path, content: &str) -> Result<Vec<SourceItem>> {
+    let parsed_file = syn::parse_file(content).map_err(|e| Error::parse(path, &e))?;
 
     parsed_file
         .items
         .into_iter()
-        .map(SourceItem::try_from)
+        .map(|item| SourceItem::try_from(item).map_err(|error| error.in_file(path)))
         .collect::<Result<Vec<SourceItem>>>()
 }
 
@@ -364,7 +492,11 @@ mod tests {
             }
         "#;
 
-        let items = parse_file().content(content.to_string()).call().unwrap();
+        let items = parse_file()
+            .path(Path::new("test.rs"))
+            .content(content)
+            .call()
+            .unwrap();
         assert_eq!(items.len(), 2); // struct + impl
     }
 
@@ -403,7 +535,10 @@ mod extensive_tests {
             struct Bar;
         "#;
 
-        let result = parse_file().content(content.to_string()).call();
+        let result = parse_file()
+            .path(Path::new("test.rs"))
+            .content(content)
+            .call();
         assert!(result.is_ok());
         assert_eq!(result.unwrap().len(), 2);
     }
@@ -411,7 +546,10 @@ mod extensive_tests {
     #[test]
     fn test_parse_invalid_rust_file() {
         let content = "invalid rust syntax {}}";
-        let result = parse_file().content(content.to_string()).call();
+        let result = parse_file()
+            .path(Path::new("test.rs"))
+            .content(content)
+            .call();
         assert!(result.is_err());
     }
 
@@ -422,7 +560,7 @@ mod extensive_tests {
         let mut file = File::create(&file_path).unwrap();
         writeln!(file, "fn main() {{}}").unwrap();
 
-        let node = find_in().path(&file_path).call().unwrap();
+        let (node, _) = find_in().path(&file_path).call().unwrap();
         assert_eq!(node.name(), "test.rs");
         assert_eq!(node.rust_files().len(), 1);
     }
@@ -449,16 +587,58 @@ mod extensive_tests {
             .write_all(b"fn sub() {}")
             .unwrap();
 
-        let node = find_in().path(dir.path()).call().unwrap();
+        let (node, _) = find_in().path(dir.path()).call().unwrap();
         assert!(matches!(node, FileSystemNode::Directory { .. }));
         assert_eq!(node.rust_files().len(), 2);
         assert_eq!(node.directories().len(), 2); // root + subdir
     }
 
+    #[test]
+    fn test_find_in_directory_skips_ignored_paths() {
+        let dir = tempdir().unwrap();
+        for subdir in ["kept", "ignored", ".hidden", "target", "generated"] {
+            fs::create_dir(dir.path().join(subdir)).unwrap();
+            fs::write(dir.path().join(subdir).join("a.rs"), "struct A;").unwrap();
+        }
+        fs::write(dir.path().join("lib.rs"), "mod kept;").unwrap();
+        fs::write(dir.path().join(".gitignore"), "ignored/\n").unwrap();
+        fs::write(dir.path().join("target").join("CACHEDIR.TAG"), "").unwrap();
+
+        let exclude = vec!["generated".to_owned()];
+        let (node, _) = find_in().path(dir.path()).exclude(&exclude).call().unwrap();
+        let mut files: Vec<PathBuf> = node
+            .rust_file_contents()
+            .iter()
+            .map(|file| file.path().strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![PathBuf::from("kept/a.rs"), PathBuf::from("lib.rs")]
+        );
+    }
+
+    #[test]
+    fn test_find_in_directory_reports_unparsable_files() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("good.rs"), "struct Good;").unwrap();
+        fs::write(dir.path().join("bad.rs"), "struct Bad;\nfn broken( {}\n").unwrap();
+
+        let (node, report) = find_in().path(dir.path()).call().unwrap();
+        assert_eq!(node.rust_files().len(), 1);
+        assert_eq!(report.failures().len(), 1);
+        let failure = &report.failures()[0];
+        assert_eq!(failure.path(), &dir.path().join("bad.rs"));
+        assert_eq!(failure.line(), &Some(2));
+
+        let aborted = find_in().path(dir.path()).fail_fast(true).call();
+        assert!(aborted.is_err());
+    }
+
     #[test]
     fn test_empty_directory() {
         let dir = tempdir().unwrap();
-        let node = find_in().path(dir.path()).call().unwrap();
+        let (node, _) = find_in().path(dir.path()).call().unwrap();
         assert!(matches!(node, FileSystemNode::Directory { .. }));
         assert_eq!(node.rust_files().len(), 0);
     }
@@ -505,7 +685,7 @@ mod extensive_tests {
                 .build(),
         );
 
-        let mut nodes = vec![file1, dir1, file2, dir2];
+        let mut nodes = [file1, dir1, file2, dir2];
         nodes.sort();
 
         // Should be: a_dir, b_dir, a_file.rs, z_file.rs
@@ -0,0 +1,124 @@
+use crate::prelude::*;
+
+/// Resolves whether the files split out of `node` can glob import the
+/// prelude configured in `config`.
+///
+/// The prelude is available if the crate root of `node`, the closest
+/// `lib.rs` or `main.rs` in its ancestor directories, declares a module named
+/// like it, or if klyv generates it while splitting that crate root. Otherwise
+/// the returned configuration has no prelude, so that generated files import
+/// the items they need explicitly instead.
+#[bon::builder]
+pub fn resolve_prelude(node: &FileSystemNode, config: &Config) -> Config {
+    let Some(prelude) = config.prelude() else {
+        // No prelude configured, nothing to resolve
+        return config.clone();
+    };
+
+    let Some(crate_root) = find_crate_root(node.path()) else {
+        debug!("No crate root found for {}", node.path().display());
+        return config.without_prelude();
+    };
+
+    let is_declared = match node.find_rust_file(&crate_root) {
+        Some(file) => {
+            file.generates_prelude(config) || declares_module(file.content().source(), prelude)
+        }
+        None => {
+            fs::read_to_string(&crate_root).is_ok_and(|source| declares_module(&source, prelude))
+        }
+    };
+
+    if is_declared {
+        return config.clone();
+    }
+
+    info!(
+        "{} declares no `{}` module, importing split items explicitly",
+        crate_root.display(),
+        prelude
+    );
+    config.without_prelude()
+}
+
+/// Returns the closest `lib.rs` or `main.rs` in `path` or its ancestors
+pub(crate) fn find_crate_root(path: &Path) -> Option<PathBuf> {
+    let start = if path.is_file() {
+        path.parent().unwrap_or(Path::new("."))
+    } else {
+        path
+    };
+
+    start.ancestors().find_map(|dir| {
+        [RustFileContent::LIB_RS, RustFileContent::MAIN_RS]
+            .into_iter()
+            .map(|root| dir.join(root))
+            .find(|root| root.is_file())
+    })
+}
+
+/// Checks if `source` declares the module `name` at its top level, either
+/// inline or in a file of its own
+fn declares_module(source: &str, name: &str) -> bool {
+    let Ok(file) = syn::parse_file(source) else {
+        // Unparsable crate root, can't tell
+        return false;
+    };
+
+    file.items
+        .iter()
+        .any(|item| matches!(item, syn::Item::Mod(module) if module.ident == name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn resolve(source: &Path) -> Option<String> {
+        let node = FileSystemNode::RustFile(
+            NodeContent::builder()
+                .name("foo.rs".to_string())
+                .path(source.to_path_buf())
+                .content(
+                    NamedSourceItems::builder()
+                        .name("foo.rs".to_string())
+                        .items(vec![])
+                        .build(),
+                )
+                .build(),
+        );
+        resolve_prelude()
+            .node(&node)
+            .config(&Config::default())
+            .call()
+            .prelude()
+            .clone()
+    }
+
+    #[test]
+    fn test_prelude_declared_by_crate_root_is_kept() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("src/foo.rs");
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(&source, "struct Foo;\n").unwrap();
+        fs::write(
+            dir.path().join("src/lib.rs"),
+            "mod foo;\n\npub mod prelude {\n    pub use crate::foo::*;\n}\n",
+        )
+        .unwrap();
+
+        assert_eq!(resolve(&source), Some(Config::DEFAULT_PRELUDE.to_owned()));
+    }
+
+    #[test]
+    fn test_missing_prelude_is_disabled() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("src/foo.rs");
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(&source, "struct Foo;\n").unwrap();
+        fs::write(dir.path().join("src/main.rs"), "mod foo;\n\nfn main() {}\n").unwrap();
+
+        assert_eq!(resolve(&source), None);
+    }
+}
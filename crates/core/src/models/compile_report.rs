@@ -0,0 +1,73 @@
+use crate::prelude::*;
+
+/// The diagnostics of a `cargo check` run of a package
+#[derive(Clone, Debug, Default, PartialEq, Eq, Getters, Builder)]
+pub struct CompileReport {
+    /// Every diagnostic reported, in the order cargo printed them
+    #[getset(get = "pub")]
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl CompileReport {
+    /// Parses the output of `cargo check --message-format=short`
+    pub fn parse(output: &str) -> Self {
+        Self {
+            diagnostics: output.lines().filter_map(Diagnostic::parse_short).collect(),
+        }
+    }
+
+    /// Returns the number of errors reported
+    pub fn error_count(&self) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.level() == &DiagnosticLevel::Error)
+            .count()
+    }
+
+    /// Returns the diagnostics which were not reported in `baseline`. A
+    /// problem reported twice here but once in `baseline` is new once.
+    pub fn new_diagnostics(&self, baseline: &Self) -> Vec<&Diagnostic> {
+        let mut unmatched: Vec<&Diagnostic> = baseline.diagnostics.iter().collect();
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| {
+                let Some(position) = unmatched
+                    .iter()
+                    .position(|existing| existing.is_same_problem(diagnostic))
+                else {
+                    return true;
+                };
+                unmatched.swap_remove(position);
+                false
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_diagnostics_ignore_moved_ones() {
+        let baseline = CompileReport::parse(
+            "src/shapes.rs:4:5: warning: field `radius` is never read\n\
+             warning: `shapes` (lib) generated 1 warning\n",
+        );
+        let after = CompileReport::parse(
+            "src/circle.rs:2:5: warning: field `radius` is never read\n\
+             src/functions.rs:1:5: error[E0432]: unresolved import `super::Cache`\n\
+             src/functions.rs:9:5: error[E0432]: unresolved import `super::Cache`\n\
+             error: could not compile `shapes` (lib) due to 2 previous errors\n",
+        );
+
+        assert_eq!(baseline.error_count(), 0);
+        assert_eq!(after.error_count(), 2);
+        let new = after.new_diagnostics(&baseline);
+        assert_eq!(new.len(), 2);
+        assert!(
+            new.iter()
+                .all(|diagnostic| diagnostic.code().as_deref() == Some("E0432"))
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use crate::prelude::*;
+
+/// User configuration of how files are split, read from a `klyv.toml` file
+/// or from the `[package.metadata.klyv]` (or `[workspace.metadata.klyv]`)
+/// table of a `Cargo.toml`.
+///
+/// ```toml
+/// types-folder = "models"
+/// logic-folder = "ops"
+/// functions-module = "functions"
+/// prelude = false
+/// strategy = "organized"
+/// constants = "with-user"
+///
+/// [trait-impls]
+/// default = "with-type"
+/// traits = { Display = "dedicated" }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Getters, Builder, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Folder types are moved to when splitting `lib.rs` and `main.rs`
+    #[builder(default = Config::DEFAULT_TYPES_FOLDER.to_owned())]
+    #[getset(get = "pub")]
+    types_folder: String,
+    /// Folder functions and macros are moved to when splitting `lib.rs` and
+    /// `main.rs`
+    #[builder(default = Config::DEFAULT_LOGIC_FOLDER.to_owned())]
+    #[getset(get = "pub")]
+    logic_folder: String,
+    /// Module, without `.rs`, free functions are collected into
+    #[builder(default = Config::DEFAULT_FUNCTIONS_MODULE.to_owned())]
+    #[getset(get = "pub")]
+    functions_module: String,
+    /// Name of the crate level prelude module generated files glob import to
+    /// reach the items split out to other files, `None` (`prelude = false`)
+    /// to generate no prelude at all
+    #[builder(required, default = Some(Config::DEFAULT_PRELUDE.to_owned()))]
+    #[serde(deserialize_with = "deserialize_prelude")]
+    #[getset(get = "pub")]
+    prelude: Option<String>,
+    /// How split files are organized
+    #[builder(default)]
+    #[getset(get = "pub")]
+    strategy: SplitStrategy,
+    /// Where trait impls are placed
+    #[builder(default)]
+    #[getset(get = "pub")]
+    trait_impls: TraitImplPolicy,
+    /// Where constants and statics are placed
+    #[builder(default)]
+    #[getset(get = "pub")]
+    constants: ConstPlacement,
+}
+
+impl Config {
+    /// Name of the configuration file
+    pub const FILE_NAME: &'static str = "klyv.toml";
+    pub const DEFAULT_TYPES_FOLDER: &'static str = "types";
+    pub const DEFAULT_LOGIC_FOLDER: &'static str = "logic";
+    pub const DEFAULT_FUNCTIONS_MODULE: &'static str = "functions";
+    pub const DEFAULT_PRELUDE: &'static str = "prelude";
+
+    /// File name of the module free functions are collected into
+    pub fn functions_file_name(&self) -> String {
+        format!("{}.rs", self.functions_module)
+    }
+
+    /// The glob import of the prelude, followed by a blank line, which starts
+    /// the generated files referencing items split out to other files. Empty
+    /// if there is no prelude.
+    pub fn prelude_import(&self) -> String {
+        self.prelude
+            .as_ref()
+            .map(|prelude| format!("use crate::{}::*;\n\n", prelude))
+            .unwrap_or_default()
+    }
+
+    /// A copy of this configuration without a prelude, for crates which do
+    /// not declare one
+    pub fn without_prelude(&self) -> Self {
+        Self {
+            prelude: None,
+            ..self.clone()
+        }
+    }
+
+    /// Parses a configuration from the content of a `klyv.toml` file
+    pub fn from_toml(toml: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Deserializes `prelude = "name"`, or `prelude = false` for no prelude
+fn deserialize_prelude<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Prelude {
+        Enabled(bool),
+        Named(String),
+    }
+
+    Ok(match serde::Deserialize::deserialize(deserializer)? {
+        Prelude::Enabled(true) => Some(Config::DEFAULT_PRELUDE.to_owned()),
+        Prelude::Enabled(false) => None,
+        Prelude::Named(name) => Some(name),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_toml_is_default() {
+        assert_eq!(Config::from_toml("").unwrap(), Config::default());
+        assert_eq!(
+            Config::default().prelude_import(),
+            "use crate::prelude::*;\n\n"
+        );
+    }
+
+    #[test]
+    fn test_parses_all_keys() {
+        let config = Config::from_toml(
+            "types-folder = \"models\"\nlogic-folder = \"ops\"\nfunctions-module = \"free\"\nprelude = false\nstrategy = \"flat\"\nconstants = \"shared\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.types_folder(), "models");
+        assert_eq!(config.logic_folder(), "ops");
+        assert_eq!(config.functions_file_name(), "free.rs");
+        assert_eq!(config.prelude(), &None);
+        assert_eq!(config.prelude_import(), "");
+        assert_eq!(config.strategy(), &SplitStrategy::Flat);
+        assert_eq!(config.constants(), &ConstPlacement::Shared);
+    }
+
+    #[test]
+    fn test_parses_trait_impl_policy() {
+        let config = Config::from_toml(
+            "[trait-impls]\ndefault = \"with-trait\"\ntraits = { Display = \"dedicated\", From = \"with-type\" }\n",
+        )
+        .unwrap();
+
+        let policy = config.trait_impls();
+        assert_eq!(policy.placement("Display"), TraitImplPlacement::Dedicated);
+        assert_eq!(policy.placement("From"), TraitImplPlacement::WithType);
+        assert_eq!(policy.placement("Shape"), TraitImplPlacement::WithTrait);
+        assert!(Config::from_toml("[trait-impls]\ndefault = \"nowhere\"").is_err());
+    }
+
+    #[test]
+    fn test_named_prelude() {
+        let config = Config::from_toml("prelude = \"common\"").unwrap();
+        assert_eq!(config.prelude_import(), "use crate::common::*;\n\n");
+    }
+
+    #[test]
+    fn test_unknown_key_is_rejected() {
+        assert!(Config::from_toml("type-folder = \"models\"").is_err());
+    }
+}
@@ -0,0 +1,22 @@
+use crate::prelude::*;
+use syn::Ident;
+
+#[derive(Clone, Deref, From)]
+pub struct Const(ItemConst);
+
+impl std::fmt::Debug for Const {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Const")
+            .field(&self.vis)
+            .field(&self.ident)
+            .field(&self.attrs)
+            .field(&self.ty)
+            .finish()
+    }
+}
+
+impl Identifiable for Const {
+    fn ident(&self) -> &Ident {
+        &self.ident
+    }
+}
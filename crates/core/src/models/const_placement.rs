@@ -0,0 +1,16 @@
+/// Where constants and statics are placed when splitting
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, derive_more::Display)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConstPlacement {
+    /// In the file of the only type or function using it, constants used by
+    /// several items or by none stay in the original file
+    #[default]
+    #[display("with-user")]
+    WithUser,
+    /// Together with the other constants and statics in `constants.rs`
+    #[display("shared")]
+    Shared,
+    /// In a file of its own, named after it
+    #[display("dedicated")]
+    Dedicated,
+}
@@ -0,0 +1,140 @@
+use crate::prelude::*;
+
+/// A single diagnostic reported by `cargo check`
+#[derive(Clone, Debug, PartialEq, Eq, Getters, Builder)]
+pub struct Diagnostic {
+    /// Whether it is an error or a warning
+    #[getset(get = "pub")]
+    level: DiagnosticLevel,
+    /// The error code, e.g. `E0425`, if the diagnostic has one
+    #[builder(into)]
+    #[getset(get = "pub")]
+    code: Option<String>,
+    /// The message, without location, level and code
+    #[builder(into)]
+    #[getset(get = "pub")]
+    message: String,
+    /// The file the diagnostic points at, as printed by cargo
+    #[builder(into)]
+    #[getset(get = "pub")]
+    file: Option<PathBuf>,
+    /// The line in `file` the diagnostic points at
+    #[getset(get = "pub")]
+    line: Option<usize>,
+}
+
+impl Diagnostic {
+    /// Parses a line of `cargo check --message-format=short` output, e.g.
+    /// `src/foo.rs:3:5: error[E0425]: cannot find value `x` in this scope`.
+    ///
+    /// Returns `None` for lines which are not diagnostics, warnings without
+    /// location (like the number of warnings generated) and the summary
+    /// errors cargo prints when a crate fails to compile.
+    pub fn parse_short(line: &str) -> Option<Self> {
+        let (location, rest) = match line.split_once(": ") {
+            Some((head, rest)) => match Self::parse_location(head) {
+                Some(location) => (Some(location), rest),
+                None => (None, line),
+            },
+            None => return None,
+        };
+
+        let (level_and_code, message) = rest.split_once(": ")?;
+        let (level, code) = match level_and_code.split_once('[') {
+            Some((level, code)) => (level, Some(code.trim_end_matches(']').to_owned())),
+            None => (level_and_code, None),
+        };
+        let level = DiagnosticLevel::parse(level)?;
+
+        if location.is_none() && level == DiagnosticLevel::Warning {
+            // Summaries like "`foo` (lib) generated 2 warnings"
+            return None;
+        }
+
+        if message.starts_with("could not compile") || message.starts_with("aborting due to") {
+            // Summary of the errors already reported
+            return None;
+        }
+
+        let (file, line) = location.unzip();
+        Some(
+            Self::builder()
+                .level(level)
+                .maybe_code(code)
+                .message(message)
+                .maybe_file(file)
+                .maybe_line(line)
+                .build(),
+        )
+    }
+
+    /// Parses a `path:line:column` location
+    fn parse_location(location: &str) -> Option<(PathBuf, usize)> {
+        let mut parts = location.rsplitn(3, ':');
+        let _column: usize = parts.next()?.parse().ok()?;
+        let line = parts.next()?.parse().ok()?;
+        let file = parts.next()?;
+        Some((PathBuf::from(file), line))
+    }
+
+    /// Checks if `other` reports the same problem, regardless of where.
+    /// Splitting moves items between files, so the location of a diagnostic
+    /// which already existed before is expected to change.
+    pub fn is_same_problem(&self, other: &Self) -> bool {
+        self.level == other.level && self.code == other.code && self.message == other.message
+    }
+
+    /// The level, code and message, e.g. `error[E0425]: cannot find value`
+    pub fn problem(&self) -> String {
+        match &self.code {
+            Some(code) => format!("{}[{}]: {}", self.level, code, self.message),
+            None => format!("{}: {}", self.level, self.message),
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let (Some(file), Some(line)) = (&self.file, self.line) {
+            write!(f, "{}:{}: ", file.display(), line)?;
+        }
+        write!(f, "{}", self.problem())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_short_diagnostics() {
+        let error = Diagnostic::parse_short(
+            "src/foo.rs:3:5: error[E0425]: cannot find value `x` in this scope",
+        )
+        .unwrap();
+        assert_eq!(error.level(), &DiagnosticLevel::Error);
+        assert_eq!(error.code().as_deref(), Some("E0425"));
+        assert_eq!(error.message(), "cannot find value `x` in this scope");
+        assert_eq!(error.file().as_deref(), Some(Path::new("src/foo.rs")));
+        assert_eq!(error.line(), &Some(3));
+
+        let warning =
+            Diagnostic::parse_short("src/lib.rs:1:8: warning: unused import: `Foo`").unwrap();
+        assert_eq!(warning.level(), &DiagnosticLevel::Warning);
+        assert_eq!(warning.message(), "unused import: `Foo`");
+
+        let unlocated = Diagnostic::parse_short("error[E0601]: `main` function not found").unwrap();
+        assert_eq!(unlocated.file(), &None);
+    }
+
+    #[test]
+    fn test_summaries_are_not_diagnostics() {
+        for line in [
+            "    Checking foo v0.1.0 (/tmp/foo)",
+            "warning: `foo` (lib) generated 2 warnings",
+            "error: could not compile `foo` (lib) due to 1 previous error",
+        ] {
+            assert_eq!(Diagnostic::parse_short(line), None, "{}", line);
+        }
+    }
+}
@@ -0,0 +1,21 @@
+/// Severity of a compiler diagnostic
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, derive_more::Display)]
+pub enum DiagnosticLevel {
+    /// The code does not compile
+    #[display("error")]
+    Error,
+    /// The code compiles, but the compiler warns about it
+    #[display("warning")]
+    Warning,
+}
+
+impl DiagnosticLevel {
+    /// Parses the level as printed by rustc, e.g. `error` or `warning`
+    pub fn parse(level: &str) -> Option<Self> {
+        match level {
+            "error" => Some(Self::Error),
+            "warning" => Some(Self::Warning),
+            _ => None,
+        }
+    }
+}
@@ -2,3 +2,64 @@ use crate::prelude::*;
 
 /// Type alias for a directory content, which is a NodeContent containing a vector of FileSystemNode
 pub type DirectoryContent = NodeContent<Vec<FileSystemNode>>;
+
+impl DirectoryContent {
+    /// Detects files in this directory which would be produced by more than
+    /// one source file and plans disambiguated names for them.
+    ///
+    /// A source file keeps its own name, every other file claiming a
+    /// colliding name gets it suffixed with an index, counted per name in the
+    /// order of the source files: `foo_0.rs`, `foo_1.rs` etc.
+    pub(super) fn plan_collision_renames(
+        &self,
+        output_dir: &Path,
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) {
+        let mut claims: IndexMap<String, Vec<&RustFileContent>> = IndexMap::new();
+        for file in self.rust_files() {
+            for file_name in file.planned_file_names(config) {
+                claims.entry(file_name).or_default().push(file);
+            }
+        }
+
+        let taken: Vec<String> = claims.keys().cloned().collect();
+        for (file_name, claimants) in &claims {
+            if claimants.len() < 2 || file_name == RustFileContent::MOD_RS {
+                // No collision, or a module file which is edited rather than replaced
+                continue;
+            }
+
+            let stem = file_name.trim_end_matches(RustFileContent::RS_EXTENSION);
+            let mut suffixes = (0..).map(|index| format!("{}_{}.rs", stem, index));
+
+            for claimant in claimants {
+                if claimant.name() == file_name {
+                    // The original file keeps its name
+                    continue;
+                }
+
+                let renamed = suffixes
+                    .by_ref()
+                    .find(|candidate| !taken.contains(candidate))
+                    .expect("Infinite suffixes");
+                plan.add_rename(
+                    FileRename::builder()
+                        .source(claimant.path().clone())
+                        .directory(output_dir.to_path_buf())
+                        .original_name(file_name.clone())
+                        .renamed(renamed)
+                        .build(),
+                );
+            }
+        }
+    }
+
+    /// The Rust files directly inside this directory
+    fn rust_files(&self) -> impl Iterator<Item = &RustFileContent> {
+        self.content().iter().filter_map(|node| match node {
+            FileSystemNode::RustFile(file) => Some(file),
+            FileSystemNode::Directory(_) => None,
+        })
+    }
+}
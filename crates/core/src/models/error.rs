@@ -1,24 +1,236 @@
-use derive_more::Debug;
+use crate::prelude::*;
 
 pub const BINARY_NAME: &str = "klyv";
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+/// Everything that can make klyv fail. The underlying I/O, TOML and glob
+/// errors are kept as the `source` of the error.
+#[derive(Debug, thiserror::Error)]
 pub enum Error {
-    #[error("An error occurred {underlying}")]
-    Generic { underlying: String },
+    /// The source or output path does not exist
+    #[error("Path does not exist: {}", path.display())]
+    PathNotFound { path: PathBuf },
+
+    /// The source path is neither a directory nor a Rust file
+    #[error("Not a Rust file or directory: {}", path.display())]
+    NotRustSource { path: PathBuf },
+
+    /// Reading, writing or removing a file or directory failed
+    #[error("Failed to {action} {}", path.display())]
+    Io {
+        action: IoAction,
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// A file is not valid Rust
+    #[error("{}:{line}:{column}: {message}", path.display())]
+    Parse {
+        path: PathBuf,
+        /// Line of the syntax error, starting at 1
+        line: usize,
+        /// Column of the syntax error, starting at 1
+        column: usize,
+        message: String,
+    },
+
+    /// A file contains an item of a kind klyv does not know how to split.
+    /// `path` is `None` until the item is known to belong to a file, see
+    /// `Error::in_file`.
+    #[error(
+        "{}unsupported item `{kind}`, please file an issue at https://github.com/Sajjon/klyv/issues/new",
+        Self::location_prefix(path.as_deref(), *line, *column)
+    )]
+    UnsupportedItem {
+        path: Option<PathBuf>,
+        /// Line of the item, starting at 1
+        line: usize,
+        /// Column of the item, starting at 1
+        column: usize,
+        /// The kind of item, e.g. `Item::Const`
+        kind: String,
+    },
+
+    /// Files klyv would touch have changes in git which are not allowed, see
+    /// `Input::allow_git_dirty` and `Input::allow_git_staged`
+    #[error(
+        "The git repository has {} in files klyv would touch: {}. Please commit or stash your changes before running this command.",
+        Self::git_changes(*staged, *unstaged),
+        Self::joined_paths(paths)
+    )]
+    GitDirty {
+        staged: bool,
+        unstaged: bool,
+        /// The changed files, relative to the root of the repository
+        paths: Vec<PathBuf>,
+    },
+
+    /// An external command, like `git` or `cargo`, could not be run or failed
+    #[error("Failed to run `{command}`")]
+    Command {
+        command: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// A `klyv.toml` or the klyv metadata of a `Cargo.toml` is invalid
+    #[error("Invalid configuration in {}", path.display())]
+    Config {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    /// An `--exclude` glob is invalid
+    #[error("Invalid exclude glob `{glob}`")]
+    ExcludeGlob {
+        glob: String,
+        #[source]
+        source: ignore::Error,
+    },
+
+    /// Files or directories could not be scanned and were left out of the
+    /// split, see `ScanReport`
+    #[error("{count} path(s) could not be parsed and were skipped")]
+    SkippedPaths { count: usize },
+
+    /// A verified split introduced compile errors and was rolled back, see
+    /// `Input::verify`
+    #[error(
+        "The split introduced {errors} compile error(s) in {} and was rolled back",
+        package_dir.display()
+    )]
+    RolledBack {
+        package_dir: PathBuf,
+        /// The number of errors the split introduced
+        errors: usize,
+    },
+
+    /// The directory to merge is not a module with a `mod.rs`, `lib.rs`,
+    /// `main.rs` or a sibling `<dir>.rs`
+    #[error("Not a module directory: {}", path.display())]
+    NotAModule { path: PathBuf },
+
+    /// Merging would define or import the same name twice in one file
+    #[error("Can not merge into {}, `{name}` would be defined more than once", path.display())]
+    MergeConflict { path: PathBuf, name: String },
 }
 
 impl Error {
-    pub fn bail(msg: impl AsRef<str>) -> Self {
-        Self::Generic {
-            underlying: msg.as_ref().to_owned(),
+    /// An I/O error while doing `action` with `path`
+    pub fn io(action: IoAction, path: impl AsRef<Path>, source: std::io::Error) -> Self {
+        Self::Io {
+            action,
+            path: path.as_ref().to_path_buf(),
+            source,
         }
     }
-    pub fn from(error: impl std::error::Error) -> Self {
-        Self::Generic {
-            underlying: error.to_string(),
+
+    /// A syntax error `syn` reported for the file at `path`
+    pub fn parse(path: impl AsRef<Path>, error: &syn::Error) -> Self {
+        let start = error.span().start();
+        Self::Parse {
+            path: path.as_ref().to_path_buf(),
+            line: start.line,
+            column: start.column + 1,
+            message: error.to_string(),
         }
     }
+
+    /// Attributes an error without path, like an `UnsupportedItem`, to the
+    /// file at `path`
+    pub fn in_file(self, path: impl AsRef<Path>) -> Self {
+        match self {
+            Self::UnsupportedItem {
+                path: None,
+                line,
+                column,
+                kind,
+            } => Self::UnsupportedItem {
+                path: Some(path.as_ref().to_path_buf()),
+                line,
+                column,
+                kind,
+            },
+            other => other,
+        }
+    }
+
+    /// The file, line and column the error points at, if it points into a
+    /// file
+    pub fn location(&self) -> Option<(&Path, usize, usize)> {
+        match self {
+            Self::Parse {
+                path, line, column, ..
+            } => Some((path, *line, *column)),
+            Self::UnsupportedItem {
+                path: Some(path),
+                line,
+                column,
+                ..
+            } => Some((path, *line, *column)),
+            _ => None,
+        }
+    }
+
+    /// The `path:line:column: ` an error is prefixed with, `line:column: `
+    /// without path
+    fn location_prefix(path: Option<&Path>, line: usize, column: usize) -> String {
+        match path {
+            Some(path) => format!("{}:{}:{}: ", path.display(), line, column),
+            None => format!("{}:{}: ", line, column),
+        }
+    }
+
+    /// Lists `paths` separated by commas
+    fn joined_paths(paths: &[PathBuf]) -> String {
+        paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Describes the changes of a dirty git repository
+    fn git_changes(staged: bool, unstaged: bool) -> &'static str {
+        match (staged, unstaged) {
+            (true, true) => "staged changes and unstaged changes",
+            (true, false) => "staged changes",
+            _ => "unstaged changes",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn test_parse_errors_point_at_their_location() {
+        let syntax_error = syn::parse_file("struct Foo;\nfn bar( {}\n").unwrap_err();
+        let error = Error::parse("src/foo.rs", &syntax_error);
+
+        let (path, line, _) = error.location().unwrap();
+        assert_eq!(path, Path::new("src/foo.rs"));
+        assert_eq!(line, 2);
+        assert!(error.to_string().starts_with("src/foo.rs:2:"), "{}", error);
+    }
+
+    #[test]
+    fn test_io_errors_keep_their_source() {
+        let error = Error::io(
+            IoAction::Write,
+            "src/foo.rs",
+            std::io::Error::from(std::io::ErrorKind::PermissionDenied),
+        );
+
+        assert_eq!(error.to_string(), "Failed to write src/foo.rs");
+        assert!(matches!(
+            error.source().and_then(|source| source.downcast_ref::<std::io::Error>()),
+            Some(source) if source.kind() == std::io::ErrorKind::PermissionDenied
+        ));
+    }
 }
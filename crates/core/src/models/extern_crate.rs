@@ -0,0 +1,22 @@
+use crate::prelude::*;
+use syn::Ident;
+
+#[derive(Clone, Deref, From)]
+pub struct ExternCrate(ItemExternCrate);
+
+impl std::fmt::Debug for ExternCrate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ExternCrate")
+            .field(&self.vis)
+            .field(&self.ident)
+            .field(&self.rename)
+            .field(&self.attrs)
+            .finish()
+    }
+}
+
+impl Identifiable for ExternCrate {
+    fn ident(&self) -> &Ident {
+        &self.ident
+    }
+}
@@ -0,0 +1,28 @@
+use crate::prelude::*;
+
+/// What klyv will do with a single file on disk when a plan is executed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, derive_more::Display)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileAction {
+    /// The file does not exist yet and will be created
+    #[display("create")]
+    Create,
+    /// The file already exists and its content will be replaced
+    #[display("overwrite")]
+    Overwrite,
+    /// The file exists and will be removed, since all its items moved out
+    #[display("delete")]
+    Delete,
+}
+
+impl FileAction {
+    /// Determines the action needed for writing to `path` given the current
+    /// state of the disk.
+    pub fn for_path(path: impl AsRef<Path>) -> Self {
+        if path.as_ref().exists() {
+            Self::Overwrite
+        } else {
+            Self::Create
+        }
+    }
+}
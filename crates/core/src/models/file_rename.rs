@@ -0,0 +1,31 @@
+use crate::prelude::*;
+
+/// An output file given a disambiguated name because several source files in
+/// the same directory would otherwise produce a file with the same name
+#[derive(Clone, Debug, PartialEq, Eq, Getters, Builder)]
+pub struct FileRename {
+    /// The source file the renamed file is split out of
+    #[getset(get = "pub")]
+    source: PathBuf,
+    /// The directory the renamed file is written to
+    #[getset(get = "pub")]
+    directory: PathBuf,
+    /// The colliding file name, e.g. `foo.rs`
+    #[getset(get = "pub")]
+    original_name: String,
+    /// The disambiguated file name, e.g. `foo_0.rs`
+    #[getset(get = "pub")]
+    renamed: String,
+}
+
+impl std::fmt::Display for FileRename {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} -> {} (split out of {})",
+            self.directory.join(&self.original_name).display(),
+            self.renamed,
+            self.source.display()
+        )
+    }
+}
@@ -10,14 +10,34 @@ pub enum FileSystemNode {
 }
 
 pub trait FileWritable {
-    fn write_to(&self, path: impl AsRef<Path>) -> Result<()>;
+    /// Records every file that writing `self` to `path` according to
+    /// `config` would produce into `plan`, without touching the disk.
+    fn plan_write_to(
+        &self,
+        path: impl AsRef<Path>,
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) -> Result<()>;
+
+    /// Plans writing `self` to `path` and immediately executes the plan.
+    fn write_to(&self, path: impl AsRef<Path>, config: &Config) -> Result<()> {
+        let mut plan = SplitPlan::default();
+        self.plan_write_to(path, config, &mut plan)?;
+        plan.apply_module_edits()?;
+        plan.execute()
+    }
 }
 
 impl FileWritable for FileSystemNode {
-    fn write_to(&self, path: impl AsRef<Path>) -> Result<()> {
+    fn plan_write_to(
+        &self,
+        path: impl AsRef<Path>,
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) -> Result<()> {
         match self {
-            Self::Directory(dir) => dir.write_to(path),
-            Self::RustFile(file) => file.write_to(path),
+            Self::Directory(dir) => dir.plan_write_to(path, config, plan),
+            Self::RustFile(file) => file.plan_write_to(path, config, plan),
         }
     }
 }
@@ -49,6 +69,52 @@ impl FileSystemNode {
         }
     }
 
+    /// Get all Rust files recursively from this node, with their paths
+    pub fn rust_file_contents(&self) -> Vec<&RustFileContent> {
+        match self {
+            Self::Directory(dir) => dir
+                .content()
+                .iter()
+                .flat_map(|child| child.rust_file_contents())
+                .collect(),
+            Self::RustFile(file) => vec![file],
+        }
+    }
+
+    /// Replaces the Rust file at the path of `file` in this node,
+    /// recursively. Returns `false` if there is no such file.
+    pub fn replace_rust_file(&mut self, file: RustFileContent) -> bool {
+        match self {
+            Self::Directory(dir) => {
+                let Some(child) = dir
+                    .content_mut()
+                    .iter_mut()
+                    .find(|child| file.path().starts_with(child.path()))
+                else {
+                    // Not in this directory
+                    return false;
+                };
+                child.replace_rust_file(file)
+            }
+            Self::RustFile(existing) if existing.path() == file.path() => {
+                *existing = file;
+                true
+            }
+            Self::RustFile(_) => false,
+        }
+    }
+
+    /// Finds the Rust file at `path` in this node, recursively
+    pub fn find_rust_file(&self, path: &Path) -> Option<&RustFileContent> {
+        match self {
+            Self::Directory(dir) => dir
+                .content()
+                .iter()
+                .find_map(|child| child.find_rust_file(path)),
+            Self::RustFile(file) => (file.path() == path).then_some(file),
+        }
+    }
+
     /// Get all directories recursively from this node
     pub fn directories(&self) -> Vec<&FileSystemNode> {
         match self {
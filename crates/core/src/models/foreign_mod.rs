@@ -0,0 +1,14 @@
+use crate::prelude::*;
+
+#[derive(Clone, Deref, From)]
+pub struct ForeignMod(ItemForeignMod);
+
+impl std::fmt::Debug for ForeignMod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ForeignMod")
+            .field(&self.abi)
+            .field(&self.attrs)
+            .field(&self.items)
+            .finish()
+    }
+}
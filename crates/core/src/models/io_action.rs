@@ -0,0 +1,29 @@
+/// What klyv was doing with a file or directory when an I/O error occurred,
+/// see `Error::Io`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, derive_more::Display)]
+pub enum IoAction {
+    /// Reading a file
+    #[display("read")]
+    Read,
+    /// Listing the entries of a directory
+    #[display("read directory")]
+    ReadDirectory,
+    /// Writing a planned file
+    #[display("write")]
+    Write,
+    /// Deleting a file which was planned for deletion or created by a plan
+    #[display("delete")]
+    Delete,
+    /// Creating the parent directories of a planned file
+    #[display("create directory")]
+    CreateDirectory,
+    /// Removing a directory created by a plan which was rolled back
+    #[display("remove directory")]
+    RemoveDirectory,
+    /// Reading a file before a plan overwrites it
+    #[display("back up")]
+    BackUp,
+    /// Writing back a file a plan overwrote
+    #[display("restore")]
+    Restore,
+}
@@ -0,0 +1,138 @@
+use crate::prelude::*;
+use proc_macro2::TokenTree;
+
+/// Keywords introducing a type in the input of a macro invocation, as in
+/// `bitflags! { pub struct Flags: u32 { ... } }`
+const TYPE_KEYWORDS: [&str; 5] = ["struct", "enum", "union", "trait", "type"];
+
+/// Keywords introducing any other named item in the input of a macro
+/// invocation, as in `thread_local! { static COUNT: Cell<u32> = ...; }`
+const ITEM_KEYWORDS: [&str; 4] = ["fn", "const", "static", "mod"];
+
+/// Keywords which may follow an item keyword before the name of the item, as
+/// in `lazy_static! { static ref CACHE: Cache = ...; }`
+const NAME_PREFIXES: [&str; 2] = ["ref", "mut"];
+
+/// A macro invoked at item position, e.g. `bitflags! { ... }` or
+/// `impl_ops!(Circle);`, as opposed to a `macro_rules!` definition
+#[derive(Clone, Deref, From)]
+pub struct MacroInvocation(ItemMacro);
+
+impl std::fmt::Debug for MacroInvocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("MacroInvocation")
+            .field(&self.attrs)
+            .field(&self.mac)
+            .finish()
+    }
+}
+
+impl MacroInvocation {
+    /// The name of the first type declared in the input of the macro, which
+    /// it presumably expands to, e.g. `Flags` for
+    /// `bitflags! { pub struct Flags: u32 { ... } }`
+    pub fn declared_type_name(&self) -> Option<String> {
+        self.top_level_idents()
+            .windows(2)
+            .find(|pair| TYPE_KEYWORDS.contains(&pair[0].as_str()))
+            .map(|pair| pair[1].to_string())
+    }
+
+    /// The names of the items declared in the input of the macro, which
+    /// other items may reference, e.g. `CACHE` for
+    /// `lazy_static! { static ref CACHE: Cache = Cache::new(); }`
+    pub fn declared_item_names(&self) -> Vec<String> {
+        let idents = self.top_level_idents();
+        let mut names = Vec::new();
+        for (index, ident) in idents.iter().enumerate() {
+            if !TYPE_KEYWORDS.contains(&ident.as_str()) && !ITEM_KEYWORDS.contains(&ident.as_str())
+            {
+                // Not introducing an item
+                continue;
+            }
+
+            let name = idents[index + 1..]
+                .iter()
+                .find(|next| !NAME_PREFIXES.contains(&next.as_str()));
+            names.extend(name.cloned());
+        }
+        names
+    }
+
+    /// Checks if the input of the macro declares an item `pub`, as in
+    /// `bitflags! { pub struct Flags: u32 { ... } }`, as opposed to a
+    /// restricted visibility like `pub(crate)`
+    pub fn declares_public_item(&self) -> bool {
+        let tokens: Vec<TokenTree> = self.mac.tokens.clone().into_iter().collect();
+        tokens.iter().enumerate().any(|(index, token)| {
+            matches!(token, TokenTree::Ident(ident) if ident == "pub")
+                && !matches!(
+                    tokens.get(index + 1),
+                    Some(TokenTree::Group(group)) if group.delimiter() == proc_macro2::Delimiter::Parenthesis
+                )
+        })
+    }
+
+    /// Collects all identifiers of the input of the macro, including those
+    /// nested in delimited groups
+    pub fn idents(&self) -> Vec<String> {
+        let mut idents = Vec::new();
+        Self::collect_idents(self.mac.tokens.clone(), &mut idents);
+        idents
+    }
+
+    /// Collects the identifiers of the input of the macro which are not
+    /// nested in delimited groups, i.e. not in bodies or parameter lists
+    fn top_level_idents(&self) -> Vec<String> {
+        self.mac
+            .tokens
+            .clone()
+            .into_iter()
+            .filter_map(|token| match token {
+                TokenTree::Ident(ident) => Some(ident.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Collects the identifiers of `tokens` into `idents`, recursively
+    fn collect_idents(tokens: proc_macro2::TokenStream, idents: &mut Vec<String>) {
+        for token in tokens {
+            match token {
+                TokenTree::Ident(ident) => idents.push(ident.to_string()),
+                TokenTree::Group(group) => Self::collect_idents(group.stream(), idents),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invocation(source: &str) -> MacroInvocation {
+        MacroInvocation::from(syn::parse_str::<ItemMacro>(source).unwrap())
+    }
+
+    #[test]
+    fn test_declared_type_is_found_at_the_top_level() {
+        let flags = invocation("bitflags! { pub struct Flags: u32 { const A = 1; } }");
+        assert_eq!(flags.declared_type_name(), Some("Flags".to_owned()));
+        assert_eq!(flags.declared_item_names(), vec!["Flags"]);
+        assert!(flags.declares_public_item());
+
+        let lazy = invocation("lazy_static! { static ref CACHE: Cache = Cache::new(); }");
+        assert_eq!(lazy.declared_type_name(), None);
+        assert_eq!(lazy.declared_item_names(), vec!["CACHE"]);
+        assert!(!lazy.declares_public_item());
+        assert!(
+            !invocation("lazy_static! { pub(crate) static ref A: u8 = 1; }").declares_public_item()
+        );
+
+        let ops = invocation("impl_ops!(Circle, { fn area() {} });");
+        assert_eq!(ops.declared_type_name(), None);
+        assert!(ops.declared_item_names().is_empty());
+        assert_eq!(ops.idents(), vec!["Circle", "fn", "area"]);
+    }
+}
@@ -1,35 +1,103 @@
+mod compile_report;
+mod config;
+mod r#const;
+mod const_placement;
+mod diagnostic;
+mod diagnostic_level;
 mod directory_content;
 mod r#enum;
 mod error;
+mod extern_crate;
+mod file_action;
+mod file_rename;
 mod file_system_node;
+mod foreign_mod;
 mod function;
 mod identifiable;
 mod implementation;
+mod io_action;
+mod macro_invocation;
 mod macro_rules;
+mod module;
+mod module_declaration;
+mod module_declaration_edit;
+mod moved_item;
 mod named_source_items;
 mod node_content;
+mod plan_backup;
+mod planned_file;
+mod reported_file;
 mod rust_file_content;
+mod scan_failure;
+mod scan_filter;
+mod scan_report;
+mod sibling_items;
 mod source_item;
+mod split_outcome;
+mod split_plan;
+mod split_report;
+mod split_strategy;
+mod r#static;
 mod r#struct;
+mod text_edits;
 mod r#trait;
+mod trait_alias;
+mod trait_impl_placement;
+mod trait_impl_policy;
 mod r#type;
+mod type_index;
 mod union;
 mod r#use;
+mod verification;
 
+pub use compile_report::*;
+pub use config::*;
+pub use r#const::*;
+pub use const_placement::*;
+pub use diagnostic::*;
+pub use diagnostic_level::*;
 pub use directory_content::*;
 pub use r#enum::*;
 pub use error::*;
+pub use extern_crate::*;
+pub use file_action::*;
+pub use file_rename::*;
 pub use file_system_node::*;
+pub use foreign_mod::*;
 pub use function::*;
 pub use identifiable::*;
 pub use implementation::*;
+pub use io_action::*;
+pub use macro_invocation::*;
 pub use macro_rules::*;
+pub use module::*;
+pub use module_declaration::*;
+pub use module_declaration_edit::*;
+pub use moved_item::*;
 pub use named_source_items::*;
 pub use node_content::*;
+pub use plan_backup::*;
+pub use planned_file::*;
+pub use reported_file::*;
 pub use rust_file_content::*;
+pub use scan_failure::*;
+pub use scan_filter::*;
+pub use scan_report::*;
+pub use sibling_items::*;
 pub use source_item::*;
+pub use split_outcome::*;
+pub use split_plan::*;
+pub use split_report::*;
+pub use split_strategy::*;
+pub use r#static::*;
 pub use r#struct::*;
+pub use text_edits::*;
 pub use r#trait::*;
+pub use trait_alias::*;
+pub use trait_impl_placement::*;
+pub use trait_impl_policy::*;
 pub use r#type::*;
+pub use type_index::*;
 pub use union::*;
 pub use r#use::*;
+pub use verification::*;
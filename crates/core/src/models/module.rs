@@ -0,0 +1,14 @@
+use crate::prelude::*;
+
+#[derive(Clone, Deref, From)]
+pub struct Module(syn::ItemMod);
+
+impl std::fmt::Debug for Module {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Module")
+            .field(&self.vis)
+            .field(&self.ident)
+            .field(&self.attrs)
+            .finish()
+    }
+}
@@ -0,0 +1,71 @@
+use crate::prelude::*;
+
+/// A child module declared by a module file, together with how the items of
+/// the child are re-exported by it
+#[derive(Clone, Debug, PartialEq, Eq, Getters, Builder)]
+pub struct ModuleDeclaration {
+    /// Name of the child module
+    #[builder(into)]
+    #[getset(get = "pub")]
+    name: String,
+    /// Visibility of the glob re-export of the child's items, e.g. `pub` or
+    /// `pub(crate)`, empty for a private glob import. `None` if the items are
+    /// not re-exported.
+    #[builder(into)]
+    #[getset(get = "pub")]
+    reexport: Option<String>,
+    /// Attributes and doc comments written above the declaration, e.g.
+    /// `#[cfg(test)]`, one per line
+    #[builder(default)]
+    #[getset(get = "pub")]
+    attributes: Vec<String>,
+}
+
+impl ModuleDeclaration {
+    pub const MACRO_USE: &'static str = "#[macro_use]";
+
+    /// A module whose items are glob re-exported publicly, the layout klyv
+    /// generates by default
+    pub fn reexported(name: impl Into<String>) -> Self {
+        Self::builder().name(name).reexport("pub").build()
+    }
+
+    /// A module defining macros, declared with `#[macro_use]` so that its
+    /// macros are textually in scope of the modules declared after it
+    pub fn macro_use(name: impl Into<String>) -> Self {
+        Self::builder()
+            .name(name)
+            .attributes(vec![Self::MACRO_USE.to_owned()])
+            .build()
+    }
+
+    /// Checks if the module is declared with `#[macro_use]`, which must come
+    /// before the modules using its macros
+    pub fn is_macro_use(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|attribute| attribute == Self::MACRO_USE)
+    }
+
+    /// The `mod x;` line declaring the module, preceded by its attributes
+    pub fn declaration_line(&self) -> String {
+        let mut declaration: String = self
+            .attributes
+            .iter()
+            .map(|attribute| format!("{}\n", attribute))
+            .collect();
+        declaration.push_str(&format!("mod {};", self.name));
+        declaration
+    }
+
+    /// The `use x::*;` line re-exporting the module's items, if any
+    pub fn reexport_line(&self) -> Option<String> {
+        self.reexport.as_ref().map(|visibility| {
+            if visibility.is_empty() {
+                format!("use {}::*;", self.name)
+            } else {
+                format!("{} use {}::*;", visibility, self.name)
+            }
+        })
+    }
+}
@@ -0,0 +1,652 @@
+use crate::prelude::*;
+
+/// Changes to the `mod` declarations and glob re-exports of a module file
+/// (`mod.rs`, `lib.rs`, `main.rs` or `<dir>.rs`).
+///
+/// Edits are collected while a split is planned and applied to the final
+/// content of the module file afterwards, so they do not depend on the order
+/// in which files are planned.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Getters)]
+pub struct ModuleDeclarationEdit {
+    /// Modules whose declaration and glob re-export are removed
+    #[getset(get = "pub")]
+    removed: Vec<String>,
+    /// Modules which are declared and re-exported as given, unless already
+    /// declared
+    #[getset(get = "pub")]
+    added: Vec<ModuleDeclaration>,
+    /// Modules whose public glob re-export is narrowed to the given
+    /// visibility, or removed for `None`, keeping their declaration
+    #[getset(get = "pub")]
+    narrowed: IndexMap<String, Option<String>>,
+}
+
+impl ModuleDeclarationEdit {
+    /// Records that a module must be declared and re-exported as given
+    pub fn add(&mut self, declaration: ModuleDeclaration) {
+        self.removed.retain(|removed| removed != declaration.name());
+        if !self
+            .added
+            .iter()
+            .any(|added| added.name() == declaration.name())
+        {
+            self.added.push(declaration);
+        }
+    }
+
+    /// Records that the declaration and re-export of `module` must be removed
+    pub fn remove(&mut self, module: impl Into<String>) {
+        let module = module.into();
+        self.added.retain(|added| added.name() != &module);
+        if !self.removed.contains(&module) {
+            self.removed.push(module);
+        }
+    }
+
+    /// Records that the public glob re-export of `module` must be narrowed to
+    /// `reexport`, or removed if it is `None`
+    pub fn narrow_reexport(&mut self, module: impl Into<String>, reexport: Option<String>) {
+        self.narrowed.insert(module.into(), reexport);
+    }
+
+    /// Applies the edit to the content of a module file.
+    ///
+    /// Files consisting of nothing but declarations are rebuilt in the layout
+    /// klyv generates, otherwise new declarations are inserted after the
+    /// existing ones so that the rest of the file is left untouched.
+    pub fn apply_to(&self, content: &str) -> String {
+        let narrowed = self.with_narrowed_reexports(content);
+        let (lines, removed_at) = self.without_removed_lines(&narrowed);
+
+        let declared: Vec<&str> = lines
+            .iter()
+            .filter_map(|line| Self::declared_module(line))
+            .collect();
+        let mut new_modules: Vec<&ModuleDeclaration> = self
+            .added
+            .iter()
+            .filter(|module| !declared.contains(&module.name().as_str()))
+            .collect();
+        new_modules.sort_by_key(|module| Self::declaration_order(module));
+
+        if new_modules.is_empty() && lines.len() == narrowed.lines().count() {
+            // Nothing to change but narrowed re-exports
+            return narrowed;
+        }
+
+        let reexported: Vec<&str> = lines
+            .iter()
+            .filter_map(|line| Self::glob_reexported_module(line))
+            .collect();
+        let is_plain = lines.iter().all(|line| Self::is_plain_declaration(line))
+            && reexported.iter().all(|module| declared.contains(module));
+
+        if is_plain {
+            let mut modules: Vec<ModuleDeclaration> = declared
+                .iter()
+                .map(|module| {
+                    ModuleDeclaration::builder()
+                        .name(*module)
+                        .maybe_reexport(reexported.contains(module).then_some("pub"))
+                        .build()
+                })
+                .collect();
+            modules.extend(new_modules.into_iter().cloned());
+            modules.sort_by(|a, b| Self::declaration_order(a).cmp(&Self::declaration_order(b)));
+            return Self::declarations_content(&modules);
+        }
+
+        Self::insert_declarations(lines, removed_at, &new_modules)
+    }
+
+    /// Sort key of a declaration: `#[macro_use]` modules come first, so that
+    /// their macros are in scope of the other modules, then by name
+    fn declaration_order(module: &ModuleDeclaration) -> (bool, &String) {
+        (!module.is_macro_use(), module.name())
+    }
+
+    /// Builds the content of a module file declaring and re-exporting `modules`
+    pub fn declarations_content(modules: &[ModuleDeclaration]) -> String {
+        let mut content = String::new();
+        for module in modules {
+            content.push_str(&module.declaration_line());
+            content.push('\n');
+        }
+
+        let reexports: Vec<String> = modules
+            .iter()
+            .filter_map(ModuleDeclaration::reexport_line)
+            .collect();
+        if !reexports.is_empty() {
+            content.push('\n');
+        }
+
+        for reexport in reexports {
+            content.push_str(&reexport);
+            content.push('\n');
+        }
+        content
+    }
+
+    /// Inserts declarations of `new_modules` after the last existing
+    /// declaration, and their re-exports after the last existing glob
+    /// re-export, keeping every other line as is. `#[macro_use]` modules are
+    /// declared before the first existing declaration instead. Without
+    /// existing declarations, they take the place of the first removed one,
+    /// `removed_at`, so that they stay below the macros defined above it.
+    fn insert_declarations(
+        lines: Vec<&str>,
+        removed_at: Option<usize>,
+        new_modules: &[&ModuleDeclaration],
+    ) -> String {
+        let has_declarations = lines
+            .iter()
+            .any(|line| Self::declared_module(line).is_some());
+        let (macro_modules, new_modules): (Vec<&ModuleDeclaration>, Vec<&ModuleDeclaration>) =
+            if has_declarations {
+                new_modules.iter().partition(|module| module.is_macro_use())
+            } else {
+                // Declared first within the new block
+                (Vec::new(), new_modules.to_vec())
+            };
+
+        let declarations: Vec<String> = new_modules
+            .iter()
+            .map(|module| module.declaration_line())
+            .collect();
+        let reexports: Vec<String> = new_modules
+            .iter()
+            .filter_map(|module| module.reexport_line())
+            .collect();
+
+        let mut result: Vec<String> = lines.into_iter().map(str::to_owned).collect();
+
+        if !new_modules.is_empty() {
+            let last_declaration = result
+                .iter()
+                .rposition(|line| Self::declared_module(line).is_some());
+            let last_reexport = result
+                .iter()
+                .rposition(|line| Self::glob_reexported_module(line).is_some());
+
+            match (last_declaration, last_reexport) {
+                (Some(declaration), Some(reexport)) if reexport > declaration => {
+                    result.splice(reexport + 1..reexport + 1, reexports);
+                    result.splice(declaration + 1..declaration + 1, declarations);
+                }
+                (Some(declaration), _) => {
+                    let block = Self::declaration_block(declarations, reexports);
+                    result.splice(declaration + 1..declaration + 1, block);
+                }
+                (None, _) => {
+                    let start = removed_at.unwrap_or_else(|| Self::header_end(&result));
+                    let mut block = Self::declaration_block(declarations, reexports);
+                    if start > 0 && !result[start - 1].trim().is_empty() {
+                        block.insert(0, String::new());
+                    }
+                    if result
+                        .get(start)
+                        .is_some_and(|line| !line.trim().is_empty())
+                    {
+                        block.push(String::new());
+                    }
+                    result.splice(start..start, block);
+                }
+            }
+        }
+
+        if !macro_modules.is_empty() {
+            let first_declaration = Self::first_declaration_start(&result);
+            let declarations = macro_modules.iter().map(|module| module.declaration_line());
+            result.splice(first_declaration..first_declaration, declarations);
+        }
+
+        let mut content = result.join("\n");
+        content.push('\n');
+        content
+    }
+
+    /// Returns the index of the first line of the first declaration among
+    /// `lines`, including the attributes and doc comments above it
+    fn first_declaration_start(lines: &[String]) -> usize {
+        let mut start = lines
+            .iter()
+            .position(|line| Self::declared_module(line).is_some())
+            .unwrap_or(lines.len());
+        while start > 0 && Self::is_outer_attribute(&lines[start - 1]) {
+            start -= 1;
+        }
+        start
+    }
+
+    /// Checks if a line is an outer attribute or doc comment of the item
+    /// below it
+    fn is_outer_attribute(line: &str) -> bool {
+        line.starts_with("#[") || line.starts_with("///")
+    }
+
+    /// Joins declarations and re-exports into one block, separated by a blank
+    /// line if there are re-exports
+    fn declaration_block(declarations: Vec<String>, reexports: Vec<String>) -> Vec<String> {
+        let mut block = declarations;
+        if !reexports.is_empty() {
+            block.push(String::new());
+            block.extend(reexports);
+        }
+        block
+    }
+
+    /// Returns the lines of `content` without those of removed modules and
+    /// their attributes, e.g. `#[macro_use]`, collapsing blank lines which
+    /// become adjacent by the removal, and the index the first removed
+    /// declaration had among them
+    fn without_removed_lines<'a>(&self, content: &'a str) -> (Vec<&'a str>, Option<usize>) {
+        let mut lines: Vec<&str> = Vec::new();
+        let mut removed_at = None;
+        let mut removed_since_blank = false;
+
+        for line in content.lines() {
+            if self.is_removed_line(line) {
+                while lines
+                    .last()
+                    .is_some_and(|last| Self::is_outer_attribute(last))
+                {
+                    lines.pop();
+                }
+                if Self::declared_module(line).is_some() {
+                    removed_at.get_or_insert(lines.len());
+                }
+                removed_since_blank = true;
+                continue;
+            }
+
+            let is_blank = line.trim().is_empty();
+            let previous_is_blank = lines.last().is_some_and(|last| last.trim().is_empty());
+            if is_blank && previous_is_blank && removed_since_blank {
+                // Blank line left behind by a removed declaration
+                continue;
+            }
+
+            if is_blank {
+                removed_since_blank = false;
+            }
+            lines.push(line);
+        }
+        (lines, removed_at)
+    }
+
+    /// Checks if a line declares or glob re-exports a removed module, or
+    /// re-exports a module no longer re-exported
+    fn is_removed_line(&self, line: &str) -> bool {
+        if Self::glob_reexported_module(line)
+            .is_some_and(|module| self.narrowed.get(module).is_some_and(Option::is_none))
+        {
+            return true;
+        }
+
+        Self::declared_module(line)
+            .or_else(|| Self::glob_reexported_module(line))
+            .is_some_and(|module| self.removed.iter().any(|removed| removed == module))
+    }
+
+    /// Returns `content` with the public glob re-exports of narrowed modules
+    /// rewritten to their narrowed visibility. Removed ones are left to
+    /// [`ModuleDeclarationEdit::without_removed_lines`].
+    fn with_narrowed_reexports(&self, content: &str) -> String {
+        let mut narrowed = String::with_capacity(content.len());
+        for line in content.split_inclusive('\n') {
+            let rewritten = Self::glob_reexport(line)
+                .filter(|(visibility, _)| *visibility == "pub")
+                .and_then(|(_, module)| {
+                    let reexport = self.narrowed.get(module)?.clone()?;
+                    ModuleDeclaration::builder()
+                        .name(module)
+                        .reexport(reexport)
+                        .build()
+                        .reexport_line()
+                });
+            match rewritten {
+                Some(rewritten) => {
+                    narrowed.push_str(&rewritten);
+                    if line.ends_with('\n') {
+                        narrowed.push('\n');
+                    }
+                }
+                None => narrowed.push_str(line),
+            }
+        }
+        narrowed
+    }
+
+    /// Checks if a line is a private `mod x;`, a `pub use x::*;` or blank,
+    /// i.e. something klyv would have generated itself
+    fn is_plain_declaration(line: &str) -> bool {
+        line.trim().is_empty()
+            || (line.starts_with("mod ") && Self::declared_module(line).is_some())
+            || (line.starts_with("pub use ") && Self::glob_reexported_module(line).is_some())
+    }
+
+    /// Returns the index of the first line after the header of a file and
+    /// the `extern crate` items following it, which belong at the very top
+    /// of a crate root
+    fn header_end(lines: &[String]) -> usize {
+        let mut end = 0;
+        let mut index = 0;
+        while let Some(line) = lines.get(index) {
+            let trimmed = line.trim();
+            if Self::is_file_header(line) {
+                index += 1;
+                end = index;
+                continue;
+            }
+
+            if !trimmed.starts_with("#[") && !Self::is_extern_crate(trimmed) {
+                // Start of the actual content
+                break;
+            }
+
+            let item = index
+                + lines[index..]
+                    .iter()
+                    .take_while(|line| line.trim().starts_with("#["))
+                    .count();
+            if !lines
+                .get(item)
+                .is_some_and(|line| Self::is_extern_crate(line.trim()))
+            {
+                // Attributes of some other item
+                break;
+            }
+            index = item + 1;
+            end = index;
+        }
+        end
+    }
+
+    /// Checks if a trimmed line is an `extern crate` item, with any visibility
+    fn is_extern_crate(trimmed: &str) -> bool {
+        Self::strip_visibility(trimmed).starts_with("extern crate ")
+    }
+
+    /// Checks if a line belongs to the header of a file which must stay on
+    /// top: inner attributes, inner doc comments and blank lines
+    fn is_file_header(line: &str) -> bool {
+        let trimmed = line.trim();
+        trimmed.is_empty() || trimmed.starts_with("#![") || trimmed.starts_with("//!")
+    }
+
+    /// Returns the module declared by a top level `mod x;` line, with any
+    /// visibility
+    pub fn declared_module(line: &str) -> Option<&str> {
+        Self::module_declaration(line).map(|(_, module)| module)
+    }
+
+    /// Returns the visibility, empty if private, and the module of a top
+    /// level `mod x;` line
+    pub fn module_declaration(line: &str) -> Option<(&str, &str)> {
+        let line = Self::top_level(line)?;
+        let rest = Self::strip_visibility(line);
+        let visibility = line[..line.len() - rest.len()].trim();
+        let module = rest.strip_prefix("mod ")?.strip_suffix(';')?.trim();
+        Self::is_module_name(module).then_some((visibility, module))
+    }
+
+    /// Returns the module glob re-exported by a top level `use x::*;` line,
+    /// with any visibility and an optional `self::` prefix
+    pub fn glob_reexported_module(line: &str) -> Option<&str> {
+        Self::glob_reexport(line).map(|(_, module)| module)
+    }
+
+    /// Returns the visibility, empty if private, and the module of a top
+    /// level `use x::*;` line
+    pub fn glob_reexport(line: &str) -> Option<(&str, &str)> {
+        let line = Self::top_level(line)?;
+        let rest = Self::strip_visibility(line);
+        let visibility = line[..line.len() - rest.len()].trim();
+        let path = rest.strip_prefix("use ")?.strip_suffix("::*;")?.trim();
+        let module = path.strip_prefix("self::").unwrap_or(path);
+        Self::is_module_name(module).then_some((visibility, module))
+    }
+
+    /// Returns the trimmed line unless it is indented, i.e. inside an inline
+    /// module or other block
+    fn top_level(line: &str) -> Option<&str> {
+        if line.starts_with(char::is_whitespace) {
+            // Nested inside a block
+            return None;
+        }
+
+        Some(line.trim_end())
+    }
+
+    /// Strips a leading `pub`, `pub(crate)`, `pub(super)` or `pub(in ...)`
+    fn strip_visibility(line: &str) -> &str {
+        let Some(rest) = line.strip_prefix("pub") else {
+            // No visibility
+            return line;
+        };
+
+        if let Some(restricted) = rest.strip_prefix('(') {
+            return restricted
+                .split_once(')')
+                .map_or(line, |(_, rest)| rest.trim_start());
+        }
+
+        if rest.starts_with(char::is_whitespace) {
+            return rest.trim_start();
+        }
+
+        // An identifier starting with `pub`
+        line
+    }
+
+    /// Checks if `name` is a single identifier naming a child module
+    fn is_module_name(name: &str) -> bool {
+        if ["crate", "self", "super"].contains(&name) {
+            // Path keywords, not child modules
+            return false;
+        }
+
+        let name = name.strip_prefix("r#").unwrap_or(name);
+        !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pure_module_file_is_rebuilt_sorted() {
+        let mut edit = ModuleDeclarationEdit::default();
+        edit.remove("foo");
+        edit.add(ModuleDeclaration::reexported("bar"));
+        edit.add(ModuleDeclaration::reexported("baz"));
+
+        assert_eq!(
+            edit.apply_to("mod foo;\nmod qux;\n\npub use foo::*;\npub use qux::*;\n"),
+            "mod bar;\nmod baz;\nmod qux;\n\npub use bar::*;\npub use baz::*;\npub use qux::*;\n"
+        );
+    }
+
+    #[test]
+    fn test_other_content_is_kept() {
+        let mut edit = ModuleDeclarationEdit::default();
+        edit.remove("foo");
+        edit.add(ModuleDeclaration::reexported("bar"));
+
+        assert_eq!(
+            edit.apply_to(
+                "//! Crate docs\n\npub mod api;\nmod foo;\n\npub use self::foo::*;\n\nfn main() {}\n"
+            ),
+            "//! Crate docs\n\npub mod api;\nmod bar;\n\npub use bar::*;\n\nfn main() {}\n"
+        );
+        assert_eq!(
+            edit.apply_to("//! Crate docs\n\nfn main() {}\n"),
+            "//! Crate docs\n\nmod bar;\n\npub use bar::*;\n\nfn main() {}\n"
+        );
+    }
+
+    #[test]
+    fn test_already_declared_module_is_not_added_again() {
+        let mut edit = ModuleDeclarationEdit::default();
+        edit.add(ModuleDeclaration::reexported("bar"));
+
+        assert_eq!(
+            edit.apply_to("pub mod bar;\n\nfn main() {}\n"),
+            "pub mod bar;\n\nfn main() {}\n"
+        );
+    }
+
+    #[test]
+    fn test_reexport_visibility_is_kept() {
+        let mut edit = ModuleDeclarationEdit::default();
+        edit.add(
+            ModuleDeclaration::builder()
+                .name("bar")
+                .reexport("pub(crate)")
+                .build(),
+        );
+        edit.add(ModuleDeclaration::builder().name("baz").build());
+
+        assert_eq!(
+            edit.apply_to("mod foo;\n\npub use foo::*;\n"),
+            "mod bar;\nmod baz;\nmod foo;\n\npub(crate) use bar::*;\npub use foo::*;\n"
+        );
+        assert_eq!(
+            edit.apply_to("pub mod foo;\n\nfn main() {}\n"),
+            "pub mod foo;\nmod bar;\nmod baz;\n\npub(crate) use bar::*;\n\nfn main() {}\n"
+        );
+    }
+
+    #[test]
+    fn test_public_reexports_are_narrowed_or_removed() {
+        let mut edit = ModuleDeclarationEdit::default();
+        edit.narrow_reexport("foo", Some("pub(crate)".to_owned()));
+        edit.narrow_reexport("bar", None);
+
+        assert_eq!(
+            edit.apply_to(
+                "mod bar;
+mod foo;
+
+pub use bar::*;
+pub use foo::*;
+"
+            ),
+            "mod bar;
+mod foo;
+
+pub(crate) use foo::*;
+"
+        );
+        assert_eq!(
+            edit.apply_to(
+                "mod bar;
+mod foo;
+
+pub use bar::*;
+use foo::*;
+
+fn main() {}
+"
+            ),
+            "mod bar;
+mod foo;
+
+use foo::*;
+
+fn main() {}
+"
+        );
+    }
+
+    #[test]
+    fn test_attributes_are_written_above_the_declaration() {
+        let mut edit = ModuleDeclarationEdit::default();
+        edit.add(
+            ModuleDeclaration::builder()
+                .name("tests")
+                .attributes(vec!["#[cfg(test)]".to_owned()])
+                .build(),
+        );
+
+        assert_eq!(
+            edit.apply_to("mod foo;\n\npub use foo::*;\n"),
+            "mod foo;\n#[cfg(test)]\nmod tests;\n\npub use foo::*;\n"
+        );
+    }
+
+    #[test]
+    fn test_macro_modules_are_declared_first() {
+        let mut edit = ModuleDeclarationEdit::default();
+        edit.add(ModuleDeclaration::reexported("circle"));
+        edit.add(ModuleDeclaration::macro_use("square"));
+
+        assert_eq!(
+            edit.apply_to(
+                "macro_rules! m {\n    () => {};\n}\n\nmod shapes;\n\npub use shapes::*;\n"
+            ),
+            "macro_rules! m {\n    () => {};\n}\n\n#[macro_use]\nmod square;\nmod shapes;\nmod circle;\n\npub use shapes::*;\npub use circle::*;\n"
+        );
+    }
+
+    #[test]
+    fn test_removed_declaration_is_replaced_in_place_with_its_attributes() {
+        let mut edit = ModuleDeclarationEdit::default();
+        edit.remove("shapes");
+        edit.add(ModuleDeclaration::reexported("circle"));
+        edit.add(ModuleDeclaration::macro_use("square"));
+
+        assert_eq!(
+            edit.apply_to("macro_rules! m {\n    () => {};\n}\n\n#[macro_use]\nmod shapes;\n\npub use shapes::*;\n"),
+            "macro_rules! m {\n    () => {};\n}\n\n#[macro_use]\nmod square;\nmod circle;\n\npub use circle::*;\n"
+        );
+    }
+
+    #[test]
+    fn test_declarations_are_written_below_extern_crates() {
+        let mut edit = ModuleDeclarationEdit::default();
+        edit.add(ModuleDeclaration::reexported("foo"));
+
+        assert_eq!(
+            edit.apply_to("#[macro_use]\nextern crate log;\nextern crate core;\n#[derive(Debug)]\npub struct Foo;\n"),
+            "#[macro_use]\nextern crate log;\nextern crate core;\n\nmod foo;\n\npub use foo::*;\n\n#[derive(Debug)]\npub struct Foo;\n"
+        );
+    }
+
+    #[test]
+    fn test_parses_declarations() {
+        assert_eq!(
+            ModuleDeclarationEdit::declared_module("pub(crate) mod foo;"),
+            Some("foo")
+        );
+        assert_eq!(ModuleDeclarationEdit::declared_module("mod foo {"), None);
+        assert_eq!(
+            ModuleDeclarationEdit::glob_reexported_module("pub use self::foo::*;"),
+            Some("foo")
+        );
+        assert_eq!(
+            ModuleDeclarationEdit::glob_reexported_module("use std::fmt::*;"),
+            None
+        );
+        assert_eq!(
+            ModuleDeclarationEdit::glob_reexported_module("pub use crate::*;"),
+            None
+        );
+        assert_eq!(
+            ModuleDeclarationEdit::glob_reexported_module("    pub use inner::*;"),
+            None
+        );
+        assert_eq!(
+            ModuleDeclarationEdit::glob_reexport("pub(crate) use foo::*;"),
+            Some(("pub(crate)", "foo"))
+        );
+        assert_eq!(
+            ModuleDeclarationEdit::glob_reexport("use foo::*;"),
+            Some(("", "foo"))
+        );
+    }
+}
@@ -0,0 +1,18 @@
+use crate::prelude::*;
+
+/// A named item which a split moves out of the file it was written in
+#[derive(Clone, Debug, PartialEq, Eq, Getters, Builder, serde::Serialize)]
+pub struct MovedItem {
+    /// Name of the item, e.g. `Circle` or `area`
+    #[builder(into)]
+    #[getset(get = "pub")]
+    name: String,
+    /// The file the item was written in
+    #[builder(into)]
+    #[getset(get = "pub")]
+    from: PathBuf,
+    /// The file the item is written to
+    #[builder(into)]
+    #[getset(get = "pub")]
+    to: PathBuf,
+}
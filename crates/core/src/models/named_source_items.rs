@@ -1,4 +1,6 @@
 use crate::prelude::*;
+use derive_more::Debug;
+use std::collections::HashSet;
 
 /// A named collection of source items from a single Rust file
 #[derive(Clone, Debug, Getters, Builder)]
@@ -9,4 +11,19 @@ pub struct NamedSourceItems {
     /// Name of the file
     #[getset(get = "pub")]
     name: String,
+
+    /// The original text of the file, used to re-emit items verbatim with
+    /// their regular (non-doc) comments.
+    #[builder(default)]
+    #[debug(skip)]
+    #[getset(get = "pub")]
+    source: String,
+
+    /// Identifiers referenced by the inline modules moved out of the file,
+    /// keyed by module name. The modules may reach items of the file through
+    /// `super::`, so their declarations count as referencing these.
+    #[builder(default)]
+    #[debug(skip)]
+    #[getset(get = "pub")]
+    moved_modules: IndexMap<String, HashSet<String>>,
 }
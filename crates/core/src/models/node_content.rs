@@ -1,9 +1,10 @@
 use std::path::PathBuf;
 
 use crate::prelude::*;
+use getset::MutGetters;
 
 /// Represents the content of a node in the file system
-#[derive(Clone, Debug, Getters, Builder)]
+#[derive(Clone, Debug, Getters, MutGetters, Builder)]
 pub struct NodeContent<C> {
     /// The name of the directory or file
     #[getset(get = "pub")]
@@ -12,16 +13,20 @@ pub struct NodeContent<C> {
     #[getset(get = "pub")]
     path: PathBuf,
     /// The content of the node, which can be a directory or a Rust file
-    #[getset(get = "pub")]
+    #[getset(get = "pub", get_mut = "pub")]
     content: C,
 }
 
 impl FileWritable for DirectoryContent {
-    fn write_to(&self, path: impl AsRef<Path>) -> Result<()> {
+    fn plan_write_to(
+        &self,
+        path: impl AsRef<Path>,
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) -> Result<()> {
+        self.plan_collision_renames(path.as_ref(), config, plan);
         self.content()
             .iter()
-            .map(|node| node.write_to(path.as_ref().join(node.name())))
-            .collect::<Result<Vec<()>>>()?;
-        Ok(())
+            .try_for_each(|node| node.plan_write_to(path.as_ref().join(node.name()), config, plan))
     }
 }
@@ -0,0 +1,62 @@
+use crate::prelude::*;
+
+/// The state of the disk before a plan was executed, used to roll it back,
+/// see [`SplitPlan::backup`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Getters)]
+pub struct PlanBackup {
+    /// Content of every planned file before the plan was executed, `None` if
+    /// the file did not exist
+    #[getset(get = "pub")]
+    files: IndexMap<PathBuf, Option<String>>,
+    /// Directories the plan creates, parents before their children
+    #[getset(get = "pub")]
+    created_dirs: Vec<PathBuf>,
+}
+
+impl PlanBackup {
+    /// Records the original content of `path`
+    pub fn insert_file(&mut self, path: impl AsRef<Path>, content: Option<String>) {
+        self.files.insert(path.as_ref().to_path_buf(), content);
+    }
+
+    /// Records that `dir` does not exist yet and will be created
+    pub fn insert_created_dir(&mut self, dir: impl AsRef<Path>) {
+        let dir = dir.as_ref().to_path_buf();
+        if !self.created_dirs.contains(&dir) {
+            self.created_dirs.push(dir);
+        }
+    }
+
+    /// Restores every backed up file, deletes the files the plan created and
+    /// removes the directories it created
+    pub fn restore(&self) -> Result<()> {
+        for (path, content) in &self.files {
+            match content {
+                Some(content) => {
+                    if let Some(parent) = path.parent() {
+                        fs::create_dir_all(parent)
+                            .map_err(|e| Error::io(IoAction::CreateDirectory, parent, e))?;
+                    }
+                    debug!("Restoring {}", path.display());
+                    fs::write(path, content).map_err(|e| Error::io(IoAction::Restore, path, e))?;
+                }
+                None => {
+                    if !path.exists() {
+                        // Never written
+                        continue;
+                    }
+                    debug!("Removing created {}", path.display());
+                    fs::remove_file(path).map_err(|e| Error::io(IoAction::Delete, path, e))?;
+                }
+            }
+        }
+
+        for dir in self.created_dirs.iter().rev() {
+            if fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_none()) {
+                debug!("Removing created directory {}", dir.display());
+                fs::remove_dir(dir).map_err(|e| Error::io(IoAction::RemoveDirectory, dir, e))?;
+            }
+        }
+        Ok(())
+    }
+}
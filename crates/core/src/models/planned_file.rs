@@ -0,0 +1,27 @@
+use crate::prelude::*;
+
+/// A single file klyv intends to write or delete, with its complete content
+#[derive(Clone, Debug, PartialEq, Eq, Getters, Builder)]
+pub struct PlannedFile {
+    /// The path the file will be written to
+    #[getset(get = "pub")]
+    path: PathBuf,
+    /// The complete content of the file, empty for deleted files
+    #[getset(get = "pub")]
+    content: String,
+    /// Whether the file will be created, overwritten or deleted
+    #[getset(get = "pub")]
+    action: FileAction,
+}
+
+impl PlannedFile {
+    /// Replaces the content of this planned file, keeping its path and action
+    pub fn set_content(&mut self, content: impl Into<String>) {
+        self.content = content.into();
+    }
+
+    /// Changes what will be done with this planned file
+    pub fn set_action(&mut self, action: FileAction) {
+        self.action = action;
+    }
+}
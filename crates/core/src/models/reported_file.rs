@@ -0,0 +1,12 @@
+use crate::prelude::*;
+
+/// A file a split creates, overwrites or deletes, see `SplitReport`
+#[derive(Clone, Debug, PartialEq, Eq, Getters, Builder, serde::Serialize)]
+pub struct ReportedFile {
+    /// The path of the file
+    #[getset(get = "pub")]
+    path: PathBuf,
+    /// Whether the file is created, overwritten or deleted
+    #[getset(get = "pub")]
+    action: FileAction,
+}
@@ -0,0 +1,351 @@
+use crate::prelude::*;
+use quote::ToTokens;
+use std::collections::HashSet;
+use std::ops::Range;
+use syn::UseTree;
+
+impl RustFileContent {
+    /// Finds the impl blocks of this file for a type defined in another file
+    /// of `index`, each with the file defining the type.
+    ///
+    /// The type is the outermost one of the self type found in the tree, see
+    /// [`RustFileContent::self_type_paths`], so impls for a type of this file
+    /// stay where they are.
+    pub(crate) fn foreign_impls(&self, index: &TypeIndex) -> Vec<(&SourceItem, PathBuf)> {
+        self.content()
+            .items()
+            .iter()
+            .filter_map(|item| {
+                let SourceItem::Impl(impl_block) = item else {
+                    // Not an impl block
+                    return None;
+                };
+                let target = self
+                    .self_type_paths(impl_block)
+                    .iter()
+                    .find_map(|path| index.resolve(path, self.path()))?;
+                (target != self.path()).then(|| (item, target.clone()))
+            })
+            .collect()
+    }
+
+    /// The text of the impl block `item` as written in this file
+    pub(crate) fn moved_impl_text(&self, item: &SourceItem) -> String {
+        self.source_item_to_string(item, &[])
+    }
+
+    /// Builds the use statements `target` needs once the impl block `item`
+    /// moved into it: the imports of this file the impl uses, with `self::`
+    /// and `super::` paths made absolute, and imports of the items of this
+    /// file it uses. Imports `target` already has are left out.
+    ///
+    /// Also returns the items of this file the impl uses, which must be
+    /// visible in the whole crate. `None` if a path can not be made absolute
+    /// since this file is not part of a crate.
+    pub(crate) fn moved_impl_imports(
+        &self,
+        item: &SourceItem,
+        target: &RustFileContent,
+        index: &TypeIndex,
+    ) -> Option<(Vec<ItemUse>, Vec<&SourceItem>)> {
+        let defined_in_target: HashSet<String> = target
+            .content()
+            .items()
+            .iter()
+            .filter_map(|item| target.defined_item_name(item))
+            .collect();
+        let needed: HashSet<String> = self
+            .collect_needed_identifiers(std::slice::from_ref(item))
+            .into_iter()
+            .filter(|name| !defined_in_target.contains(name))
+            .collect();
+        let module = index.modules().get(self.path());
+
+        let mut imports = Vec::new();
+        for use_item in self.content().items() {
+            if !self.is_private_use(use_item) {
+                // Not an import
+                continue;
+            }
+
+            let mut narrowed = use_item.clone();
+            if !self.prune_use_item(&mut narrowed, &needed) {
+                // Not used by the impl
+                continue;
+            }
+            let SourceItem::Use(narrowed) = narrowed else {
+                unreachable!("Checked to be a use statement above");
+            };
+            if self.is_glob_only(&narrowed.tree) {
+                // Can't know if the impl needs it
+                continue;
+            }
+
+            let mut absolute: ItemUse = (*narrowed).clone();
+            absolute.tree = self.absolute_use_tree(&narrowed.tree, module)?;
+            imports.push(absolute);
+        }
+
+        // Methods named like an item of this file don't refer to it
+        let defined_in_impl = self.impl_item_names(item);
+        let mut used_items = Vec::new();
+        for defined in self.content().items() {
+            let Some(name) = self.defined_item_name(defined) else {
+                // Defines nothing to import
+                continue;
+            };
+            if !needed.contains(&name) || defined_in_impl.contains(&name) {
+                // Not used by the impl
+                continue;
+            }
+
+            let path: Vec<&str> = std::iter::once("crate")
+                .chain(
+                    self.exposing_module_path(module)?
+                        .iter()
+                        .map(String::as_str),
+                )
+                .chain(std::iter::once(name.as_str()))
+                .collect();
+            imports.push(syn::parse_str(&format!("use {};", path.join("::"))).ok()?);
+            used_items.push(defined);
+        }
+
+        let existing: HashSet<String> = target
+            .content()
+            .items()
+            .iter()
+            .filter_map(|item| match item {
+                SourceItem::Use(use_item) => Some(use_item.to_token_stream().to_string()),
+                _ => None,
+            })
+            .collect();
+        imports.retain(|import| !existing.contains(&import.to_token_stream().to_string()));
+        Some((imports, used_items))
+    }
+
+    /// Plans removing the impl blocks `moved` from this file, widening the
+    /// `widened` items they use to `pub(crate)` and narrowing the use
+    /// statements down to what the remaining items use
+    pub(crate) fn moved_impls_removal(
+        &self,
+        moved: &[&SourceItem],
+        widened: &[&SourceItem],
+    ) -> TextEdits {
+        let source = self.content().source();
+        let mut edits = TextEdits::default();
+
+        for item in moved {
+            if let Some(range) = self.original_source_range(item) {
+                edits.remove(self.removed_lines_range(source, range));
+            }
+        }
+
+        for item in widened {
+            let Some(visibility) = self.item_visibility(item) else {
+                // Visibility can't change
+                continue;
+            };
+            if self.is_crate_visible(visibility) {
+                // Already visible to the file it is used in
+                continue;
+            }
+            let Some(range) = self.visibility_byte_range(self.convert_item_to_token_stream(item))
+            else {
+                // Not found in the original file
+                continue;
+            };
+            let widened = if range.is_empty() {
+                "pub(crate) "
+            } else {
+                "pub(crate)"
+            };
+            edits.replace(range, widened);
+        }
+
+        let items = self.content().items();
+        let remaining: Vec<SourceItem> = items
+            .iter()
+            .filter(|item| !moved.iter().any(|moved| std::ptr::eq(*moved, *item)))
+            .cloned()
+            .collect();
+        let used_before = self.collect_needed_identifiers(items);
+        let used_after = self.collect_needed_identifiers(&remaining);
+
+        for use_item in items.iter().filter(|item| self.is_private_use(item)) {
+            let (mut before, mut after) = (use_item.clone(), use_item.clone());
+            let kept_before = self.prune_use_item(&mut before, &used_before);
+            let kept_after = self.prune_use_item(&mut after, &used_after);
+            let tokens = |item: &SourceItem| self.convert_item_to_token_stream(item).to_string();
+            if kept_before == kept_after && tokens(&before) == tokens(&after) {
+                // Needed as much as before
+                continue;
+            }
+
+            let Some(range) = self.original_source_range(use_item) else {
+                // Not found in the original file
+                continue;
+            };
+            if kept_after {
+                let narrowed = self.format_token_stream(self.convert_item_to_token_stream(&after));
+                edits.replace(range, narrowed.trim_end());
+            } else {
+                edits.remove(self.removed_lines_range(source, range));
+            }
+        }
+        edits
+    }
+
+    /// Plans adding `imports` below the use statements of this file and
+    /// appending the impl blocks `impls` to it
+    pub(crate) fn gathered_impls_insertion(
+        &self,
+        imports: &[ItemUse],
+        impls: &[String],
+    ) -> TextEdits {
+        let source = self.content().source();
+        let items = self.content().items();
+        let mut edits = TextEdits::default();
+
+        if !imports.is_empty() {
+            let lines: String = imports
+                .iter()
+                .map(|import| self.format_token_stream(import.to_token_stream()))
+                .collect();
+            let last_use_end = items
+                .iter()
+                .filter(|item| item.is_use())
+                .filter_map(|item| self.original_source_range(item))
+                .map(|range| range.end)
+                .max();
+            match last_use_end {
+                Some(end) => edits.insert(end, format!("\n{}", lines.trim_end())),
+                None => {
+                    let start = items
+                        .first()
+                        .and_then(|item| self.original_source_range(item))
+                        .map_or(source.len(), |range| range.start);
+                    edits.insert(start, format!("{}\n", lines));
+                }
+            }
+        }
+
+        let mut appended = String::new();
+        if !source.is_empty() && !source.ends_with('\n') {
+            appended.push('\n');
+        }
+        for text in impls {
+            if !source.trim().is_empty() || !appended.is_empty() {
+                appended.push('\n');
+            }
+            appended.push_str(text);
+        }
+        edits.insert(source.len(), appended);
+        edits
+    }
+
+    /// The module path, given the path `module` of this file, through which
+    /// the items of this file stay reachable once it is split: the file
+    /// itself if it is a module root or kept as a facade, or else the parent
+    /// module glob re-exporting its items, see
+    /// [`RustFileContent::original_module_exposure`]
+    fn exposing_module_path<'a>(&self, module: Option<&'a Vec<String>>) -> Option<&'a [String]> {
+        let module = module?;
+        let (_, needs_facade) = self.original_module_exposure();
+        if self.is_module_root() || needs_facade {
+            return Some(module);
+        }
+        module.split_last().map(|(_, parent)| parent)
+    }
+
+    /// The names of the methods, constants and types an impl block defines
+    fn impl_item_names(&self, item: &SourceItem) -> HashSet<String> {
+        let SourceItem::Impl(impl_block) = item else {
+            // Not an impl block
+            return HashSet::new();
+        };
+
+        impl_block
+            .items
+            .iter()
+            .filter_map(|impl_item| match impl_item {
+                syn::ImplItem::Fn(method) => Some(method.sig.ident.to_string()),
+                syn::ImplItem::Const(constant) => Some(constant.ident.to_string()),
+                syn::ImplItem::Type(ty) => Some(ty.ident.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Checks if a use tree imports nothing but globs
+    fn is_glob_only(&self, tree: &UseTree) -> bool {
+        match tree {
+            UseTree::Path(path) => self.is_glob_only(&path.tree),
+            UseTree::Glob(_) => true,
+            UseTree::Group(group) => group.items.iter().all(|tree| self.is_glob_only(tree)),
+            UseTree::Name(_) | UseTree::Rename(_) => false,
+        }
+    }
+
+    /// Rewrites a use tree starting at `self::` or `super::` in the module
+    /// `module` to start at `crate::` instead, `None` if the module is unknown
+    fn absolute_use_tree(&self, tree: &UseTree, module: Option<&Vec<String>>) -> Option<UseTree> {
+        let UseTree::Path(path) = tree else {
+            // A single name or group, not relative
+            return Some(tree.clone());
+        };
+
+        let (levels, rest) = match path.ident.to_string().as_str() {
+            "self" => (0, &*path.tree),
+            "super" => {
+                let mut levels = 1;
+                let mut rest = &*path.tree;
+                while let UseTree::Path(path) = rest
+                    && path.ident == "super"
+                {
+                    levels += 1;
+                    rest = &path.tree;
+                }
+                (levels, rest)
+            }
+            _ => return Some(tree.clone()),
+        };
+
+        let module = module?;
+        let parent = &module[..module.len().checked_sub(levels)?];
+        let prefix: Vec<&str> = std::iter::once("crate")
+            .chain(parent.iter().map(String::as_str))
+            .collect();
+        syn::parse_str(&format!(
+            "{}::{}",
+            prefix.join("::"),
+            rest.to_token_stream()
+        ))
+        .ok()
+    }
+
+    /// Checks if `visibility` makes an item visible in the whole crate
+    fn is_crate_visible(&self, visibility: &syn::Visibility) -> bool {
+        match visibility {
+            syn::Visibility::Public(_) => true,
+            syn::Visibility::Restricted(restricted) => restricted.path.is_ident("crate"),
+            syn::Visibility::Inherited => false,
+        }
+    }
+
+    /// Extends the range of an item to remove to the end of its line and a
+    /// blank line above it, so that no gap is left behind
+    fn removed_lines_range(&self, source: &str, range: Range<usize>) -> Range<usize> {
+        let mut start = range.start;
+        let mut end = range.end;
+        if source[end..].starts_with('\n') {
+            end += 1;
+        }
+        if source[..start].ends_with("\n\n") {
+            start -= 1;
+        } else if start == 0 && source[end..].starts_with('\n') {
+            end += 1;
+        }
+        start..end
+    }
+}
@@ -1,11 +1,12 @@
-use super::special_case_utils::SpecialCaseConfig;
 use crate::prelude::*;
 
 impl RustFileContent {
     /// Checks if this is a main.rs file that should receive special treatment
-    pub(super) fn is_main_rs_special_case(&self) -> bool {
+    pub(super) fn is_main_rs_special_case(&self, config: &Config) -> bool {
         let file_name = self.content().name();
-        file_name == Self::MAIN_RS && self.has_multiple_types_or_functions_for_main()
+        config.strategy() == &SplitStrategy::Organized
+            && file_name == Self::MAIN_RS
+            && self.has_multiple_types_or_functions_for_main()
     }
 
     /// Checks if the main.rs file has types or functions that warrant special organization
@@ -22,23 +23,34 @@ impl RustFileContent {
     }
 
     /// Handles the special main.rs case by organizing into models and logic folders
-    pub(super) fn handle_main_rs_special_case(&self, base_path: &Path) -> Result<()> {
+    pub(super) fn handle_main_rs_special_case(
+        &self,
+        base_path: &Path,
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) -> Result<()> {
         let items = self.content().items();
-        let (type_items, logic_items, main_items) = self.categorize_main_rs_items(items);
-        let config = SpecialCaseConfig::main_rs();
+        let (type_items, logic_items, main_items) = self.categorize_main_rs_items(items, config);
 
         // Create models and logic folders using shared utilities
-        self.create_types_folder_with_config(&type_items, base_path, &config)?;
-        self.create_logic_folder_with_config(&logic_items, base_path, &config)?;
+        let siblings = self.special_case_sibling_items(
+            base_path,
+            (&type_items, &logic_items, &main_items),
+            config,
+            plan,
+        );
+        self.create_types_folder_with_config(&type_items, base_path, &siblings, config, plan);
+        self.create_logic_folder_with_config(&logic_items, base_path, &siblings, config, plan);
 
         // Create the new main.rs with module declarations and main function
         self.create_main_file_with_prelude(
             base_path,
-            Self::MAIN_RS,
             !type_items.is_empty(),
             !logic_items.is_empty(),
             &main_items,
-        )?;
+            config,
+            plan,
+        );
 
         Ok(())
     }
@@ -47,6 +59,7 @@ impl RustFileContent {
     fn categorize_main_rs_items(
         &self,
         items: &[SourceItem],
+        config: &Config,
     ) -> (Vec<SourceItem>, Vec<SourceItem>, Vec<SourceItem>) {
         let mut type_items = Vec::new();
         let mut logic_items = Vec::new();
@@ -60,6 +73,11 @@ impl RustFileContent {
                 SourceItem::Function(_) | SourceItem::MacroRules(_) => {
                     logic_items.push(item.clone());
                 }
+                _ if self.is_attached_item(item) => self.categorize_attached_item(
+                    item,
+                    config,
+                    (&mut type_items, &mut logic_items, &mut main_items),
+                ),
                 _ if self.is_type_item(item) => {
                     type_items.push(item.clone());
                 }
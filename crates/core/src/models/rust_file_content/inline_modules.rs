@@ -0,0 +1,268 @@
+use crate::prelude::*;
+use quote::ToTokens;
+use std::collections::HashSet;
+use std::ops::Range;
+
+impl RustFileContent {
+    /// Moves the inline modules of this file into directories of their own
+    /// and splits them like any other file.
+    ///
+    /// Each `mod foo { ... }` becomes `foo/mod.rs` in the directory of this
+    /// file's module, holding the module's items, and is replaced by a
+    /// `mod foo;` declaration keeping the module's attributes and visibility.
+    /// Test modules (`#[cfg(test)]`) and modules without anything to split,
+    /// like a prelude of re-exports, stay where they are.
+    ///
+    /// The references of each moved module are recorded for its new file, so
+    /// that the items split out of a module private to the crate are only
+    /// re-exported as far as paths of the crate use them.
+    ///
+    /// Returns this file with its inline modules replaced by declarations,
+    /// planned to `base_path`, or `None` if it has no inline modules to move.
+    pub(super) fn plan_inline_modules(
+        &self,
+        base_path: &Path,
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) -> Result<Option<RustFileContent>> {
+        let source = self.content().source();
+        let mut replacements: Vec<(Range<usize>, String)> = Vec::new();
+        let mut moved_modules = IndexMap::new();
+
+        let moving: Vec<_> = self
+            .content()
+            .items()
+            .iter()
+            .filter_map(|item| match item {
+                // Tests stay next to the code they test, and nothing would
+                // move out of modules without items to split
+                SourceItem::Module(module)
+                    if !self.is_test_module(module) && self.has_items_to_split(module) =>
+                {
+                    Some((module, self.inline_module_parts(module)?))
+                }
+                _ => None,
+            })
+            .collect();
+        if moving.is_empty() {
+            // Nothing moves, or spans do not point at the modules in this file
+            return Ok(None);
+        }
+
+        // What the moving modules reference is no longer referenced by this file
+        let staying: Vec<SourceItem> = self
+            .content()
+            .items()
+            .iter()
+            .filter(|item| {
+                !matches!(item, SourceItem::Module(module)
+                    if moving.iter().any(|(moved, _)| moved.ident == module.ident))
+            })
+            .cloned()
+            .collect();
+        plan.record_references_of(self.path(), self.collect_imported_identifiers(&staying));
+
+        for (module, (range, declaration, body)) in moving {
+            let module_file = self
+                .child_module_dir(base_path)
+                .join(module.ident.to_string())
+                .join(Self::MOD_RS);
+            debug!(
+                "Moving inline module {} to {}",
+                module.ident,
+                module_file.display()
+            );
+            let module_content = Self::parsed(
+                module_file.clone(),
+                self.module_body(&body),
+                IndexMap::new(),
+            )?;
+            plan.add_file(&module_file, module_content.content().source().clone());
+            plan.record_inline_module(
+                self.path(),
+                module,
+                &module_file,
+                module_content.collect_imported_identifiers(module_content.content().items()),
+            );
+            module_content.handle_file_writing_strategy(&module_file, config, plan)?;
+            moved_modules.insert(
+                module.ident.to_string(),
+                module_content.collect_used_identifiers(module_content.content().items()),
+            );
+
+            replacements.push((range, declaration));
+        }
+
+        let mut rewritten = String::new();
+        let mut position = 0;
+        for (range, declaration) in replacements {
+            rewritten.push_str(&source[position..range.start]);
+            rewritten.push_str(&declaration);
+            position = range.end;
+        }
+        rewritten.push_str(&source[position..]);
+
+        let rewritten = Self::parsed(self.path().clone(), rewritten, moved_modules)?;
+        plan.add_file(base_path, rewritten.content().source().clone());
+        Ok(Some(rewritten))
+    }
+
+    /// Checks if `module` contains types or logic, directly or in its own
+    /// inline modules
+    fn has_items_to_split(&self, module: &syn::ItemMod) -> bool {
+        let Some((_, items)) = &module.content else {
+            // Declaration only
+            return false;
+        };
+
+        items.iter().any(|item| match item {
+            syn::Item::Fn(_) | syn::Item::Macro(_) | syn::Item::Impl(_) => true,
+            syn::Item::Struct(_)
+            | syn::Item::Enum(_)
+            | syn::Item::Trait(_)
+            | syn::Item::Type(_)
+            | syn::Item::Union(_) => true,
+            syn::Item::Mod(nested) => self.has_items_to_split(nested),
+            _ => false,
+        })
+    }
+
+    /// Splits an inline module into the byte range it covers in the original
+    /// file, its declaration (`mod foo;` preceded by its attributes and
+    /// visibility, as written) and the text of its body
+    fn inline_module_parts(&self, module: &syn::ItemMod) -> Option<(Range<usize>, String, String)> {
+        let source = self.content().source();
+        let (brace, _) = module.content.as_ref()?;
+        let start = module
+            .to_token_stream()
+            .into_iter()
+            .next()?
+            .span()
+            .byte_range()
+            .start;
+        let open = brace.span.open().byte_range();
+        let close = brace.span.close().byte_range();
+
+        if !source.get(start..close.end)?.ends_with('}') || open.is_empty() {
+            // Synthesized tokens without a location in the original file
+            return None;
+        }
+
+        let declaration = format!("{};", source[start..open.start].trim_end());
+        let body = source.get(open.end..close.start)?.to_owned();
+        Some((start..close.end, declaration, body))
+    }
+
+    /// The content of the `mod.rs` of an inline module with the body `body`,
+    /// dedented and without the blank lines around it
+    fn module_body(&self, body: &str) -> String {
+        let body = self.dedent(body.trim_matches('\n'));
+        if body.trim().is_empty() {
+            return String::new();
+        }
+        format!("{}\n", body.trim_end())
+    }
+
+    /// Directory the child modules of this file's module live in: the
+    /// directory of a module root, `<dir>/<name>/` for other files
+    fn child_module_dir(&self, base_path: &Path) -> PathBuf {
+        let output_dir = self.determine_output_directory(base_path);
+        if self.is_module_root() {
+            return output_dir;
+        }
+        output_dir.join(self.module_name())
+    }
+
+    /// Parses `source` as the Rust file at `path`, out of which the inline
+    /// modules `moved_modules` were moved
+    pub(crate) fn parsed(
+        path: PathBuf,
+        source: String,
+        moved_modules: IndexMap<String, HashSet<String>>,
+    ) -> Result<RustFileContent> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let items = parse_file().path(&path).content(&source).call()?;
+
+        Ok(NodeContent::builder()
+            .name(name.clone())
+            .path(path)
+            .content(
+                NamedSourceItems::builder()
+                    .name(name)
+                    .items(items)
+                    .source(source)
+                    .moved_modules(moved_modules)
+                    .build(),
+            )
+            .build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_module_is_replaced_by_its_declaration() {
+        let file = RustFileContent::parsed(
+            PathBuf::from("src/foo.rs"),
+            "// Kept above\n/// Docs\n#[cfg(feature = \"x\")]\npub(crate) mod bar {\n    // Inside\n    pub struct Bar;\n}\n".to_owned(),
+            IndexMap::new(),
+        )
+        .unwrap();
+        let SourceItem::Module(module) = &file.content().items()[0] else {
+            panic!("Expected an inline module");
+        };
+
+        let (range, declaration, body) = file.inline_module_parts(module).unwrap();
+        assert_eq!(&file.content().source()[..range.start], "// Kept above\n");
+        assert_eq!(
+            declaration,
+            "/// Docs\n#[cfg(feature = \"x\")]\npub(crate) mod bar;"
+        );
+        assert_eq!(file.module_body(&body), "// Inside\npub struct Bar;\n");
+        assert_eq!(
+            file.child_module_dir(Path::new("src/foo.rs")),
+            PathBuf::from("src/foo")
+        );
+    }
+
+    #[test]
+    fn test_module_body_keeps_multi_line_literals() {
+        let file = RustFileContent::parsed(
+            PathBuf::from("src/lib.rs"),
+            "pub mod geo {\n    pub fn banner() -> &'static str {\n        \"first\n        second\"\n    }\n}\n".to_owned(),
+            IndexMap::new(),
+        )
+        .unwrap();
+        let SourceItem::Module(module) = &file.content().items()[0] else {
+            panic!("Expected an inline module");
+        };
+
+        let (_, _, body) = file.inline_module_parts(module).unwrap();
+        assert_eq!(
+            file.module_body(&body),
+            "pub fn banner() -> &'static str {\n    \"first\n        second\"\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_test_modules_and_preludes_stay_inline() {
+        let file = RustFileContent::parsed(
+            PathBuf::from("src/lib.rs"),
+            "pub mod prelude {\n    pub use crate::*;\n}\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn it_works() {}\n}\n".to_owned(),
+            IndexMap::new(),
+        )
+        .unwrap();
+        let mut plan = SplitPlan::default();
+
+        let rewritten = file
+            .plan_inline_modules(Path::new("src/lib.rs"), &Config::default(), &mut plan)
+            .unwrap();
+        assert!(rewritten.is_none());
+        assert!(plan.is_empty());
+    }
+}
@@ -0,0 +1,563 @@
+use crate::prelude::*;
+use quote::ToTokens;
+use std::collections::HashSet;
+use std::ops::Range;
+use syn::UseTree;
+use syn::ext::IdentExt;
+
+impl RustFileContent {
+    /// Names of the child modules of this module file which can be merged
+    /// into it: modules declared without attributes or body, whose items are
+    /// glob re-exported, and which are single files in `dir` without child
+    /// modules of their own
+    pub(crate) fn mergeable_children(&self, dir: &Path) -> Vec<String> {
+        let items = self.content().items();
+        let reexported: HashSet<String> = items
+            .iter()
+            .filter_map(|item| match item {
+                SourceItem::Use(use_item) => match &use_item.tree {
+                    UseTree::Path(path) if matches!(*path.tree, UseTree::Glob(_)) => {
+                        Some(path.ident.unraw().to_string())
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+
+        items
+            .iter()
+            .filter_map(|item| {
+                let SourceItem::Unsplittable(syn::Item::Mod(module)) = item else {
+                    // Not a module declaration
+                    return None;
+                };
+                if module.content.is_some() || !module.attrs.is_empty() {
+                    // Inline, or declared with a `#[path]`, `#[cfg]` or docs
+                    return None;
+                }
+
+                let name = module.ident.unraw().to_string();
+                if !reexported.contains(&name) {
+                    // Items are reached through the module, merging changes their paths
+                    return None;
+                }
+
+                let file = dir.join(format!("{}{}", name, Self::RS_EXTENSION));
+                (file.is_file() && !dir.join(&name).exists()).then_some(name)
+            })
+            .collect()
+    }
+
+    /// Builds the content of this module file with the items of the child
+    /// modules `children` merged into it, in place of their declarations.
+    ///
+    /// The use statements of every file are deduplicated and come first,
+    /// followed by the remaining module declarations of this file, then the
+    /// macros, types, other items, impls and inline modules of this file and
+    /// the children, in that order. Imports of the merged
+    /// modules and of items now defined in this file, however the crate
+    /// reaches them, are dropped, and
+    /// `pub(super)` visibilities of the children are narrowed to what they
+    /// meant in the child.
+    ///
+    /// Fails if a name would be defined or imported more than once.
+    pub(crate) fn merged_content(&self, children: &[RustFileContent]) -> Result<String> {
+        let merged: HashSet<String> = children
+            .iter()
+            .map(|child| {
+                child
+                    .content()
+                    .name()
+                    .trim_end_matches(Self::RS_EXTENSION)
+                    .to_owned()
+            })
+            .collect();
+        let crate_module = self.crate_module_path();
+
+        let mut uses = Vec::new();
+        let mut seen_uses = HashSet::new();
+        let mut kept = Vec::new();
+        let mut buckets: [Vec<(&RustFileContent, &SourceItem, String)>; 5] = Default::default();
+        for item in self.content().items() {
+            match item {
+                SourceItem::Unsplittable(syn::Item::Mod(module))
+                    if module.content.is_none()
+                        && merged.contains(&module.ident.unraw().to_string()) =>
+                {
+                    // Merged below
+                }
+                SourceItem::Use(use_item) => {
+                    let tree = self.merged_use_tree(&use_item.tree, &merged, &crate_module, false);
+                    Self::push_unique_use(&mut uses, &mut seen_uses, use_item, tree);
+                }
+                SourceItem::Unsplittable(syn::Item::Mod(_)) | SourceItem::ExternCrate(_) => {
+                    // Declarations stay with the use statements
+                    kept.push(item.clone())
+                }
+                _ => buckets[Self::merge_bucket(item)].push((
+                    self,
+                    item,
+                    self.source_item_to_string(item, &[]),
+                )),
+            }
+        }
+
+        for child in children {
+            for item in child.content().items() {
+                if let SourceItem::Use(use_item) = item {
+                    let tree = self.merged_use_tree(&use_item.tree, &merged, &crate_module, true);
+                    Self::push_unique_use(&mut uses, &mut seen_uses, use_item, tree);
+                    continue;
+                }
+                let changes = child.narrowed_visibility_changes(item);
+                buckets[Self::merge_bucket(item)].push((
+                    child,
+                    item,
+                    child.source_item_to_string(item, &changes),
+                ));
+            }
+        }
+
+        let merged_items: Vec<(&RustFileContent, &SourceItem, String)> =
+            buckets.into_iter().flatten().collect();
+        let defined: HashSet<String> = kept
+            .iter()
+            .filter_map(|item| self.defined_item_name(item))
+            .chain(
+                merged_items
+                    .iter()
+                    .filter_map(|(file, item, _)| file.defined_item_name(item)),
+            )
+            .collect();
+        let uses = Self::without_defined_imports(uses, &defined);
+        self.check_merge_conflicts(&uses, &kept, &merged_items)?;
+
+        let source = self.content().source();
+        let header_end = self
+            .content()
+            .items()
+            .iter()
+            .filter_map(|item| self.original_source_range(item))
+            .map(|range| range.start)
+            .min()
+            .unwrap_or(source.len());
+        let mut content = source[..header_end].trim_end().to_owned();
+        if !content.is_empty() {
+            content.push_str("\n\n");
+        }
+
+        let mut root_items = uses;
+        root_items.extend(kept);
+        self.push_items_with_spacing(&mut content, &root_items, None);
+        for (_, _, text) in &merged_items {
+            content.push_str(text);
+            content.push('\n');
+        }
+
+        let mut content = content.trim_end().to_owned();
+        content.push('\n');
+        Ok(content)
+    }
+
+    /// The position of the group `item` is merged in: macros, types, other
+    /// items, impls and then inline modules, like `mod tests`
+    fn merge_bucket(item: &SourceItem) -> usize {
+        match item {
+            SourceItem::MacroRules(_) => 0,
+            SourceItem::Struct(_)
+            | SourceItem::Enum(_)
+            | SourceItem::Trait(_)
+            | SourceItem::Type(_)
+            | SourceItem::Union(_) => 1,
+            SourceItem::MacroInvocation(invocation)
+                if invocation.declared_type_name().is_some() =>
+            {
+                1
+            }
+            SourceItem::Impl(_) => 3,
+            SourceItem::Module(_) => 4,
+            _ => 2,
+        }
+    }
+
+    /// Adds the use statement `use_item` with its tree replaced by `tree` to
+    /// `uses`, unless nothing is left of it or it was added before
+    fn push_unique_use(
+        uses: &mut Vec<SourceItem>,
+        seen: &mut HashSet<String>,
+        use_item: &ItemUse,
+        tree: Option<UseTree>,
+    ) {
+        let Some(tree) = tree else {
+            // Nothing left to import
+            return;
+        };
+
+        let mut rewritten = use_item.clone();
+        rewritten.tree = tree;
+        if seen.insert(rewritten.to_token_stream().to_string()) {
+            uses.push(SourceItem::r#use(rewritten));
+        }
+    }
+
+    /// Rewrites the use tree `tree` of this file, or of a child module if
+    /// `in_child`, for the module the children in `merged` are merged into.
+    ///
+    /// In a child `self::` and `super::` both point at the merged module, as
+    /// do `crate::` paths through `crate_module`. Imports of the merged
+    /// modules and of single items of the merged module are dropped, since
+    /// these are defined in it now, other paths into it start at `self::`.
+    /// `None` if nothing is left.
+    fn merged_use_tree(
+        &self,
+        tree: &UseTree,
+        merged: &HashSet<String>,
+        crate_module: &Option<Vec<String>>,
+        in_child: bool,
+    ) -> Option<UseTree> {
+        match tree {
+            UseTree::Path(path) if path.ident == "self" || (in_child && path.ident == "super") => {
+                self.module_relative_use_tree(&path.tree, merged)
+            }
+            UseTree::Path(path) if path.ident == "crate" => {
+                let Some(module) = crate_module else {
+                    // Not part of a crate
+                    return Some(tree.clone());
+                };
+                let mut rest = &*path.tree;
+                for segment in module {
+                    match rest {
+                        UseTree::Path(path) if path.ident.unraw() == segment => rest = &path.tree,
+                        _ => return Some(tree.clone()),
+                    }
+                }
+                self.module_relative_use_tree(rest, merged)
+            }
+            UseTree::Path(path)
+                if !in_child && merged.contains(&path.ident.unraw().to_string()) =>
+            {
+                None
+            }
+            UseTree::Group(group) => {
+                let items: Vec<UseTree> = group
+                    .items
+                    .iter()
+                    .filter_map(|tree| self.merged_use_tree(tree, merged, crate_module, in_child))
+                    .collect();
+                (!items.is_empty()).then(|| {
+                    UseTree::Group(syn::UseGroup {
+                        brace_token: group.brace_token,
+                        items: items.into_iter().collect(),
+                    })
+                })
+            }
+            _ => Some(tree.clone()),
+        }
+    }
+
+    /// Rewrites `tree`, a path relative to the merged module, see
+    /// [`RustFileContent::merged_use_tree`]
+    fn module_relative_use_tree(
+        &self,
+        tree: &UseTree,
+        merged: &HashSet<String>,
+    ) -> Option<UseTree> {
+        let relative = self.kept_relative_use_tree(tree, merged)?;
+        match &relative {
+            UseTree::Path(path) if path.ident == "super" => Some(relative),
+            _ => Some(Self::prefixed_use_tree(relative, "self", 1)),
+        }
+    }
+
+    /// What is left of `tree`, relative to the merged module, once the
+    /// merged modules and single items are dropped
+    fn kept_relative_use_tree(&self, tree: &UseTree, merged: &HashSet<String>) -> Option<UseTree> {
+        match tree {
+            UseTree::Path(path) if merged.contains(&path.ident.unraw().to_string()) => None,
+            UseTree::Path(_) | UseTree::Rename(_) => Some(tree.clone()),
+            UseTree::Name(_) | UseTree::Glob(_) => None,
+            UseTree::Group(group) => {
+                let items: Vec<UseTree> = group
+                    .items
+                    .iter()
+                    .filter_map(|tree| self.kept_relative_use_tree(tree, merged))
+                    .collect();
+                (!items.is_empty()).then(|| {
+                    UseTree::Group(syn::UseGroup {
+                        brace_token: group.brace_token,
+                        items: items.into_iter().collect(),
+                    })
+                })
+            }
+        }
+    }
+
+    /// Drops the imports of `uses` through paths of the crate which name a
+    /// single item of `defined`, the items now defined in the merged file,
+    /// whichever path reached them, e.g. `use crate::Circle;` for a `Circle`
+    /// re-exported at the crate root
+    fn without_defined_imports(
+        uses: Vec<SourceItem>,
+        defined: &HashSet<String>,
+    ) -> Vec<SourceItem> {
+        let mut kept = Vec::new();
+        let mut seen = HashSet::new();
+        for item in uses {
+            let SourceItem::Use(use_item) = &item else {
+                // Not an import
+                kept.push(item);
+                continue;
+            };
+
+            let tree = match &use_item.tree {
+                UseTree::Path(path)
+                    if ["crate", "self", "super"]
+                        .iter()
+                        .any(|root| path.ident == root) =>
+                {
+                    Self::without_defined_names(&use_item.tree, defined)
+                }
+                tree => Some(tree.clone()),
+            };
+            Self::push_unique_use(&mut kept, &mut seen, use_item, tree);
+        }
+        kept
+    }
+
+    /// What is left of `tree` once the single names of `defined` are dropped,
+    /// `None` if nothing is
+    fn without_defined_names(tree: &UseTree, defined: &HashSet<String>) -> Option<UseTree> {
+        match tree {
+            UseTree::Path(path) => {
+                let rest = Self::without_defined_names(&path.tree, defined)?;
+                let mut path = path.clone();
+                path.tree = Box::new(rest);
+                Some(UseTree::Path(path))
+            }
+            UseTree::Name(name) if defined.contains(&name.ident.unraw().to_string()) => None,
+            UseTree::Group(group) => {
+                let items: Vec<UseTree> = group
+                    .items
+                    .iter()
+                    .filter_map(|tree| Self::without_defined_names(tree, defined))
+                    .collect();
+                (!items.is_empty()).then(|| {
+                    UseTree::Group(syn::UseGroup {
+                        brace_token: group.brace_token,
+                        items: items.into_iter().collect(),
+                    })
+                })
+            }
+            _ => Some(tree.clone()),
+        }
+    }
+
+    /// Fails if any name is defined or imported more than once among `uses`
+    /// and `kept` of this file and the `merged_items`
+    fn check_merge_conflicts(
+        &self,
+        uses: &[SourceItem],
+        kept: &[SourceItem],
+        merged_items: &[(&RustFileContent, &SourceItem, String)],
+    ) -> Result<()> {
+        let imported = uses.iter().flat_map(|item| match item {
+            SourceItem::Use(use_item) => Self::imported_names(&use_item.tree, None),
+            _ => Vec::new(),
+        });
+        let defined = kept
+            .iter()
+            .filter_map(|item| self.defined_item_name(item))
+            .chain(
+                merged_items
+                    .iter()
+                    .filter_map(|(file, item, _)| file.defined_item_name(item)),
+            );
+
+        let mut names = HashSet::new();
+        for name in imported.chain(defined) {
+            if !names.insert(name.clone()) {
+                return Err(Error::MergeConflict {
+                    path: self.path().clone(),
+                    name,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// The names `tree` brings into scope, globs left out, with `parent`
+    /// being the last path segment before `tree`
+    fn imported_names(tree: &UseTree, parent: Option<&syn::Ident>) -> Vec<String> {
+        match tree {
+            UseTree::Path(path) => Self::imported_names(&path.tree, Some(&path.ident)),
+            UseTree::Name(name) if name.ident == "self" => {
+                parent.map(|ident| ident.to_string()).into_iter().collect()
+            }
+            UseTree::Name(name) => vec![name.ident.to_string()],
+            UseTree::Rename(rename) if rename.rename == "_" => Vec::new(),
+            UseTree::Rename(rename) => vec![rename.rename.to_string()],
+            UseTree::Glob(_) => Vec::new(),
+            UseTree::Group(group) => group
+                .items
+                .iter()
+                .flat_map(|tree| Self::imported_names(tree, parent))
+                .collect(),
+        }
+    }
+
+    /// Collects the visibility changes `item` of a merged child module needs:
+    /// `pub(super)` becomes private and `pub(in super::super)` becomes
+    /// `pub(super)`, for the item itself, the fields of a struct and the
+    /// methods and constants of an inherent impl
+    fn narrowed_visibility_changes(&self, item: &SourceItem) -> Vec<(Range<usize>, String)> {
+        let mut visibilities: Vec<(&syn::Visibility, proc_macro2::TokenStream)> = Vec::new();
+        if let Some(visibility) = self.item_visibility(item) {
+            visibilities.push((visibility, self.convert_item_to_token_stream(item)));
+        }
+        match item {
+            SourceItem::Struct(struct_item) => {
+                for field in &struct_item.fields {
+                    visibilities.push((&field.vis, field.to_token_stream()));
+                }
+            }
+            SourceItem::Impl(impl_block) if impl_block.trait_.is_none() => {
+                for impl_item in &impl_block.items {
+                    match impl_item {
+                        syn::ImplItem::Fn(method) => {
+                            visibilities.push((&method.vis, impl_item.to_token_stream()))
+                        }
+                        syn::ImplItem::Const(constant) => {
+                            visibilities.push((&constant.vis, impl_item.to_token_stream()))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let source = self.content().source();
+        visibilities
+            .into_iter()
+            .filter_map(|(visibility, tokens)| {
+                let narrowed = Self::narrowed_visibility(visibility)?;
+                let range = self.visibility_byte_range(tokens)?;
+                if narrowed.is_empty() && source[range.end..].starts_with(' ') {
+                    // Drop the space after the removed visibility as well
+                    return Some((range.start..range.end + 1, narrowed));
+                }
+                Some((range, narrowed))
+            })
+            .collect()
+    }
+
+    /// The visibility `visibility` of a child module item becomes once merged
+    /// into its parent, `None` if it stays the same
+    fn narrowed_visibility(visibility: &syn::Visibility) -> Option<String> {
+        let syn::Visibility::Restricted(restricted) = visibility else {
+            // Private or public
+            return None;
+        };
+        if restricted.in_token.is_none() && restricted.path.is_ident("super") {
+            return Some(String::new());
+        }
+
+        let segments: Vec<String> = restricted
+            .path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect();
+        if restricted.in_token.is_none()
+            || segments.len() < 2
+            || segments.iter().any(|s| s != "super")
+        {
+            // Not relative to the parent
+            return None;
+        }
+        Some(match segments.len() {
+            2 => "pub(super)".to_owned(),
+            levels => format!("pub(in {})", vec!["super"; levels - 1].join("::")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rust_file(path: &str, source: &str) -> RustFileContent {
+        RustFileContent::parsed(PathBuf::from(path), source.to_owned(), IndexMap::new()).unwrap()
+    }
+
+    #[test]
+    fn test_merged_uses_are_deduplicated_and_relative_to_the_module() {
+        let module = rust_file(
+            "shapes/mod.rs",
+            "mod circle;\nmod kind;\n\npub use circle::*;\npub use kind::*;\nuse std::fmt;\n",
+        );
+        let circle = rust_file(
+            "shapes/circle.rs",
+            "use std::fmt;\nuse super::kind::Kind;\nuse super::super::Config;\nuse super::{other::Other, kind};\n\npub struct Circle(Kind);\n",
+        );
+        let kind = rust_file("shapes/kind.rs", "pub enum Kind {}\n");
+
+        let merged = module.merged_content(&[circle, kind]).unwrap();
+        assert_eq!(
+            merged,
+            "use std::fmt;\nuse super::Config;\nuse self::other::Other;\n\npub struct Circle(Kind);\n\npub enum Kind {}\n"
+        );
+    }
+
+    #[test]
+    fn test_imports_of_merged_items_through_the_crate_root_are_dropped() {
+        let module = rust_file(
+            "shapes/mod.rs",
+            "mod circle;\nmod square;\n\npub use circle::*;\npub use square::*;\n",
+        );
+        let circle = rust_file("shapes/circle.rs", "pub struct Circle;\n");
+        let square = rust_file(
+            "shapes/square.rs",
+            "use crate::{Circle, Config};\n\npub struct Square(Circle);\n",
+        );
+
+        let merged = module.merged_content(&[circle, square]).unwrap();
+        assert_eq!(
+            merged,
+            "use crate::Config;\n\npub struct Circle;\n\npub struct Square(Circle);\n"
+        );
+    }
+
+    #[test]
+    fn test_items_of_the_module_file_are_ordered_like_those_of_the_children() {
+        let module = rust_file(
+            "shapes/mod.rs",
+            "mod circle;\nmod other;\n\npub use circle::*;\n\nimpl Shape {}\n\npub struct Shape;\n\n#[cfg(test)]\nmod tests {}\n",
+        );
+        let circle = rust_file("shapes/circle.rs", "impl Circle {}\n\npub struct Circle;\n");
+
+        let merged = module.merged_content(&[circle]).unwrap();
+        assert_eq!(
+            merged,
+            "mod other;\n\npub struct Shape;\n\npub struct Circle;\n\nimpl Shape {}\n\nimpl Circle {}\n\n#[cfg(test)]\nmod tests {}\n"
+        );
+    }
+
+    #[test]
+    fn test_merging_the_same_name_twice_fails() {
+        let module = rust_file(
+            "shapes/mod.rs",
+            "mod a;\nmod b;\n\npub use a::*;\npub use b::*;\n",
+        );
+        let a = rust_file("shapes/a.rs", "pub fn area() {}\n");
+        let b = rust_file("shapes/b.rs", "pub fn area() {}\n");
+
+        let error = module.merged_content(&[a, b]).unwrap_err();
+        assert!(
+            matches!(&error, Error::MergeConflict { name, .. } if name == "area"),
+            "{:?}",
+            error
+        );
+    }
+}
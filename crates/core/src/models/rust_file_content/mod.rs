@@ -1,8 +1,12 @@
+mod gather_impls;
 mod handle_lib_rs_special_case;
 mod handle_main_rs_special_case;
+mod inline_modules;
+mod merge;
 #[allow(clippy::module_inception)]
 mod rust_file_content;
 mod special_case_utils;
+mod test_modules;
 mod utils;
 
 pub use rust_file_content::*;
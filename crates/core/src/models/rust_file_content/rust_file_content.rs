@@ -4,19 +4,17 @@ use crate::prelude::*;
 pub type RustFileContent = NodeContent<NamedSourceItems>;
 
 impl FileWritable for RustFileContent {
-    /// Writes Rust file content to one or more files based on item types.
+    /// Plans writing Rust file content to one or more files based on item types.
     ///
     /// Types are split into separate files with their implementations,
     /// while keeping use statements and global items appropriately distributed.
-    fn write_to(&self, path: impl AsRef<Path>) -> Result<()> {
-        let base_path = path.as_ref();
-
-        // Ensure output directory exists
-        self.ensure_output_directory_exists(base_path)?;
-
-        self.handle_file_writing_strategy(base_path)?;
-
-        Ok(())
+    fn plan_write_to(
+        &self,
+        path: impl AsRef<Path>,
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) -> Result<()> {
+        self.handle_file_writing_strategy(path.as_ref(), config, plan)
     }
 }
 
@@ -38,7 +36,6 @@ impl RustFileContent {
     pub const LIB_RS: &'static str = "lib.rs";
     pub const MOD_RS: &'static str = "mod.rs";
     pub const UTILS_RS: &'static str = "utils.rs";
-    pub const FUNCTIONS_RS: &'static str = "functions.rs";
     pub const RS_EXTENSION: &'static str = ".rs";
 
     // pub constants for categories
@@ -48,44 +45,62 @@ impl RustFileContent {
     pub const CATEGORY_CLI: &'static str = "cli";
     pub const CATEGORY_CORE: &'static str = "core";
 
-    // pub constants for folder names
-    pub const FOLDER_TYPES: &'static str = "types";
-    pub const FOLDER_MODELS: &'static str = "models";
-    pub const FOLDER_LOGIC: &'static str = "logic";
-
     // pub constants for module names
     pub const MAIN_FUNCTION: &'static str = "main";
     pub const UTILS_MODULE: &'static str = "utils";
-    pub const FUNCTIONS_MODULE: &'static str = "functions";
 
     // pub constants for common patterns
     pub const DOC_ATTR_PREFIX: &'static str = "[doc = \"";
     pub const MOD_PREFIX: &'static str = "mod ";
     pub const COMMENT_PREFIX: &'static str = "///";
-    pub const PRELUDE_IMPORT: &'static str = "use crate::prelude::*;\n\n";
 
-    /// Determines and executes the appropriate file writing strategy
-    fn handle_file_writing_strategy(&self, base_path: &Path) -> Result<()> {
+    /// Determines the appropriate file writing strategy and plans its files
+    pub(super) fn handle_file_writing_strategy(
+        &self,
+        base_path: &Path,
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) -> Result<()> {
+        if let Some(rewritten) = self.plan_inline_modules(base_path, config, plan)? {
+            // Split what is left once the inline modules moved out
+            return rewritten.handle_file_writing_strategy(base_path, config, plan);
+        }
+
         // Check if this is a special lib.rs case
-        if self.is_lib_rs_special_case() {
+        if self.is_lib_rs_special_case(config) {
             debug!("Detected lib.rs special case");
-            return self.handle_lib_rs_special_case(base_path);
+            return self.handle_lib_rs_special_case(base_path, config, plan);
         }
 
-        if self.is_main_rs_special_case() {
+        if self.is_main_rs_special_case(config) {
             debug!("Detected main.rs special case");
-            return self.handle_main_rs_special_case(base_path);
+            return self.handle_main_rs_special_case(base_path, config, plan);
         }
         debug!("Non main.rs or lib.rs case, using standard file splitting");
         // Use standard file splitting for regular files
-        self.handle_standard_file_splitting(base_path)
+        self.handle_standard_file_splitting(base_path, config, plan)
     }
 
-    /// Handles standard file splitting logic for regular files
-    fn handle_standard_file_splitting(&self, base_path: &Path) -> Result<()> {
-        // Apply the same categorization logic as special cases
-        let items = self.content().items();
-        let (type_items, logic_items, other_items) = self.categorize_regular_file_items(items);
+    /// Handles standard file splitting logic for regular files.
+    ///
+    /// Items moved out of the file are planned as sibling files, the original
+    /// file is reduced to the items left behind (or deleted if none are left),
+    /// and the parent module file is edited to declare the new modules.
+    /// Without a prelude, the files import the sibling items they reference
+    /// through `super::`. Test modules follow the items they test, see
+    /// [`RustFileContent::place_test_modules`].
+    fn handle_standard_file_splitting(
+        &self,
+        base_path: &Path,
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) -> Result<()> {
+        // Apply the same categorization logic as special cases, test modules
+        // are placed once it is known where the items they test go
+        let (test_modules, items) = self.partition_test_modules(self.content().items());
+        let items = &items;
+        let (type_items, logic_items, other_items) =
+            self.categorize_regular_file_items(items, config);
 
         debug!(
             "Found {} type items, {} logic items, {} other items",
@@ -96,28 +111,129 @@ impl RustFileContent {
 
         // Determine the directory where files should be written
         let output_dir = self.determine_output_directory(base_path);
+        let base_path = &output_dir.join(self.content().name());
+
+        if type_items.is_empty() && logic_items.is_empty() {
+            // Nothing to split out of this file
+            self.keep_original_file(base_path, plan);
+            return Ok(());
+        }
+
+        // With both types and logic, logic is organized like in the special
+        // cases, otherwise items are grouped by the type they belong to
+        let is_organized = !type_items.is_empty() && !logic_items.is_empty();
+        let (grouped_items, mut logic_items) = if is_organized {
+            (
+                self.group_items_by_target_file(&type_items, config),
+                logic_items,
+            )
+        } else {
+            (self.group_items_by_target_file(items, config), Vec::new())
+        };
+        let mut grouped_items = self.rename_colliding_groups(grouped_items, &output_dir, plan);
+
+        if is_organized {
+            // Other items stay in the original file, which may also be the
+            // file of a type named like it
+            let own_name = self.content().name();
+            let remaining_items = self.remaining_items(&other_items);
+            match grouped_items.get_mut(own_name) {
+                Some(own_group) => own_group.extend(remaining_items),
+                None if !remaining_items.is_empty() => {
+                    grouped_items.shift_insert(0, own_name.clone(), remaining_items);
+                }
+                None => {}
+            }
+        }
+
+        let mut siblings = SiblingItems::new(&output_dir, base_path, false);
+        self.add_sibling_groups(&mut siblings, &output_dir, &grouped_items);
+        self.add_sibling_logic_items(&mut siblings, &output_dir, &logic_items, config, plan);
+        let (test_files, test_parts) = self.place_test_modules(
+            &test_modules,
+            &output_dir,
+            (&mut grouped_items, &mut logic_items),
+            &mut siblings,
+            config,
+            plan,
+        );
+
+        if !grouped_items.contains_key(self.content().name()) {
+            self.remove_original_file(base_path, plan);
+        }
+        self.write_grouped_items_to_directory(&output_dir, &grouped_items, &siblings, config, plan);
+        // Write logic items (functions to functions.rs, macros to individual files)
+        self.write_logic_items_shared(&logic_items, &output_dir, &siblings, config, plan);
+        for test_file in &test_files {
+            let content = self.build_organized_file_content(
+                &test_file.items,
+                &test_file.file,
+                &siblings,
+                config,
+            );
+            plan.add_file(&test_file.file, content);
+        }
+        for part in test_parts {
+            let content = plan.current_content(&part.file)?.unwrap_or_default();
+            plan.add_file(
+                &part.file,
+                format!("{}\n\n{}", content.trim_end(), part.text),
+            );
+        }
 
-        // If we have both types and logic, organize like special cases
-        if !type_items.is_empty() && !logic_items.is_empty() {
-            // Create organized structure with categorized items
-            self.create_organized_structure_for_regular_file(
-                &output_dir,
-                &type_items,
-                &logic_items,
-                &other_items,
-            )?;
-        } else if !type_items.is_empty() || !logic_items.is_empty() || !other_items.is_empty() {
-            // Fall back to traditional grouping for simpler cases
-            let grouped_items = self.group_items_by_target_file(items);
-            self.write_grouped_items_to_directory(&output_dir, &grouped_items)?;
-
-            // Update mod.rs if multiple files were created
-            if grouped_items.len() > 1 {
-                debug!("Multiple files created, updating mod.rs");
-                self.update_mod_file(base_path, &grouped_items)?;
+        let module_names = |file_names: Vec<String>| -> Vec<String> {
+            file_names
+                .into_iter()
+                .map(|file_name| plan.renamed_file_name(self.path(), &output_dir, &file_name))
+                .map(|file_name| file_name.trim_end_matches(Self::RS_EXTENSION).to_string())
+                .collect()
+        };
+        let macro_modules = module_names(self.macro_file_names(&logic_items));
+        let mut new_modules = self.extract_module_names_from_groups(&grouped_items);
+        new_modules.extend(
+            module_names(self.logic_file_names(&logic_items, config))
+                .into_iter()
+                .filter(|module| !macro_modules.contains(module)),
+        );
+
+        new_modules.sort();
+
+        // Keep the items reachable the way the parent module exposed them,
+        // as far as they are reached through it
+        let module_file = self.parent_module_file(base_path, &output_dir);
+        let exposures: Vec<(String, Option<&str>)> = new_modules
+            .into_iter()
+            .map(|module| {
+                let items = self.new_module_items(&module, &grouped_items, &logic_items);
+                let exposure = self.module_exposure(&items, &module_file, &siblings, config, plan);
+                (module, exposure)
+            })
+            .collect();
+        let (reexport, needs_facade) = self.original_module_exposure();
+        if needs_facade && !exposures.is_empty() {
+            self.plan_facade(base_path, &exposures, plan);
+        }
+        if let Some(kept_items) = grouped_items.get(self.content().name())
+            && !self.is_module_root()
+        {
+            // The public items may all have moved out of the kept file
+            let exposure = self.module_exposure(kept_items, &module_file, &siblings, config, plan);
+            if exposure != Some("pub") {
+                plan.edit_module_declarations(&module_file)
+                    .narrow_reexport(self.module_name(), exposure.map(str::to_owned));
             }
         }
 
+        self.update_module_declarations(base_path, &output_dir, exposures, reexport, plan);
+        let edit = plan.edit_module_declarations(&module_file);
+        for module in macro_modules {
+            edit.add(ModuleDeclaration::macro_use(module));
+        }
+        self.declare_nested_group_modules(&module_file, &output_dir, &grouped_items, plan);
+        let edit = plan.edit_module_declarations(module_file);
+        for test_file in test_files {
+            edit.add(test_file.declaration);
+        }
         Ok(())
     }
 
@@ -125,6 +241,7 @@ impl RustFileContent {
     fn categorize_regular_file_items(
         &self,
         items: &[SourceItem],
+        config: &Config,
     ) -> (Vec<SourceItem>, Vec<SourceItem>, Vec<SourceItem>) {
         let mut type_items = Vec::new();
         let mut logic_items = Vec::new();
@@ -135,6 +252,11 @@ impl RustFileContent {
                 SourceItem::Function(_) | SourceItem::MacroRules(_) => {
                     logic_items.push(item.clone())
                 }
+                _ if self.is_attached_item(item) => self.categorize_attached_item(
+                    item,
+                    config,
+                    (&mut type_items, &mut logic_items, &mut other_items),
+                ),
                 _ if self.is_type_item(item) => type_items.push(item.clone()),
                 _ => other_items.push(item.clone()),
             }
@@ -143,102 +265,125 @@ impl RustFileContent {
         (type_items, logic_items, other_items)
     }
 
-    /// Creates organized structure for regular files that have both types and logic
-    fn create_organized_structure_for_regular_file(
-        &self,
-        output_dir: &Path,
-        type_items: &[SourceItem],
-        logic_items: &[SourceItem],
-        other_items: &[SourceItem],
-    ) -> Result<()> {
-        // Write type items using the same logic as special cases
-        if !type_items.is_empty() {
-            let grouped_type_items = self.group_items_by_target_file(type_items);
-            self.write_grouped_items_to_directory(output_dir, &grouped_type_items)?;
-        }
+    /// Returns the items which are not moved out of the original file when
+    /// splitting types and logic out of it. Private use statements are not
+    /// among them, they are distributed to the files needing them.
+    fn remaining_items(&self, other_items: &[SourceItem]) -> Vec<SourceItem> {
+        other_items
+            .iter()
+            .filter(|item| !self.is_private_use(item))
+            .cloned()
+            .collect()
+    }
 
-        // Write logic items (functions to functions.rs, macros to individual files)
-        if !logic_items.is_empty() {
-            self.write_logic_items_shared(logic_items, output_dir)?;
+    /// Returns the names of the files logic items are written to: the
+    /// configured functions file for functions and one file per macro
+    fn logic_file_names(&self, logic_items: &[SourceItem], config: &Config) -> Vec<String> {
+        let mut file_names = Vec::new();
+
+        // Check if we have functions (they go into functions.rs)
+        let has_functions = logic_items
+            .iter()
+            .any(|item| matches!(item, SourceItem::Function(_)));
+        if has_functions {
+            file_names.push(config.functions_file_name());
         }
 
-        // Write other items to a separate file in output directory if they exist
-        if !other_items.is_empty() {
-            let other_file_path = output_dir.join("other.rs");
-            let content = self.build_organized_file_content(other_items);
-            self.write_content_to_file(&content, &other_file_path)?;
-        }
+        // Add file names for each macro (they get individual files)
+        file_names.extend(self.macro_file_names(logic_items));
+        file_names
+    }
 
-        // Create mod.rs with all the modules
-        self.create_comprehensive_mod_file(output_dir, type_items, logic_items)?;
+    /// Returns the names of the files the macro definitions among
+    /// `logic_items` are written to, one per macro
+    fn macro_file_names(&self, logic_items: &[SourceItem]) -> Vec<String> {
+        logic_items
+            .iter()
+            .filter_map(|item| match item {
+                SourceItem::MacroRules(macro_item) => macro_item.ident.as_ref(),
+                _ => None,
+            })
+            .map(|ident| format!("{}{}", ident, Self::RS_EXTENSION))
+            .collect()
+    }
 
-        Ok(())
+    /// Checks if splitting this file generates the prelude module, which is
+    /// the case for `lib.rs` and `main.rs` organized into folders
+    pub fn generates_prelude(&self, config: &Config) -> bool {
+        config.prelude().is_some()
+            && (self.is_lib_rs_special_case(config) || self.is_main_rs_special_case(config))
     }
 
-    /// Creates a comprehensive mod.rs file that includes both type and logic modules
-    fn create_comprehensive_mod_file(
-        &self,
-        output_dir: &Path,
-        type_items: &[SourceItem],
-        logic_items: &[SourceItem],
-    ) -> Result<()> {
-        let mut module_names = Vec::new();
-
-        // Add type module names
-        if !type_items.is_empty() {
-            let grouped_type_items = self.group_items_by_target_file(type_items);
-            let type_module_names =
-                self.extract_module_names_for_organized_items(&grouped_type_items);
-            module_names.extend(type_module_names);
-        }
-
-        // Add logic module names
-        if !logic_items.is_empty() {
-            // Check if we have functions (they go into functions.rs)
-            let has_functions = logic_items
-                .iter()
-                .any(|item| matches!(item, SourceItem::Function(_)));
-            if has_functions {
-                module_names.push(Self::FUNCTIONS_MODULE.to_string());
-            }
+    /// Names of the files this file is split into within its own directory,
+    /// including its own name if it is kept. Used to detect collisions
+    /// between the files of a directory before any of them is planned.
+    pub fn planned_file_names(&self, config: &Config) -> Vec<String> {
+        let own_name = self.content().name().clone();
+        if self.is_lib_rs_special_case(config) || self.is_main_rs_special_case(config) {
+            // Split into their own folders
+            return vec![own_name];
+        }
 
-            // Add module names for each macro (they get individual files)
-            for item in logic_items {
-                let SourceItem::MacroRules(macro_item) = item else {
-                    continue;
-                };
+        let items = self.content().items();
+        let (type_items, logic_items, other_items) =
+            self.categorize_regular_file_items(items, config);
 
-                let Some(ident) = &macro_item.ident else {
-                    continue;
-                };
+        if type_items.is_empty() && logic_items.is_empty() {
+            // Nothing to split out of this file
+            return vec![own_name];
+        }
 
-                module_names.push(ident.to_string());
+        let mut file_names: Vec<String> = if !logic_items.is_empty() && !type_items.is_empty() {
+            let mut file_names: Vec<String> = self
+                .group_items_by_target_file(&type_items, config)
+                .into_keys()
+                .collect();
+            file_names.extend(self.logic_file_names(&logic_items, config));
+            if !self.remaining_items(&other_items).is_empty() {
+                file_names.push(own_name.clone());
             }
-        }
+            file_names
+        } else {
+            self.group_items_by_target_file(items, config)
+                .into_keys()
+                .collect()
+        };
 
-        if !module_names.is_empty() {
-            module_names.sort();
-            self.write_mod_file_content(&output_dir.join(Self::MOD_RS), module_names)?;
+        if self.is_module_root() && !file_names.contains(&own_name) {
+            file_names.push(own_name);
         }
+        file_names
+    }
 
-        Ok(())
+    /// Renames the groups whose file names collide with files split out of
+    /// other source files in the same directory, see
+    /// [`DirectoryContent::plan_collision_renames`]
+    fn rename_colliding_groups(
+        &self,
+        grouped_items: IndexMap<String, Vec<SourceItem>>,
+        output_dir: &Path,
+        plan: &SplitPlan,
+    ) -> IndexMap<String, Vec<SourceItem>> {
+        grouped_items
+            .into_iter()
+            .map(|(file_name, items)| {
+                (
+                    plan.renamed_file_name(self.path(), output_dir, &file_name),
+                    items,
+                )
+            })
+            .collect()
     }
 
-    /// Write grouped items to a directory
+    /// Plans writing grouped items to a directory
     fn write_grouped_items_to_directory(
         &self,
         output_dir: &Path,
         grouped_items: &IndexMap<String, Vec<SourceItem>>,
-    ) -> Result<()> {
-        // Ensure the output directory exists
-        std::fs::create_dir_all(output_dir).map_err(|e| {
-            Error::bail(format!(
-                "Failed to create output directory {}: {}",
-                output_dir.display(),
-                e
-            ))
-        })?;
-
+        siblings: &SiblingItems,
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) {
         debug!(
             "Writing {} groups to directory: {}",
             grouped_items.len(),
@@ -247,25 +392,295 @@ impl RustFileContent {
         for (file_name, group_items) in grouped_items {
             let target_file = output_dir.join(file_name);
             debug!(
-                "Writing file: {} with {} items",
+                "Planning file: {} with {} items",
                 target_file.display(),
                 group_items.len()
             );
-            let content = self.build_organized_file_content(group_items);
-            self.write_content_to_file(&content, &target_file)?;
+            let content =
+                self.build_organized_file_content(group_items, &target_file, siblings, config);
+            plan.add_file(target_file, content);
         }
-        Ok(())
     }
 
-    /// Creates the output directory if it doesn't exist
-    fn ensure_output_directory_exists(&self, base_path: &Path) -> Result<()> {
-        let Some(parent) = base_path.parent() else {
-            // No parent directory, nothing to create
-            return Ok(());
+    /// Keeps a file which has nothing to split out unchanged, copying it if
+    /// the output is written somewhere else than the source or writing it if
+    /// its source was edited
+    fn keep_original_file(&self, base_path: &Path, plan: &mut SplitPlan) {
+        if self.is_written_in_place(base_path) && !self.is_edited() {
+            // Already where it belongs
+            return;
+        }
+
+        plan.add_file(base_path, self.content().source().clone());
+    }
+
+    /// Plans deleting the original file once all its items moved out.
+    ///
+    /// Module root files (`mod.rs`, `lib.rs`, `main.rs`) are emptied instead
+    /// of deleted since they declare the modules split out of them.
+    fn remove_original_file(&self, base_path: &Path, plan: &mut SplitPlan) {
+        if self.is_module_root() {
+            // Emptied module root, its declarations are added afterwards
+            plan.add_file(base_path, String::new());
+            return;
+        }
+
+        debug!(
+            "All items moved out of {}, deleting it",
+            base_path.display()
+        );
+        plan.remove_file(base_path);
+    }
+
+    /// Determines how the parent module exposes the items of this file, as
+    /// the visibility the new modules are glob re-exported with (empty for a
+    /// private glob import, `None` for no re-export) and whether this file
+    /// must stay as a facade re-exporting them.
+    ///
+    /// The new modules are re-exported like the parent re-exports this file's
+    /// module. If the parent declares the module publicly or only declares it,
+    /// its items are reached through its path, which a facade keeps working.
+    /// Module roots and files no parent declares get klyv's default layout.
+    pub(super) fn original_module_exposure(&self) -> (Option<String>, bool) {
+        let default = (Some("pub".to_owned()), false);
+        if self.is_module_root() {
+            // Re-exports its children into itself, keeping their visibility
+            return default;
+        }
+
+        let Some(content) = self
+            .source_parent_module_file()
+            .and_then(|parent| fs::read_to_string(parent).ok())
+        else {
+            // No parent module file yet
+            return default;
+        };
+
+        let module = self.module_name();
+        let Some(declaration_visibility) = content
+            .lines()
+            .filter_map(ModuleDeclarationEdit::module_declaration)
+            .find_map(|(visibility, declared)| (declared == module).then_some(visibility))
+        else {
+            // Not declared by its parent
+            return default;
+        };
+
+        let reexport = content
+            .lines()
+            .filter_map(ModuleDeclarationEdit::glob_reexport)
+            .find_map(|(visibility, reexported)| {
+                (reexported == module).then(|| visibility.to_owned())
+            });
+        let needs_facade = !declaration_visibility.is_empty() || reexport.is_none();
+        (reexport, needs_facade)
+    }
+
+    /// Returns the items planned to the new module `module`: the items of its
+    /// group, or the functions among `logic_items` for the functions module
+    fn new_module_items(
+        &self,
+        module: &str,
+        grouped_items: &IndexMap<String, Vec<SourceItem>>,
+        logic_items: &[SourceItem],
+    ) -> Vec<SourceItem> {
+        if let Some(items) = grouped_items.get(&format!("{}{}", module, Self::RS_EXTENSION)) {
+            return items.clone();
+        }
+
+        logic_items
+            .iter()
+            .filter(|item| !item.is_macro_rules())
+            .cloned()
+            .collect()
+    }
+
+    /// Determines the widest visibility the items of a new module are reached
+    /// with through `module_file`, which declares it: `pub` if any of them
+    /// is public and this file is not an inline module private to the crate,
+    /// `pub(crate)` if another source file references them or a split file
+    /// reaching them through the module root does, and `None` if they are
+    /// only imported from the new module directly
+    fn module_exposure(
+        &self,
+        items: &[SourceItem],
+        module_file: &Path,
+        siblings: &SiblingItems,
+        config: &Config,
+        plan: &SplitPlan,
+    ) -> Option<&'static str> {
+        if !plan.crate_private_modules().contains(self.path())
+            && items.iter().any(|item| self.is_public_item(item))
+        {
+            return Some("pub");
+        }
+
+        let names: Vec<String> = items
+            .iter()
+            .flat_map(|item| self.defined_item_names(item))
+            .collect();
+        let is_reached = plan.is_referenced_outside(self.path(), &names)
+            || siblings.is_reached_through_root(&names, module_file, config.prelude().is_some());
+        is_reached.then_some("pub(crate)")
+    }
+
+    /// Plans this file as a facade glob re-exporting the modules split out of
+    /// it with their `exposures`, in front of the items it keeps, so that the
+    /// items remain reachable through its module path
+    fn plan_facade(
+        &self,
+        base_path: &Path,
+        exposures: &[(String, Option<&str>)],
+        plan: &mut SplitPlan,
+    ) {
+        debug!("Keeping {} as a facade", base_path.display());
+        let mut content: String = exposures
+            .iter()
+            .filter_map(|(module, exposure)| {
+                exposure.map(|visibility| format!("{} use super::{}::*;\n", visibility, module))
+            })
+            .collect();
+
+        if let Some(kept) = plan.current_content(base_path).ok().flatten()
+            && !kept.is_empty()
+        {
+            content.push('\n');
+            content.push_str(&kept);
+        }
+        plan.add_file(base_path, content);
+    }
+
+    /// Edits the declarations of the module file owning this file: the new
+    /// modules are declared and re-exported with `reexport`, narrowed to
+    /// their exposures, and, if this file is planned to be deleted, its own
+    /// declaration is removed
+    fn update_module_declarations(
+        &self,
+        base_path: &Path,
+        output_dir: &Path,
+        exposures: Vec<(String, Option<&str>)>,
+        reexport: Option<String>,
+        plan: &mut SplitPlan,
+    ) {
+        let is_original_removed =
+            !self.is_module_root() && plan.current_content(base_path).ok().flatten().is_none();
+        let module_file = self.parent_module_file(base_path, output_dir);
+        debug!(
+            "Declaring {} module(s) in {}",
+            exposures.len(),
+            module_file.display()
+        );
+
+        let edit = plan.edit_module_declarations(module_file);
+        if is_original_removed {
+            edit.remove(self.module_name());
+        }
+        for (module, exposure) in exposures {
+            edit.add(
+                ModuleDeclaration::builder()
+                    .name(module)
+                    .maybe_reexport(Self::narrowed_reexport(reexport.as_deref(), exposure))
+                    .build(),
+            );
+        }
+    }
+
+    /// Narrows the visibility `reexport` the original module was re-exported
+    /// with to the `exposure` of a new module. Restricted re-exports are kept
+    /// as they are, nothing is re-exported if the new module is not reached
+    /// through its parent.
+    fn narrowed_reexport(reexport: Option<&str>, exposure: Option<&str>) -> Option<String> {
+        let exposure = exposure?;
+        match reexport? {
+            "pub" => Some(exposure.to_owned()),
+            restricted => Some(restricted.to_owned()),
+        }
+    }
+
+    /// Finds the module file declaring this file's module, and with it the
+    /// sibling modules split out of it.
+    ///
+    /// Module roots declare their siblings themselves, other files are
+    /// declared by their parent module file, see
+    /// [`RustFileContent::source_parent_module_file`]. The lookup is done in
+    /// the source tree, so that it does not depend on which files have been
+    /// planned already. If there is no such file, a new `mod.rs` is created.
+    fn parent_module_file(&self, base_path: &Path, output_dir: &Path) -> PathBuf {
+        if self.is_module_root() {
+            // Declares its sibling modules itself
+            return base_path.to_path_buf();
+        }
+
+        let Some(parent) = self.source_parent_module_file() else {
+            // No parent module file yet
+            return output_dir.join(Self::MOD_RS);
         };
 
-        std::fs::create_dir_all(parent)
-            .map_err(|e| Error::bail(format!("Failed to create directory: {}", e)))
+        let source_dir = self.path().parent().unwrap_or(Path::new("."));
+        if parent.parent() == Some(source_dir)
+            && let Some(file_name) = parent.file_name()
+        {
+            return output_dir.join(file_name);
+        }
+
+        Self::directory_module_file(output_dir).unwrap_or_else(|| output_dir.join(Self::MOD_RS))
+    }
+
+    /// Finds the module file declaring this file in the source tree: the
+    /// `mod.rs`, `lib.rs` or `main.rs` next to it or the `<dir>.rs` next to
+    /// its directory
+    fn source_parent_module_file(&self) -> Option<PathBuf> {
+        let source_dir = self.path().parent().unwrap_or(Path::new("."));
+        if let Some(root) = [Self::MOD_RS, Self::LIB_RS, Self::MAIN_RS]
+            .into_iter()
+            .map(|root| source_dir.join(root))
+            .find(|root| root.exists())
+        {
+            return Some(root);
+        }
+
+        Self::directory_module_file(source_dir).filter(|path| path.exists())
+    }
+
+    /// The `<dir>.rs` file declaring the modules in `dir` in the 2018 layout
+    fn directory_module_file(dir: &Path) -> Option<PathBuf> {
+        let name = dir.file_name()?.to_str()?;
+        Some(
+            dir.parent()?
+                .join(format!("{}{}", name, Self::RS_EXTENSION)),
+        )
+    }
+
+    /// Checks if this file is `mod.rs`, `lib.rs` or `main.rs`, i.e. declares
+    /// the modules of the directory it is in
+    pub(super) fn is_module_root(&self) -> bool {
+        [Self::MOD_RS, Self::LIB_RS, Self::MAIN_RS].contains(&self.content().name().as_str())
+    }
+
+    /// Checks if the output for this file is written over the file itself
+    fn is_written_in_place(&self, base_path: &Path) -> bool {
+        if base_path == self.path() {
+            return true;
+        }
+
+        match (base_path.canonicalize(), self.path().canonicalize()) {
+            (Ok(base_path), Ok(path)) => base_path == path,
+            _ => false,
+        }
+    }
+
+    /// Checks if the source of this file differs from the file on disk, e.g.
+    /// after impl blocks were gathered into it
+    fn is_edited(&self) -> bool {
+        fs::read_to_string(self.path()).is_ok_and(|on_disk| on_disk != *self.content().source())
+    }
+
+    /// The name of the module this file defines
+    pub(super) fn module_name(&self) -> String {
+        self.content()
+            .name()
+            .trim_end_matches(Self::RS_EXTENSION)
+            .to_string()
     }
 
     /// Determines the output directory based on the base path
@@ -280,29 +695,6 @@ impl RustFileContent {
         }
     }
 
-    /// Updates or creates mod.rs file with module declarations for split files
-    fn update_mod_file(
-        &self,
-        base_path: &Path,
-        grouped_items: &IndexMap<String, Vec<SourceItem>>,
-    ) -> Result<()> {
-        let mod_file_path = if base_path.is_dir() {
-            base_path.join(Self::MOD_RS)
-        } else {
-            base_path
-                .parent()
-                .unwrap_or(Path::new("."))
-                .join(Self::MOD_RS)
-        };
-
-        let module_names = self.extract_module_names_from_groups(grouped_items);
-        let existing_modules = self.read_existing_modules(&mod_file_path)?;
-        let combined_modules = self.combine_module_lists(existing_modules, module_names);
-        self.write_mod_file_content(&mod_file_path, combined_modules)?;
-
-        Ok(())
-    }
-
     /// Extracts module names from the grouped items, excluding the main file
     fn extract_module_names_from_groups(
         &self,
@@ -310,53 +702,8 @@ impl RustFileContent {
     ) -> Vec<String> {
         grouped_items
             .keys()
-            .filter(|&name| name != self.content().name())
+            .filter(|&name| name != self.content().name() && !Self::is_nested_group(name))
             .map(|name| name.trim_end_matches(Self::RS_EXTENSION).to_string())
             .collect()
     }
-
-    /// Reads existing module declarations from mod.rs if it exists
-    fn read_existing_modules(&self, mod_file_path: &Path) -> Result<Vec<String>> {
-        if !mod_file_path.exists() {
-            // No existing mod.rs file to read from
-            return Ok(vec![]);
-        }
-
-        let content = std::fs::read_to_string(mod_file_path).map_err(|e| {
-            Error::bail(format!(
-                "Failed to read mod.rs file {}: {}",
-                mod_file_path.display(),
-                e
-            ))
-        })?;
-        Ok(self.parse_module_declarations(&content))
-    }
-
-    /// Parses mod declarations from file content
-    fn parse_module_declarations(&self, content: &str) -> Vec<String> {
-        content
-            .lines()
-            .filter_map(|line| {
-                let trimmed = line.trim();
-                if !trimmed.starts_with(Self::MOD_PREFIX) || !trimmed.ends_with(';') {
-                    // Skip lines that aren't mod declarations
-                    return None;
-                }
-
-                Some(trimmed[4..trimmed.len() - 1].to_string())
-            })
-            .collect()
-    }
-
-    /// Combines existing and new module lists, removing duplicates
-    fn combine_module_lists(&self, existing: Vec<String>, new: Vec<String>) -> Vec<String> {
-        let mut combined: Vec<String> = existing;
-        for module in new {
-            if !combined.contains(&module) {
-                combined.push(module);
-            }
-        }
-        combined.sort();
-        combined
-    }
 }
@@ -1,57 +1,65 @@
 use crate::prelude::*;
 
-/// Configuration for special case handling
-pub struct SpecialCaseConfig {
-    pub types_folder: &'static str,
-    pub logic_folder: &'static str,
-}
+impl RustFileContent {
+    /// Records where the items of a `lib.rs` or `main.rs` special case are
+    /// planned to: types and logic into their folders, the other items stay
+    /// in the crate root
+    pub(super) fn special_case_sibling_items(
+        &self,
+        base_path: &Path,
+        (type_items, logic_items, root_items): (&[SourceItem], &[SourceItem], &[SourceItem]),
+        config: &Config,
+        plan: &SplitPlan,
+    ) -> SiblingItems {
+        let output_dir = self.determine_output_directory(base_path);
+        let mut siblings =
+            SiblingItems::new(&output_dir, output_dir.join(self.content().name()), true);
 
-impl SpecialCaseConfig {
-    /// Configuration for lib.rs special case
-    pub fn lib_rs() -> Self {
-        Self {
-            types_folder: RustFileContent::FOLDER_TYPES,
-            logic_folder: RustFileContent::FOLDER_LOGIC,
-        }
-    }
+        let types_dir = output_dir.join(config.types_folder());
+        let grouped_type_items = self.group_items_by_target_file(type_items, config);
+        self.add_sibling_groups(&mut siblings, &types_dir, &grouped_type_items);
 
-    /// Configuration for main.rs special case (uses types folder but models module name)
-    pub fn main_rs() -> Self {
-        Self {
-            types_folder: RustFileContent::FOLDER_TYPES,
-            logic_folder: RustFileContent::FOLDER_LOGIC,
-        }
+        let logic_dir = output_dir.join(config.logic_folder());
+        self.add_sibling_logic_items(&mut siblings, &logic_dir, logic_items, config, plan);
+
+        let root_file = output_dir.join(self.content().name());
+        self.add_sibling_items(&mut siblings, &root_file, root_items);
+        siblings
     }
-}
 
-impl RustFileContent {
     /// Shared logic for writing types folder and files
     pub(super) fn create_types_folder_with_config(
         &self,
         type_items: &[SourceItem],
         base_path: &Path,
-        config: &SpecialCaseConfig,
-    ) -> Result<()> {
+        siblings: &SiblingItems,
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) {
         if type_items.is_empty() {
-            return Ok(());
+            return;
         }
 
         let output_dir = self.determine_output_directory(base_path);
-        let types_dir = output_dir.join(config.types_folder);
-        std::fs::create_dir_all(&types_dir)
-            .map_err(|e| Error::bail(format!("Failed to create types directory: {}", e)))?;
+        let types_dir = output_dir.join(config.types_folder());
 
-        // Group items by target file and write each group
-        let grouped_items = self.group_items_by_target_file(type_items);
+        // Group items by target file and plan each group
+        let grouped_items = self.group_items_by_target_file(type_items, config);
 
-        for (file_name, group_items) in grouped_items {
-            let target_file = types_dir.join(&file_name);
-            let content = self.build_organized_file_content(&group_items);
-            self.write_content_to_file(&content, &target_file)?;
+        for (file_name, group_items) in &grouped_items {
+            let target_file = types_dir.join(file_name);
+            let content =
+                self.build_organized_file_content(group_items, &target_file, siblings, config);
+            plan.add_file(target_file, content);
         }
 
-        self.create_types_mod_rs_shared(&types_dir, type_items)?;
-        Ok(())
+        self.create_types_mod_rs_shared(&types_dir, type_items, config, plan);
+        self.declare_nested_group_modules(
+            &types_dir.join(Self::MOD_RS),
+            &types_dir,
+            &grouped_items,
+            plan,
+        );
     }
 
     /// Shared logic for creating logic folder and files
@@ -59,25 +67,32 @@ impl RustFileContent {
         &self,
         logic_items: &[SourceItem],
         base_path: &Path,
-        config: &SpecialCaseConfig,
-    ) -> Result<()> {
+        siblings: &SiblingItems,
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) {
         if logic_items.is_empty() {
             // No logic items to process
-            return Ok(());
+            return;
         }
 
         let output_dir = self.determine_output_directory(base_path);
-        let logic_dir = output_dir.join(config.logic_folder);
-        std::fs::create_dir_all(&logic_dir)
-            .map_err(|e| Error::bail(format!("Failed to create logic directory: {}", e)))?;
+        let logic_dir = output_dir.join(config.logic_folder());
 
-        // Write logic items to individual files (functions.rs, macro_name.rs, etc.)
-        self.write_logic_items_shared(logic_items, &logic_dir)?;
-        self.create_logic_mod_rs_shared(&logic_dir, logic_items)
+        // Plan logic items as individual files (functions.rs, macro_name.rs, etc.)
+        self.write_logic_items_shared(logic_items, &logic_dir, siblings, config, plan);
+        self.create_logic_mod_rs_shared(&logic_dir, logic_items, config, plan);
     }
 
     /// Shared logic for writing logic items (functions and macros) to individual files
-    pub(super) fn write_logic_items_shared(&self, items: &[SourceItem], dir: &Path) -> Result<()> {
+    pub(super) fn write_logic_items_shared(
+        &self,
+        items: &[SourceItem],
+        dir: &Path,
+        siblings: &SiblingItems,
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) {
         // Separate functions and macros
         let mut functions = Vec::new();
         let mut macros = Vec::new();
@@ -92,14 +107,17 @@ impl RustFileContent {
             }
         }
 
-        // Write functions to functions.rs if any exist
+        // Write functions to the functions file if any exist
         if !functions.is_empty() {
-            let functions_file = dir.join(Self::FUNCTIONS_RS);
-            let content = self.build_organized_file_content(&functions);
-            self.write_content_to_file(&content, &functions_file)?;
+            let functions_file_name = config.functions_file_name();
+            let functions_file =
+                dir.join(plan.renamed_file_name(self.path(), dir, &functions_file_name));
+            let content =
+                self.build_organized_file_content(&functions, &functions_file, siblings, config);
+            plan.add_file(functions_file, content);
         }
 
-        // Write each macro to its own file with #[macro_export]
+        // Write each macro to its own file
         for item in &macros {
             let SourceItem::MacroRules(macro_item) = item else {
                 // Not a macro, skip
@@ -111,20 +129,16 @@ impl RustFileContent {
                 continue;
             };
 
-            let macro_name = ident.to_string();
-            let macro_file = dir.join(format!("{}.rs", macro_name));
+            let macro_file_name = format!("{}{}", ident, Self::RS_EXTENSION);
+            let macro_file = dir.join(plan.renamed_file_name(self.path(), dir, &macro_file_name));
 
-            // Build content with #[macro_export] attribute
-            let mut content = String::new();
-            content.push_str(Self::PRELUDE_IMPORT);
-            content.push_str("#[macro_export]\n");
-            content.push_str(&self.source_item_to_string(item));
+            // The macro keeps its attributes, it stays in scope through the
+            // `#[macro_use]` declaration of its module and needs no imports
+            let mut content = self.source_item_to_string(item, &[]);
             content.push('\n');
 
-            self.write_content_to_file(&content, &macro_file)?;
+            plan.add_file(macro_file, content);
         }
-
-        Ok(())
     }
 
     /// Shared logic for creating types mod.rs
@@ -132,28 +146,24 @@ impl RustFileContent {
         &self,
         types_dir: &Path,
         items: &[SourceItem],
-    ) -> Result<()> {
-        let grouped_items = self.group_items_by_target_file(items);
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) {
+        let grouped_items = self.group_items_by_target_file(items, config);
         let module_names = self.extract_module_names_for_organized_items(&grouped_items);
-        self.write_mod_file_content(&types_dir.join(Self::MOD_RS), module_names)?;
-        Ok(())
+        self.write_mod_file_content(&types_dir.join(Self::MOD_RS), module_names, plan);
     }
 
-    /// Shared logic for creating logic mod.rs
+    /// Shared logic for creating logic mod.rs. Macro modules are declared
+    /// first with `#[macro_use]`, so that the functions can use the macros.
     pub(super) fn create_logic_mod_rs_shared(
         &self,
         logic_dir: &Path,
         items: &[SourceItem],
-    ) -> Result<()> {
-        let mut module_names = Vec::new();
-
-        // Check if we have functions (they go into functions.rs)
-        let has_functions = items
-            .iter()
-            .any(|item| matches!(item, SourceItem::Function(_)));
-        if has_functions {
-            module_names.push(Self::FUNCTIONS_MODULE.to_string());
-        }
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) {
+        let mut modules = Vec::new();
 
         // Add module names for each macro (they get individual files)
         for item in items {
@@ -167,75 +177,161 @@ impl RustFileContent {
                 continue;
             };
 
-            module_names.push(ident.to_string());
+            modules.push(ModuleDeclaration::macro_use(ident.to_string()));
         }
+        modules.sort_by(|a, b| a.name().cmp(b.name()));
 
-        if module_names.is_empty() {
+        // Check if we have functions (they go into the functions module)
+        let has_functions = items.iter().any(|item| !item.is_macro_rules());
+        if has_functions {
+            modules.push(ModuleDeclaration::reexported(config.functions_module()));
+        }
+
+        if modules.is_empty() {
             // No modules to write
-            return Ok(());
+            return;
         }
 
-        module_names.sort();
-        self.write_mod_file_content(&logic_dir.join(Self::MOD_RS), module_names)
+        let content = ModuleDeclarationEdit::declarations_content(&modules);
+        plan.add_file(logic_dir.join(Self::MOD_RS), content);
     }
 
     /// Shared logic for creating main file with prelude module structure
     pub(super) fn create_main_file_with_prelude(
         &self,
         base_path: &Path,
-        file_name: &str,
         has_types: bool,
         has_logic: bool,
         main_items: &[SourceItem],
-    ) -> Result<()> {
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) {
         let main_file_path = if base_path.is_file() {
             base_path.to_path_buf()
         } else {
-            base_path.join(file_name)
+            base_path.join(self.content().name())
         };
 
+        // `extern crate` belongs at the very top of the crate root, followed
+        // by the modules whose macros the folders may use
+        let (header_items, main_items): (Vec<SourceItem>, Vec<SourceItem>) = main_items
+            .iter()
+            .cloned()
+            .partition(|item| item.is_extern_crate() || Self::is_macro_use_module(item));
         let mut content = String::new();
+        if !header_items.is_empty() {
+            self.push_items_with_spacing(&mut content, &header_items, None);
+        }
 
-        // Add module declarations
+        let mut folders = Vec::new();
+        let has_macros = self
+            .content()
+            .items()
+            .iter()
+            .any(SourceItem::is_macro_rules);
+        if has_logic && has_macros {
+            // Macros must be declared before the types using them
+            self.warn_about_macros_out_of_scope(&main_file_path, config, plan);
+            content.push_str(&format!("{}\n", ModuleDeclaration::MACRO_USE));
+            folders.push(config.logic_folder());
+        }
         if has_types {
-            content.push_str(&format!("mod {};\n", Self::FOLDER_TYPES));
+            folders.push(config.types_folder());
         }
-        if has_logic {
-            content.push_str("mod logic;\n");
+        if has_logic && !has_macros {
+            folders.push(config.logic_folder());
         }
 
-        // Add prelude module
-        if has_types || has_logic {
-            content.push_str("\npub mod prelude {\n");
-            if has_types {
-                content.push_str(&format!("    pub use crate::{}::*;\n", Self::FOLDER_TYPES));
+        // Add module declarations
+        for folder in &folders {
+            content.push_str(&format!("mod {};\n", folder));
+        }
+
+        // Add the crate internal prelude module. A library re-exports the
+        // folders so that its public API stays the same, a binary or a crate
+        // without prelude just imports them.
+        if !folders.is_empty() {
+            let is_library = self.content().name() == Self::LIB_RS;
+            if let Some(prelude) = config.prelude() {
+                content.push_str(&format!("\npub(crate) mod {} {{\n", prelude));
+                for folder in &folders {
+                    content.push_str(&format!("    pub use crate::{}::*;\n", folder));
+                }
+                content.push_str("}\n");
             }
-            if has_logic {
-                content.push_str("    pub use crate::logic::*;\n");
+
+            content.push('\n');
+            match config.prelude() {
+                Some(prelude) if !is_library => {
+                    content.push_str(&format!("use {}::*;\n", prelude));
+                }
+                _ => {
+                    for folder in &folders {
+                        content.push_str(&format!("pub use {}::*;\n", folder));
+                    }
+                }
             }
-            content.push_str("}\n\n");
-            content.push_str("use prelude::*;\n");
         }
 
         // Add remaining items (use statements, main function, etc.)
         if main_items.is_empty() {
             // No main items to add
-            return self.write_content_to_file(&content, &main_file_path);
+            return plan.add_file(main_file_path, content);
         }
 
         if has_types || has_logic {
             content.push('\n');
         }
 
-        for item in main_items {
-            content.push_str(&self.source_item_to_string(item));
+        let main_items = self.prune_unused_use_statements(main_items);
+        self.push_items_with_spacing(&mut content, &main_items, None);
 
-            // No empty lines between `use` statements
-            if !item.is_use() {
-                content.push('\n');
+        plan.add_file(main_file_path, content)
+    }
+
+    /// Warns about the `#[macro_use]` modules declared after macros in the
+    /// original file, recording it in `plan`. They are declared before the
+    /// logic folder the macros move to, so they no longer see the macros if
+    /// they use them.
+    fn warn_about_macros_out_of_scope(
+        &self,
+        main_file_path: &Path,
+        config: &Config,
+        plan: &mut SplitPlan,
+    ) {
+        let mut macros = Vec::new();
+        for item in self.content().items() {
+            match item {
+                SourceItem::MacroRules(macro_item) => {
+                    macros.extend(macro_item.ident.as_ref().map(|ident| format!("{}!", ident)));
+                }
+                SourceItem::Unsplittable(syn::Item::Mod(module))
+                    if !macros.is_empty() && Self::is_macro_use_module(item) =>
+                {
+                    plan.add_warning(format!(
+                        "Module {} in {} is declared before the {} folder now, it can no longer use {}",
+                        module.ident,
+                        main_file_path.display(),
+                        config.logic_folder(),
+                        macros.join(", ")
+                    ));
+                }
+                _ => {}
             }
         }
+    }
 
-        self.write_content_to_file(&content, &main_file_path)
+    /// Checks if an item is a `#[macro_use] mod x;` declaration, whose
+    /// macros are only in scope of what is declared after it
+    fn is_macro_use_module(item: &SourceItem) -> bool {
+        let SourceItem::Unsplittable(syn::Item::Mod(module)) = item else {
+            // Not a module declaration
+            return false;
+        };
+
+        module
+            .attrs
+            .iter()
+            .any(|attribute| attribute.path().is_ident("macro_use"))
     }
 }
@@ -0,0 +1,419 @@
+use crate::prelude::*;
+use quote::ToTokens;
+use std::collections::HashSet;
+
+/// A test module moved into a file of its own, see
+/// [`RustFileContent::place_test_modules`]
+pub(super) struct TestModuleFile {
+    /// The file the tests are written to
+    pub file: PathBuf,
+    /// The items of the test module
+    pub items: Vec<SourceItem>,
+    /// The declaration of the file's module, with the test module's
+    /// attributes
+    pub declaration: ModuleDeclaration,
+}
+
+/// The part of a test module whose tests use the items of one file, written
+/// as a child module at the end of it, see
+/// [`RustFileContent::place_test_modules`]
+pub(super) struct TestModulePart {
+    /// The file the part is appended to
+    pub file: PathBuf,
+    /// The text of the child module, with the test module's attributes
+    pub text: String,
+}
+
+impl RustFileContent {
+    /// Checks if `module` is only compiled for tests
+    pub(super) fn is_test_module(&self, module: &syn::ItemMod) -> bool {
+        module.attrs.iter().any(|attr| {
+            attr.path().is_ident("cfg")
+                && attr
+                    .meta
+                    .require_list()
+                    .is_ok_and(|list| list.tokens.to_string() == "test")
+        })
+    }
+
+    /// Separates the inline test modules among `items` from the other items
+    pub(super) fn partition_test_modules(
+        &self,
+        items: &[SourceItem],
+    ) -> (Vec<SourceItem>, Vec<SourceItem>) {
+        items.iter().cloned().partition(
+            |item| matches!(item, SourceItem::Module(module) if self.is_test_module(module)),
+        )
+    }
+
+    /// Places each test module next to the code it tests, by resolving the
+    /// split items it references:
+    ///
+    /// - if they are all planned to one file, the test module moves into it,
+    ///   keeping private access to them,
+    /// - if they are planned to several files, the test module is split up
+    ///   by test function, each part becoming a child module of the file
+    ///   owning the items its tests use, see
+    ///   [`RustFileContent::split_test_module`],
+    /// - if it references none, it stays in the original file if that is
+    ///   kept,
+    /// - otherwise it moves into a sibling file of its own, `tests.rs` for
+    ///   `mod tests`, declared with the test module's attributes, e.g.
+    ///   `#[cfg(test)] mod tests;`.
+    ///
+    /// Tested items are only widened for tests using the private members of
+    /// items in several files, see [`RustFileContent::split_test_module`].
+    /// Test modules moved into planned files are added to `grouped_items` or,
+    /// for the functions file, to `logic_items`. The returned files still
+    /// have to be written and the returned parts appended to their files.
+    pub(super) fn place_test_modules(
+        &self,
+        test_modules: &[SourceItem],
+        output_dir: &Path,
+        (grouped_items, logic_items): (
+            &mut IndexMap<String, Vec<SourceItem>>,
+            &mut Vec<SourceItem>,
+        ),
+        siblings: &mut SiblingItems,
+        config: &Config,
+        plan: &SplitPlan,
+    ) -> (Vec<TestModuleFile>, Vec<TestModulePart>) {
+        let functions_file = output_dir.join(plan.renamed_file_name(
+            self.path(),
+            output_dir,
+            &config.functions_file_name(),
+        ));
+        let own_file = grouped_items
+            .contains_key(self.content().name())
+            .then(|| output_dir.join(self.content().name()));
+
+        let mut test_files = Vec::new();
+        let mut parts = Vec::new();
+        for item in test_modules {
+            let SourceItem::Module(module) = item else {
+                // Not a test module
+                continue;
+            };
+
+            let used = self.collect_used_identifiers(std::slice::from_ref(item));
+            let target = match self.tested_files(&used, siblings).as_slice() {
+                [file] => Some(file.clone()),
+                [] => own_file.clone(),
+                _ => {
+                    // Tests of items in several files follow the items they use
+                    parts.extend(self.split_test_module(module, siblings));
+                    continue;
+                }
+            };
+
+            let group = target.as_ref().and_then(|target| {
+                let file_name = target.file_name()?.to_str()?;
+                (output_dir.join(file_name) == *target && grouped_items.contains_key(file_name))
+                    .then(|| file_name.to_owned())
+            });
+
+            match (target, group) {
+                (Some(target), Some(file_name)) => {
+                    debug!(
+                        "Moving test module {} to {}",
+                        module.ident,
+                        target.display()
+                    );
+                    grouped_items[&file_name].push(item.clone());
+                    siblings.record_usage(target, used);
+                }
+                (Some(target), None) if target == functions_file && !logic_items.is_empty() => {
+                    debug!(
+                        "Moving test module {} to {}",
+                        module.ident,
+                        target.display()
+                    );
+                    logic_items.push(item.clone());
+                    siblings.record_usage(target, used);
+                }
+                _ => {
+                    let test_file = self.test_module_file(module, output_dir, plan);
+                    debug!(
+                        "Moving test module {} to {}",
+                        module.ident,
+                        test_file.file.display()
+                    );
+                    siblings.record_usage(&test_file.file, used);
+                    test_files.push(test_file);
+                }
+            }
+        }
+        (test_files, parts)
+    }
+
+    /// Splits a test module whose tests use the items of several files into
+    /// one part per file. Each test function goes to the file owning most of
+    /// the split items it uses, helper items go with the tests using them and
+    /// the use statements of the module are narrowed down to each part.
+    ///
+    /// Items of other files a test uses are imported, and the members it uses
+    /// are widened for the file the test moves to, as for any item of the
+    /// split referencing them, see [`SiblingItems::changed_visibility`].
+    fn split_test_module(
+        &self,
+        module: &syn::ItemMod,
+        siblings: &mut SiblingItems,
+    ) -> Vec<TestModulePart> {
+        let Some((_, content)) = &module.content else {
+            // Declared in a file of its own, nothing to split
+            return Vec::new();
+        };
+
+        let items: Vec<SourceItem> = content
+            .iter()
+            .filter_map(|item| SourceItem::try_from(item.clone()).ok())
+            .collect();
+        let helpers: Vec<&SourceItem> = items
+            .iter()
+            .filter(|item| !item.is_use() && !self.is_test_function(item))
+            .collect();
+
+        // Assign every test to a file, along with the helpers it uses
+        let mut assigned: IndexMap<PathBuf, HashSet<String>> = IndexMap::new();
+        for test in items.iter().filter(|item| self.is_test_function(item)) {
+            let mut used = self.collect_used_identifiers(std::slice::from_ref(test));
+            for helper in &helpers {
+                if self
+                    .defined_item_name(helper)
+                    .is_some_and(|name| used.contains(&name))
+                {
+                    used.extend(self.collect_used_identifiers(std::slice::from_ref(*helper)));
+                }
+            }
+
+            let Some(file) = self.owning_file(&used, siblings) else {
+                // Uses no split item, goes with the first part
+                continue;
+            };
+            self.record_test_usage(&file, &used, siblings);
+            let test_name = self.defined_item_name(test).unwrap_or_default();
+            used.insert(test_name);
+            assigned.entry(file).or_default().extend(used);
+        }
+        if assigned.is_empty() {
+            // Only the helpers use split items, keep the module whole
+            let used = self.collect_used_identifiers(&items);
+            if let Some(file) = self.owning_file(&used, siblings) {
+                self.record_test_usage(&file, &used, siblings);
+                assigned.insert(file, used);
+            }
+        }
+
+        let mut parts = Vec::new();
+        for (index, (file, used)) in assigned.iter().enumerate() {
+            let part_items: Vec<SourceItem> = items
+                .iter()
+                .filter(|item| {
+                    let name = self.defined_item_name(item);
+                    item.is_use()
+                        || index == 0
+                            && !assigned
+                                .values()
+                                .any(|other| name.as_ref().is_some_and(|name| other.contains(name)))
+                        || name.is_some_and(|name| used.contains(&name))
+                })
+                .cloned()
+                .collect();
+            let mut part_items = self.prune_unused_use_statements(part_items);
+            self.import_sibling_items_into_child(&mut part_items, file, siblings);
+
+            debug!(
+                "Moving part of test module {} to {}",
+                module.ident,
+                file.display()
+            );
+            parts.push(TestModulePart {
+                file: file.clone(),
+                text: self.child_module_text(module, &part_items),
+            });
+        }
+        parts
+    }
+
+    /// Checks if `item` is a test function, i.e. has a `#[test]` attribute
+    /// or one ending in `::test` like `#[tokio::test]`
+    fn is_test_function(&self, item: &SourceItem) -> bool {
+        let SourceItem::Function(function) = item else {
+            // Not a function
+            return false;
+        };
+
+        function.attrs.iter().any(|attr| {
+            attr.path()
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "test")
+        })
+    }
+
+    /// Returns the planned file defining most of the split items among
+    /// `used`, the first one in path order if several define as many
+    fn owning_file(&self, used: &HashSet<String>, siblings: &SiblingItems) -> Option<PathBuf> {
+        let mut counts: IndexMap<PathBuf, usize> = IndexMap::new();
+        for file in used.iter().filter_map(|name| siblings.files().get(name)) {
+            *counts.entry(file.clone()).or_default() += 1;
+        }
+        counts.sort_keys();
+
+        let most = counts.values().copied().max()?;
+        counts
+            .into_iter()
+            .find(|(_, count)| *count == most)
+            .map(|(file, _)| file)
+    }
+
+    /// Records the identifiers `used` by a test moving to `file` if it uses
+    /// the items of other files too, so that the members it accesses of
+    /// those are widened
+    fn record_test_usage(&self, file: &Path, used: &HashSet<String>, siblings: &mut SiblingItems) {
+        if self.tested_files(used, siblings).len() < 2 {
+            // Only uses items of its own file
+            return;
+        }
+
+        debug!(
+            "A test moving to {} uses items of other files, widening what it uses of them",
+            file.display()
+        );
+        siblings.record_usage(file, used.clone());
+    }
+
+    /// Imports the items of other files of the split which `items`, the
+    /// content of a child module of `file`, reference. The paths of
+    /// [`SiblingItems::use_statements`] are relative to `file`, one level
+    /// further up from the child module.
+    fn import_sibling_items_into_child(
+        &self,
+        items: &mut Vec<SourceItem>,
+        file: &Path,
+        siblings: &SiblingItems,
+    ) {
+        let used = self.collect_imported_identifiers(items);
+        let defined: HashSet<String> = siblings
+            .files()
+            .iter()
+            .filter(|(_, defining)| defining.as_path() == file)
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        let imports = siblings
+            .use_statements(file, &used, &defined)
+            .into_iter()
+            .map(|mut import| {
+                import.tree = match import.tree {
+                    syn::UseTree::Path(path) if path.ident == "crate" => syn::UseTree::Path(path),
+                    syn::UseTree::Path(path) if path.ident == "self" => {
+                        Self::prefixed_use_tree(*path.tree, "super", 1)
+                    }
+                    tree => Self::prefixed_use_tree(tree, "super", 1),
+                };
+                SourceItem::r#use(import)
+            });
+        let position = items
+            .iter()
+            .position(|item| !item.is_use())
+            .unwrap_or(items.len());
+        items.splice(position..position, imports);
+    }
+
+    /// Builds the text of a child module named and attributed like `module`
+    /// holding `items`, indented by one level
+    fn child_module_text(&self, module: &syn::ItemMod, items: &[SourceItem]) -> String {
+        let mut body = String::new();
+        self.push_items_with_spacing(&mut body, items, None);
+
+        let mut text: String = self
+            .attribute_texts(&module.attrs)
+            .into_iter()
+            .map(|attribute| attribute + "\n")
+            .collect();
+        let visibility = module.vis.to_token_stream().to_string();
+        if !visibility.is_empty() {
+            text.push_str(&visibility);
+            text.push(' ');
+        }
+        text.push_str(&format!("mod {} {{\n", module.ident));
+        for line in body.trim_end().lines() {
+            if !line.is_empty() {
+                text.push_str("    ");
+                text.push_str(line);
+            }
+            text.push('\n');
+        }
+        text.push_str("}\n");
+        text
+    }
+
+    /// Returns the planned files defining split items among `used`
+    fn tested_files(&self, used: &HashSet<String>, siblings: &SiblingItems) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = used
+            .iter()
+            .filter_map(|name| siblings.files().get(name).cloned())
+            .collect();
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// Builds the file a test module is moved to: named like the module in
+    /// `output_dir`, or prefixed with the original module's name if that is
+    /// taken already
+    fn test_module_file(
+        &self,
+        module: &syn::ItemMod,
+        output_dir: &Path,
+        plan: &SplitPlan,
+    ) -> TestModuleFile {
+        let mut name = module.ident.to_string();
+        let is_taken = |name: &str| {
+            let file = output_dir.join(format!("{}{}", name, Self::RS_EXTENSION));
+            plan.files().contains_key(&file) || file.exists()
+        };
+        if is_taken(&name) {
+            name = format!("{}_{}", self.module_name(), name);
+        }
+
+        let items = module
+            .content
+            .iter()
+            .flat_map(|(_, items)| items.iter().cloned())
+            .filter_map(|item| SourceItem::try_from(item).ok())
+            .collect();
+        let declaration = ModuleDeclaration::builder()
+            .name(name.clone())
+            .attributes(self.attribute_texts(&module.attrs))
+            .build();
+
+        TestModuleFile {
+            file: output_dir.join(format!("{}{}", name, Self::RS_EXTENSION)),
+            items,
+            declaration,
+        }
+    }
+
+    /// Returns the attributes and doc comments as written in the original
+    /// file, or re-printed if they are not found in it
+    fn attribute_texts(&self, attrs: &[syn::Attribute]) -> Vec<String> {
+        let source = self.content().source();
+        attrs
+            .iter()
+            .map(|attr| {
+                let tokens = attr.to_token_stream();
+                let start = tokens.clone().into_iter().next().map(|first| first.span());
+                let end = tokens.into_iter().last().map(|last| last.span());
+                start
+                    .zip(end)
+                    .and_then(|(start, end)| {
+                        source.get(start.byte_range().start..end.byte_range().end)
+                    })
+                    .filter(|text| text.starts_with('#') || text.starts_with("//"))
+                    .map_or_else(|| attr.to_token_stream().to_string(), str::to_owned)
+            })
+            .collect()
+    }
+}
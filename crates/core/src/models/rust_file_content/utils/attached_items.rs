@@ -0,0 +1,132 @@
+use crate::prelude::*;
+
+impl RustFileContent {
+    /// Checks if an item is placed by the file it is attached to rather than
+    /// by a name of its own: constants, statics, foreign blocks, trait
+    /// aliases and macro invocations declaring no type
+    pub(crate) fn is_attached_item(&self, item: &SourceItem) -> bool {
+        match item {
+            SourceItem::MacroInvocation(invocation) => invocation.declared_type_name().is_none(),
+            _ => matches!(
+                item,
+                SourceItem::Const(_)
+                    | SourceItem::Static(_)
+                    | SourceItem::ForeignMod(_)
+                    | SourceItem::TraitAlias(_)
+            ),
+        }
+    }
+
+    /// Sorts the attached `item` into the types, logic or other items of a
+    /// split, by the file it is placed in, see
+    /// [`RustFileContent::attached_item_file_name`]
+    pub(crate) fn categorize_attached_item(
+        &self,
+        item: &SourceItem,
+        config: &Config,
+        (type_items, logic_items, other_items): (
+            &mut Vec<SourceItem>,
+            &mut Vec<SourceItem>,
+            &mut Vec<SourceItem>,
+        ),
+    ) {
+        match self.attached_item_file_name(item, config) {
+            Some(file_name) if file_name == config.functions_file_name() => {
+                logic_items.push(item.clone())
+            }
+            Some(_) => type_items.push(item.clone()),
+            None => other_items.push(item.clone()),
+        }
+    }
+
+    /// Assigns the attached items among `items` which move out of the
+    /// original file to the group of the file they are placed in, see
+    /// [`RustFileContent::attached_item_group`]. Constants, statics and
+    /// foreign blocks come first in a group, in the order they are defined
+    /// in, trait aliases and macro invocations declaring nothing follow the
+    /// type they name.
+    pub(super) fn assign_attached_items(
+        &self,
+        groups: &mut IndexMap<String, Vec<SourceItem>>,
+        items: &[SourceItem],
+        config: &Config,
+    ) {
+        for item in items {
+            let Some(file_name) = self.attached_item_group(item, items, config) else {
+                // Not attached, or staying in the original file
+                continue;
+            };
+
+            let group = groups.entry(file_name).or_default();
+            if Self::is_attached_after_type(item) {
+                group.push(item.clone());
+                continue;
+            }
+
+            // Before the items using them
+            let position = group
+                .iter()
+                .take_while(|grouped| {
+                    self.is_attached_item(grouped) && !Self::is_attached_after_type(grouped)
+                })
+                .count();
+            group.insert(position, item.clone());
+        }
+    }
+
+    /// The group of the attached `item` when splitting `items`. `None` if it
+    /// is not attached or stays in the original file, which is the case if
+    /// the functions it is attached to stay there, or if the type it is
+    /// attached to is not among `items`.
+    pub(super) fn attached_item_group(
+        &self,
+        item: &SourceItem,
+        items: &[SourceItem],
+        config: &Config,
+    ) -> Option<String> {
+        let file_name = self.attached_item_file_name(item, config)?;
+        if file_name == config.functions_file_name() {
+            // Functions are not grouped
+            return None;
+        }
+
+        let is_type_file = |other: &SourceItem| {
+            self.extract_type_name_from_item(other).is_some_and(|name| {
+                format!("{}{}", self.to_snake_case(&name), Self::RS_EXTENSION) == file_name
+            })
+        };
+        if self.content().items().iter().any(is_type_file) && !items.iter().any(is_type_file) {
+            // Its type is not split out along with it
+            return None;
+        }
+        Some(file_name)
+    }
+
+    /// The file the attached `item` is placed in, `None` if it is not
+    /// attached or stays in the original file
+    fn attached_item_file_name(&self, item: &SourceItem, config: &Config) -> Option<String> {
+        if !self.is_attached_item(item) {
+            // Placed by a name of its own
+            return None;
+        }
+
+        match item {
+            SourceItem::Const(_) | SourceItem::Static(_) => self.constant_file_name(item, config),
+            SourceItem::ForeignMod(_) => self.foreign_block_file_name(item, config),
+            SourceItem::TraitAlias(alias) => self.trait_alias_file_name(alias),
+            SourceItem::MacroInvocation(invocation) => {
+                self.macro_invocation_file_name(item, invocation, config)
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks if the attached `item` is written after the type it is
+    /// attached to, which it refers to rather than being used by it
+    fn is_attached_after_type(item: &SourceItem) -> bool {
+        match item {
+            SourceItem::MacroInvocation(invocation) => invocation.declared_item_names().is_empty(),
+            _ => item.is_trait_alias(),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use crate::prelude::*;
+
+impl RustFileContent {
+    /// File, next to the split files, constants and statics are collected in
+    /// with [`ConstPlacement::Shared`]
+    pub const CONSTANTS_RS: &'static str = "constants.rs";
+
+    /// The file the constant or static `item` is placed in: its own file,
+    /// [`RustFileContent::CONSTANTS_RS`] or the file of the only item of this
+    /// file using it, depending on the constant placement of `config`.
+    ///
+    /// `None` if it stays in the original file, which is the case for a
+    /// constant used by several items, by none or by items staying there.
+    pub(super) fn constant_file_name(&self, item: &SourceItem, config: &Config) -> Option<String> {
+        let name = self.defined_item_name(item)?;
+        match config.constants() {
+            ConstPlacement::Dedicated => Some(format!(
+                "{}{}",
+                self.to_snake_case(&name),
+                Self::RS_EXTENSION
+            )),
+            ConstPlacement::Shared => Some(Self::CONSTANTS_RS.to_owned()),
+            ConstPlacement::WithUser => self.sole_user_file_name(item, config),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use crate::prelude::*;
+
+impl RustFileContent {
+    /// File, next to the split files, foreign blocks which are not used by a
+    /// single type or the functions only are collected in
+    pub const FFI_RS: &'static str = "ffi.rs";
+
+    /// The file the foreign block `item` is placed in: the file of the only
+    /// type or the functions using what it declares, or else
+    /// [`RustFileContent::FFI_RS`].
+    ///
+    /// `None` if this file has nothing else to split, like a file of
+    /// bindings, which is then kept as it is.
+    pub(super) fn foreign_block_file_name(
+        &self,
+        item: &SourceItem,
+        config: &Config,
+    ) -> Option<String> {
+        let has_other_items = self
+            .content()
+            .items()
+            .iter()
+            .any(|other| self.is_type_item(other) || matches!(other, SourceItem::Function(_)));
+        if !has_other_items {
+            // Nothing to place it next to
+            return None;
+        }
+
+        Some(
+            self.sole_user_file_name(item, config)
+                .unwrap_or_else(|| Self::FFI_RS.to_owned()),
+        )
+    }
+}
@@ -2,7 +2,7 @@ use crate::prelude::*;
 
 impl RustFileContent {
     /// Formats a TokenStream using prettyplease or falls back to string conversion
-    pub(super) fn format_token_stream(&self, token_stream: proc_macro2::TokenStream) -> String {
+    pub(crate) fn format_token_stream(&self, token_stream: proc_macro2::TokenStream) -> String {
         // Parse the token stream back to a syn::File to format it properly
         if let Ok(file) = syn::parse2::<syn::File>(token_stream.clone()) {
             prettyplease::unparse(&file)
@@ -13,7 +13,7 @@ impl RustFileContent {
     }
 
     /// Converts a SourceItem to a TokenStream
-    pub(super) fn convert_item_to_token_stream(
+    pub(crate) fn convert_item_to_token_stream(
         &self,
         item: &SourceItem,
     ) -> proc_macro2::TokenStream {
@@ -26,9 +26,16 @@ impl RustFileContent {
             SourceItem::Type(ty) => ty.to_token_stream(),
             SourceItem::Union(u) => u.to_token_stream(),
             SourceItem::Function(f) => f.to_token_stream(),
+            SourceItem::Const(c) => c.to_token_stream(),
+            SourceItem::Static(s) => s.to_token_stream(),
+            SourceItem::ForeignMod(f) => f.to_token_stream(),
+            SourceItem::ExternCrate(e) => e.to_token_stream(),
+            SourceItem::TraitAlias(t) => t.to_token_stream(),
             SourceItem::MacroRules(m) => m.to_token_stream(),
+            SourceItem::MacroInvocation(m) => m.to_token_stream(),
             SourceItem::Impl(i) => i.to_token_stream(),
             SourceItem::Use(u) => u.to_token_stream(),
+            SourceItem::Module(m) => m.to_token_stream(),
             SourceItem::Unsplittable(item) => item.to_token_stream(),
             SourceItem::Verbatim(tokens) => tokens.clone(),
         }
@@ -43,20 +50,26 @@ impl RustFileContent {
             .collect()
     }
 
-    /// Groups type definitions (structs, enums, traits, etc.) with their use statements
+    /// Groups type definitions (structs, enums, traits, etc.) by target file
     pub(super) fn group_type_definitions(
         &self,
         groups: &mut IndexMap<String, Vec<SourceItem>>,
         items: &[SourceItem],
-        use_statements: &[SourceItem],
+        config: &Config,
     ) {
         for item in items {
             if let Some(type_name) = self.extract_type_name_from_item(item) {
                 let file_name = format!("{}{}", self.to_snake_case(&type_name), Self::RS_EXTENSION);
-                self.add_item_to_group(groups, file_name, use_statements, item);
+                self.add_item_to_group(groups, file_name, item);
+            } else if self.attached_item_group(item, items, config).is_some() {
+                // Placed once the groups of their users are known
+                continue;
             } else if self.is_non_type_item(item) {
                 // Functions, macros, etc. go to the original file
                 self.add_item_to_original_file(groups, item);
+            } else if item.is_use() && !self.is_private_use(item) {
+                // Re-exports are part of the original module's API
+                self.add_item_to_original_file(groups, item);
             }
         }
     }
@@ -75,18 +88,14 @@ impl RustFileContent {
         )
     }
 
-    /// Adds an item and its use statements to a specific group
+    /// Adds an item to a specific group
     pub(super) fn add_item_to_group(
         &self,
         groups: &mut IndexMap<String, Vec<SourceItem>>,
         file_name: String,
-        use_statements: &[SourceItem],
         item: &SourceItem,
     ) {
-        let group = groups.entry(file_name).or_default();
-        // Add use statements first, then the item
-        group.extend(use_statements.iter().cloned());
-        group.push(item.clone());
+        groups.entry(file_name).or_default().push(item.clone());
     }
 
     /// Assigns impl blocks to their corresponding type files
@@ -94,26 +103,34 @@ impl RustFileContent {
         &self,
         groups: &mut IndexMap<String, Vec<SourceItem>>,
         items: &[SourceItem],
+        config: &Config,
     ) {
         let impl_blocks = self.collect_impl_blocks(items);
 
         for impl_item in impl_blocks {
-            self.assign_single_impl_block(groups, &impl_item);
+            self.assign_single_impl_block(groups, &impl_item, config);
         }
     }
 
-    /// Assigns a single impl block to its target type file
+    /// Assigns a single impl block to its target type file, or to the file
+    /// the trait impl policy of `config` places it in
     pub(super) fn assign_single_impl_block(
         &self,
         groups: &mut IndexMap<String, Vec<SourceItem>>,
         impl_item: &SourceItem,
+        config: &Config,
     ) {
         let SourceItem::Impl(impl_block) = impl_item else {
             return;
         };
 
-        let Some(type_name) = self.extract_impl_target_type(impl_block) else {
-            // Can't determine target type, put in original file
+        if self.assign_trait_impl_by_policy(groups, impl_item, impl_block, config) {
+            // Placed away from its type
+            return;
+        }
+
+        let Some(type_name) = self.resolve_impl_target_type(impl_block) else {
+            // No local type or trait it belongs with, put in original file
             self.add_item_to_original_file(groups, impl_item);
             return;
         };
@@ -138,22 +155,6 @@ impl RustFileContent {
         groups.get_mut(file_name).unwrap().push(impl_item.clone());
     }
 
-    /// Extract the target type name from an impl block
-    pub(super) fn extract_impl_target_type(&self, impl_block: &syn::ItemImpl) -> Option<String> {
-        // Handle impl blocks like "impl SomeType" or "impl SomeTrait for SomeType"
-        let syn::Type::Path(type_path) = impl_block.self_ty.as_ref() else {
-            // Not a path type, can't extract name
-            return None;
-        };
-
-        let Some(segment) = type_path.path.segments.last() else {
-            // No segments in path
-            return None;
-        };
-
-        Some(segment.ident.to_string())
-    }
-
     /// Collects all impl blocks from the items
     pub(super) fn collect_impl_blocks(&self, items: &[SourceItem]) -> Vec<SourceItem> {
         items
@@ -182,13 +183,14 @@ impl RustFileContent {
     }
 
     /// Extracts the type name from various SourceItem types
-    pub(super) fn extract_type_name_from_item(&self, item: &SourceItem) -> Option<String> {
+    pub(crate) fn extract_type_name_from_item(&self, item: &SourceItem) -> Option<String> {
         match item {
             SourceItem::Struct(s) => Some(s.ident.to_string()),
             SourceItem::Enum(e) => Some(e.ident.to_string()),
             SourceItem::Trait(t) => Some(t.ident.to_string()),
             SourceItem::Type(ty) => Some(ty.ident.to_string()),
             SourceItem::Union(u) => Some(u.ident.to_string()),
+            SourceItem::MacroInvocation(m) => m.declared_type_name(),
             _ => None,
         }
     }
@@ -0,0 +1,302 @@
+use crate::prelude::*;
+use std::collections::HashSet;
+
+impl RustFileContent {
+    /// Resolves the type or trait defined in this file an impl block belongs
+    /// with, whose file it is moved to.
+    ///
+    /// The self type is searched for types defined in this file, looking
+    /// through references, pointers, slices, tuples and the generic arguments
+    /// of wrappers like `Box<Foo>` or `Vec<Foo<T>>`. The outermost one found
+    /// is the most specific, e.g. `Wrapper` for `Wrapper<Foo>`. Paths naming
+    /// another module, like `other::Foo`, are not local even if a `Foo` is
+    /// defined here.
+    ///
+    /// Blanket impls (`impl<T: X> Y for T`) and impls for foreign types go to
+    /// the file of the trait, if it is defined here. Returns `None` if the impl
+    /// belongs with nothing defined in this file.
+    pub(super) fn resolve_impl_target_type(&self, impl_block: &syn::ItemImpl) -> Option<String> {
+        let local_types = self.local_type_names();
+        let type_name = self
+            .self_type_paths(impl_block)
+            .iter()
+            .find_map(|path| self.local_path_name(path, &local_types));
+        if type_name.is_some() {
+            return type_name;
+        }
+
+        let (_, trait_path, _) = impl_block.trait_.as_ref()?;
+        let trait_name = self.local_path_name(trait_path, &local_types)?;
+        if self.is_blanket_self_type(&impl_block.self_ty, &self.generic_params(impl_block)) {
+            debug!(
+                "Blanket impl of {} goes to the file of the trait",
+                trait_name
+            );
+        }
+        Some(trait_name)
+    }
+
+    /// The names of the types, traits and type aliases defined in this file
+    pub(super) fn local_type_names(&self) -> HashSet<String> {
+        self.content()
+            .items()
+            .iter()
+            .filter_map(|item| self.extract_type_name_from_item(item))
+            .collect()
+    }
+
+    /// Collects the paths of the types named in the self type of
+    /// `impl_block`, outermost first, skipping the impl's generic parameters
+    pub(crate) fn self_type_paths(&self, impl_block: &syn::ItemImpl) -> Vec<syn::Path> {
+        let mut paths = Vec::new();
+        self.collect_type_paths(
+            &impl_block.self_ty,
+            &self.generic_params(impl_block),
+            &mut paths,
+        );
+        paths
+    }
+
+    /// The names of the generic type parameters of `impl_block`
+    fn generic_params(&self, impl_block: &syn::ItemImpl) -> HashSet<String> {
+        impl_block
+            .generics
+            .type_params()
+            .map(|param| param.ident.to_string())
+            .collect()
+    }
+
+    /// Collects the paths of the types named in `ty`, outermost first
+    fn collect_type_paths(
+        &self,
+        ty: &syn::Type,
+        generic_params: &HashSet<String>,
+        paths: &mut Vec<syn::Path>,
+    ) {
+        match ty {
+            syn::Type::Reference(reference) => {
+                self.collect_type_paths(&reference.elem, generic_params, paths)
+            }
+            syn::Type::Ptr(pointer) => {
+                self.collect_type_paths(&pointer.elem, generic_params, paths)
+            }
+            syn::Type::Paren(paren) => self.collect_type_paths(&paren.elem, generic_params, paths),
+            syn::Type::Group(group) => self.collect_type_paths(&group.elem, generic_params, paths),
+            syn::Type::Slice(slice) => self.collect_type_paths(&slice.elem, generic_params, paths),
+            syn::Type::Array(array) => self.collect_type_paths(&array.elem, generic_params, paths),
+            syn::Type::Tuple(tuple) => tuple
+                .elems
+                .iter()
+                .for_each(|elem| self.collect_type_paths(elem, generic_params, paths)),
+            syn::Type::TraitObject(trait_object) => {
+                // `impl dyn Foo` belongs with the trait `Foo`
+                for bound in &trait_object.bounds {
+                    if let syn::TypeParamBound::Trait(bound) = bound {
+                        paths.push(bound.path.clone());
+                    }
+                }
+            }
+            syn::Type::Path(type_path) => {
+                if let Some(qself) = &type_path.qself {
+                    // `<Foo as Trait>::Output`
+                    self.collect_type_paths(&qself.ty, generic_params, paths);
+                    return;
+                }
+
+                let is_generic_param = type_path
+                    .path
+                    .get_ident()
+                    .is_some_and(|ident| generic_params.contains(&ident.to_string()));
+                if !is_generic_param {
+                    paths.push(type_path.path.clone());
+                }
+
+                // Generic arguments, e.g. `Foo` in `Box<Foo>`
+                let Some(last) = type_path.path.segments.last() else {
+                    // Empty path
+                    return;
+                };
+                let syn::PathArguments::AngleBracketed(arguments) = &last.arguments else {
+                    // No generic arguments
+                    return;
+                };
+                for argument in &arguments.args {
+                    if let syn::GenericArgument::Type(argument) = argument {
+                        self.collect_type_paths(argument, generic_params, paths);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the name of the item `path` refers to if it is defined in this
+    /// file: a single segment, or a path through `self::` or through this
+    /// file's module path from `crate::`
+    pub(super) fn local_path_name(
+        &self,
+        path: &syn::Path,
+        local_types: &HashSet<String>,
+    ) -> Option<String> {
+        let mut segments: Vec<String> = path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect();
+        let name = segments.pop()?;
+        if !local_types.contains(&name) {
+            // Not defined in this file
+            return None;
+        }
+
+        let is_local = match segments.first().map(String::as_str) {
+            None => true,
+            Some("self") => segments.len() == 1,
+            Some("crate") => self.crate_module_path().as_deref() == Some(&segments[1..]),
+            Some(_) => false,
+        };
+        is_local.then_some(name)
+    }
+
+    /// Checks if `ty` is made of nothing but the impl's generic parameters,
+    /// like `T`, `&T` or `Box<T>`
+    fn is_blanket_self_type(&self, ty: &syn::Type, generic_params: &HashSet<String>) -> bool {
+        match ty {
+            syn::Type::Reference(reference) => {
+                self.is_blanket_self_type(&reference.elem, generic_params)
+            }
+            syn::Type::Path(type_path) => {
+                if let Some(ident) = type_path.path.get_ident() {
+                    return generic_params.contains(&ident.to_string());
+                }
+
+                let Some(syn::PathArguments::AngleBracketed(arguments)) =
+                    type_path.path.segments.last().map(|last| &last.arguments)
+                else {
+                    // A concrete foreign type
+                    return false;
+                };
+                arguments.args.iter().all(|argument| match argument {
+                    syn::GenericArgument::Type(argument) => {
+                        self.is_blanket_self_type(argument, generic_params)
+                    }
+                    _ => true,
+                })
+            }
+            _ => false,
+        }
+    }
+
+    /// The module path of this file from the crate root, e.g. `["foo", "bar"]`
+    /// for `src/foo/bar.rs`, or `None` if it is not part of a crate
+    pub(crate) fn crate_module_path(&self) -> Option<Vec<String>> {
+        let crate_root = find_crate_root(self.path())?;
+        let relative = self.path().strip_prefix(crate_root.parent()?).ok()?;
+
+        let mut module: Vec<String> = relative
+            .iter()
+            .map(|segment| segment.to_string_lossy().into_owned())
+            .collect();
+        let file_name = module.pop()?;
+        let is_module_root = file_name == Self::MOD_RS
+            || (module.is_empty() && [Self::LIB_RS, Self::MAIN_RS].contains(&file_name.as_str()));
+        if !is_module_root {
+            module.push(file_name.trim_end_matches(Self::RS_EXTENSION).to_owned());
+        }
+        Some(module)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rust_file(source: &str) -> RustFileContent {
+        let items = syn::parse_file(source)
+            .unwrap()
+            .items
+            .into_iter()
+            .map(SourceItem::try_from)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        NodeContent::builder()
+            .name("test.rs".to_string())
+            .path(PathBuf::from("test.rs"))
+            .content(
+                NamedSourceItems::builder()
+                    .name("test.rs".to_string())
+                    .items(items)
+                    .source(source.to_string())
+                    .build(),
+            )
+            .build()
+    }
+
+    fn targets(source: &str) -> Vec<Option<String>> {
+        let file = rust_file(source);
+        file.content()
+            .items()
+            .iter()
+            .filter_map(|item| match item {
+                SourceItem::Impl(impl_block) => Some(file.resolve_impl_target_type(impl_block)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_self_types_are_looked_through() {
+        let source = r#"
+            struct Foo<'a>(&'a str);
+            struct Wrapper<T>(T);
+            trait Shape {}
+
+            impl Shape for &Foo<'_> {}
+            impl Shape for Box<Foo<'static>> {}
+            impl<T> Shape for Vec<Foo<T>> {}
+            impl Foo<'_> {}
+            impl Shape for Wrapper<Foo<'static>> {}
+            impl Shape for *const [Foo<'static>] {}
+            impl Shape for self::Foo<'static> {}
+        "#;
+
+        assert_eq!(
+            targets(source),
+            vec![
+                Some("Foo".to_owned()),
+                Some("Foo".to_owned()),
+                Some("Foo".to_owned()),
+                Some("Foo".to_owned()),
+                Some("Wrapper".to_owned()),
+                Some("Foo".to_owned()),
+                Some("Foo".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_other_modules_and_blanket_impls() {
+        let source = r#"
+            struct Foo;
+            trait Shape {}
+
+            impl std::fmt::Display for other::Foo {}
+            impl<T: Clone> Shape for T {}
+            impl<T> Shape for Box<T> {}
+            impl Shape for String {}
+            impl Clone for Vec<u8> {}
+        "#;
+
+        assert_eq!(
+            targets(source),
+            vec![
+                None,
+                Some("Shape".to_owned()),
+                Some("Shape".to_owned()),
+                Some("Shape".to_owned()),
+                None,
+            ]
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use crate::prelude::*;
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use quote::ToTokens;
+use std::ops::Range;
+
+impl RustFileContent {
+    /// Collects the visibility changes `item` needs when planned to
+    /// `target_file`, see [`SiblingItems::changed_visibility`], for the item
+    /// itself, the fields of a struct, the items of a foreign block and the
+    /// methods and constants of an inherent impl.
+    ///
+    /// Each change is the byte range of the visibility in the original file,
+    /// empty for private items, and the visibility written instead.
+    pub(super) fn visibility_changes(
+        &self,
+        item: &SourceItem,
+        target_file: &Path,
+        siblings: &SiblingItems,
+    ) -> Vec<(Range<usize>, String)> {
+        let mut changes = Vec::new();
+
+        if let (Some(name), Some(visibility)) =
+            (self.defined_item_name(item), self.item_visibility(item))
+            && let Some(changed) = siblings.changed_visibility(&name, target_file, visibility)
+            && let Some(range) = self.visibility_byte_range(self.convert_item_to_token_stream(item))
+        {
+            changes.push((range, changed));
+        }
+
+        if let SourceItem::Struct(struct_item) = item {
+            changes.extend(self.field_visibility_changes(struct_item, target_file, siblings));
+        }
+
+        if let SourceItem::ForeignMod(foreign_mod) = item {
+            changes.extend(self.foreign_item_visibility_changes(
+                foreign_mod,
+                target_file,
+                siblings,
+            ));
+        }
+
+        let SourceItem::Impl(impl_block) = item else {
+            // No associated items
+            return changes;
+        };
+
+        if impl_block.trait_.is_some() {
+            // Items of trait impls have the visibility of the trait
+            return changes;
+        }
+
+        for impl_item in &impl_block.items {
+            let (name, visibility) = match impl_item {
+                syn::ImplItem::Fn(method) => (method.sig.ident.to_string(), &method.vis),
+                syn::ImplItem::Const(constant) => (constant.ident.to_string(), &constant.vis),
+                _ => continue,
+            };
+
+            if let Some(changed) = siblings.changed_visibility(&name, target_file, visibility)
+                && let Some(range) = self.visibility_byte_range(impl_item.to_token_stream())
+            {
+                changes.push((range, changed));
+            }
+        }
+        changes
+    }
+
+    /// Collects the visibility changes of the fields of a struct. Named fields
+    /// are matched by their name, tuple fields by the name of the struct since
+    /// they are accessed by index.
+    fn field_visibility_changes(
+        &self,
+        struct_item: &syn::ItemStruct,
+        target_file: &Path,
+        siblings: &SiblingItems,
+    ) -> Vec<(Range<usize>, String)> {
+        struct_item
+            .fields
+            .iter()
+            .filter_map(|field| {
+                let name = field
+                    .ident
+                    .as_ref()
+                    .unwrap_or(&struct_item.ident)
+                    .to_string();
+                let changed = siblings.changed_visibility(&name, target_file, &field.vis)?;
+                let range = self.visibility_byte_range(field.to_token_stream())?;
+                Some((range, changed))
+            })
+            .collect()
+    }
+
+    /// Collects the visibility changes of the functions, statics and types a
+    /// foreign block declares
+    fn foreign_item_visibility_changes(
+        &self,
+        foreign_mod: &syn::ItemForeignMod,
+        target_file: &Path,
+        siblings: &SiblingItems,
+    ) -> Vec<(Range<usize>, String)> {
+        foreign_mod
+            .items
+            .iter()
+            .filter_map(|foreign_item| {
+                let (name, visibility) = match foreign_item {
+                    syn::ForeignItem::Fn(function) => (&function.sig.ident, &function.vis),
+                    syn::ForeignItem::Static(item) => (&item.ident, &item.vis),
+                    syn::ForeignItem::Type(item) => (&item.ident, &item.vis),
+                    _ => return None,
+                };
+                let changed =
+                    siblings.changed_visibility(&name.to_string(), target_file, visibility)?;
+                let range = self.visibility_byte_range(foreign_item.to_token_stream())?;
+                Some((range, changed))
+            })
+            .collect()
+    }
+
+    /// Returns the text of `range` in `source` with the visibilities in
+    /// `changes` replaced
+    pub(super) fn text_with_visibility_changes(
+        &self,
+        source: &str,
+        range: Range<usize>,
+        changes: &[(Range<usize>, String)],
+    ) -> String {
+        let mut changes: Vec<&(Range<usize>, String)> = changes
+            .iter()
+            .filter(|(changed, _)| range.contains(&changed.start))
+            .collect();
+        changes.sort_by_key(|(changed, _)| changed.start);
+
+        let mut text = String::new();
+        let mut position = range.start;
+        for (changed, visibility) in changes {
+            text.push_str(&source[position..changed.start]);
+            text.push_str(visibility);
+            if changed.is_empty() {
+                // Inserted in front of the item keyword
+                text.push(' ');
+            }
+            position = changed.end;
+        }
+        text.push_str(&source[position..range.end]);
+        text
+    }
+
+    /// Returns the visibility of items which can have one
+    pub(crate) fn item_visibility<'a>(&self, item: &'a SourceItem) -> Option<&'a syn::Visibility> {
+        match item {
+            SourceItem::Struct(item) => Some(&item.vis),
+            SourceItem::Enum(item) => Some(&item.vis),
+            SourceItem::Trait(item) => Some(&item.vis),
+            SourceItem::Type(item) => Some(&item.vis),
+            SourceItem::Union(item) => Some(&item.vis),
+            SourceItem::Function(item) => Some(&item.vis),
+            SourceItem::Const(item) => Some(&item.vis),
+            SourceItem::Static(item) => Some(&item.vis),
+            SourceItem::TraitAlias(item) => Some(&item.vis),
+            _ => None,
+        }
+    }
+
+    /// Checks if `item` is declared `pub`, or for foreign blocks and macro
+    /// invocations, if any item they declare is. Re-exports (`pub use`) and
+    /// public modules count as well.
+    pub(crate) fn is_public_item(&self, item: &SourceItem) -> bool {
+        let is_public =
+            |visibility: &syn::Visibility| matches!(visibility, syn::Visibility::Public(_));
+        match item {
+            SourceItem::Use(item) => is_public(&item.vis),
+            SourceItem::Module(item) => is_public(&item.vis),
+            SourceItem::ExternCrate(item) => is_public(&item.vis),
+            SourceItem::Unsplittable(syn::Item::Use(item)) => is_public(&item.vis),
+            SourceItem::Unsplittable(syn::Item::Mod(item)) => is_public(&item.vis),
+            SourceItem::ForeignMod(foreign_mod) => {
+                foreign_mod
+                    .items
+                    .iter()
+                    .any(|foreign_item| match foreign_item {
+                        syn::ForeignItem::Fn(function) => is_public(&function.vis),
+                        syn::ForeignItem::Static(item) => is_public(&item.vis),
+                        syn::ForeignItem::Type(item) => is_public(&item.vis),
+                        _ => false,
+                    })
+            }
+            SourceItem::MacroInvocation(invocation) => invocation.declares_public_item(),
+            _ => self.item_visibility(item).is_some_and(is_public),
+        }
+    }
+
+    /// Finds the byte range of the visibility in the original file of the
+    /// item made of `tokens`, which follows its attributes. For private items
+    /// the range is empty and placed at the first token after the attributes.
+    pub(crate) fn visibility_byte_range(&self, tokens: TokenStream) -> Option<Range<usize>> {
+        let mut tokens = tokens.into_iter().peekable();
+
+        // Attributes and doc comments are `#` followed by a bracketed group
+        while matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == '#') {
+            tokens.next();
+            tokens.next();
+        }
+
+        let first = tokens.next()?;
+        let start = first.span().byte_range().start;
+        if !matches!(&first, TokenTree::Ident(ident) if ident == "pub") {
+            // Private
+            return Some(start..start);
+        }
+
+        let end = match tokens.peek() {
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+                group.span().byte_range().end
+            }
+            _ => first.span().byte_range().end,
+        };
+        Some(start..end)
+    }
+}
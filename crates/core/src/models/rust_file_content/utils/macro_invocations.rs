@@ -0,0 +1,35 @@
+use crate::prelude::*;
+
+impl RustFileContent {
+    /// The file the macro `invocation` is placed in, `None` if it stays in
+    /// the original file.
+    ///
+    /// An invocation declaring items, like the `static` of `thread_local!`,
+    /// goes with their only user, see
+    /// [`RustFileContent::sole_user_file_name`]. Otherwise it goes to the
+    /// file of the first type of this file it names, e.g. `circle.rs` for
+    /// `impl_ops!(Circle);`, as it presumably expands to code for that type.
+    pub(super) fn macro_invocation_file_name(
+        &self,
+        item: &SourceItem,
+        invocation: &MacroInvocation,
+        config: &Config,
+    ) -> Option<String> {
+        if !invocation.declared_item_names().is_empty() {
+            // Defines something others may use
+            return self.sole_user_file_name(item, config);
+        }
+
+        let local_types = self.local_type_names();
+        let type_name = invocation
+            .idents()
+            .into_iter()
+            .find(|ident| local_types.contains(ident))?;
+
+        Some(format!(
+            "{}{}",
+            self.to_snake_case(&type_name),
+            Self::RS_EXTENSION
+        ))
+    }
+}
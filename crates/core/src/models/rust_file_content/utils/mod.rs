@@ -1,38 +1,76 @@
+mod attached_items;
+mod constants;
 mod convert_doc_attributes_to_comments;
+mod foreign_blocks;
 mod helpers;
+mod impl_target;
+mod item_visibility;
+mod macro_invocations;
+mod original_source_text;
+mod prune_use_statements;
+mod sibling_imports;
+mod sole_user;
 mod to_snake_case;
+mod trait_aliases;
+mod trait_impls;
 
 use crate::prelude::*;
+use std::ops::Range;
 
 impl RustFileContent {
-    /// Builds file content for organized items with proper prelude import
-    pub(super) fn build_organized_file_content(&self, items: &[SourceItem]) -> String {
+    /// Builds the content of `target_file` for organized items. If the items
+    /// reference sibling items of other files, the file starts with the
+    /// configured prelude import, or imports them explicitly without a
+    /// prelude. Items other files of the split need are made visible to them,
+    /// and relative imports are adjusted to the depth of the file.
+    pub(super) fn build_organized_file_content(
+        &self,
+        items: &[SourceItem],
+        target_file: &Path,
+        siblings: &SiblingItems,
+        config: &Config,
+    ) -> String {
         let mut content = String::new();
 
-        // Add prelude import at the top
-        content.push_str(Self::PRELUDE_IMPORT);
-
-        // Add items with proper spacing between different items
-        for item in items {
-            content.push_str(&self.source_item_to_string(item));
-            content.push('\n');
-            if item.is_impl() {
-                content.push('\n');
-            }
+        // Add the imports the items need, followed by the items themselves
+        let mut items = self.with_needed_use_statements(items);
+        self.deepen_relative_imports(&mut items, target_file, siblings);
+        let sibling_imports = self.sibling_imports(&items, target_file, siblings);
+        if config.prelude().is_none() {
+            self.insert_sibling_imports(&mut items, sibling_imports);
+        } else if !sibling_imports.is_empty() {
+            // The prelude re-exports the sibling items, import it at the top
+            content.push_str(&config.prelude_import());
         }
+        self.push_items_with_spacing(&mut content, &items, Some((target_file, siblings)));
 
         content
     }
 
-    /// Writes content string to the specified file path
-    pub(super) fn write_content_to_file(&self, content: &str, file_path: &Path) -> Result<()> {
-        std::fs::write(file_path, content).map_err(|e| {
-            Error::bail(format!(
-                "Failed to write file {}: {}",
-                file_path.display(),
-                e
-            ))
-        })
+    /// Appends items to `content`, separated by blank lines except between
+    /// consecutive use statements or extern crates. If the items are planned
+    /// to a file split out together with `siblings`, their visibility is
+    /// adapted to it.
+    pub(super) fn push_items_with_spacing(
+        &self,
+        content: &mut String,
+        items: &[SourceItem],
+        split: Option<(&Path, &SiblingItems)>,
+    ) {
+        for (index, item) in items.iter().enumerate() {
+            let visibility_changes = split
+                .map(|(target_file, siblings)| self.visibility_changes(item, target_file, siblings))
+                .unwrap_or_default();
+            content.push_str(&self.source_item_to_string(item, &visibility_changes));
+
+            // Consecutive `use` and `extern crate` items are kept together
+            let next = items.get(index + 1);
+            let is_compact = (item.is_use() && next.is_some_and(SourceItem::is_use))
+                || (item.is_extern_crate() && next.is_some_and(SourceItem::is_extern_crate));
+            if !is_compact {
+                content.push('\n');
+            }
+        }
     }
 
     /// Adds an item to the original file group
@@ -52,15 +90,21 @@ impl RustFileContent {
     pub(super) fn group_items_by_target_file(
         &self,
         items: &[SourceItem],
+        config: &Config,
     ) -> IndexMap<String, Vec<SourceItem>> {
         let mut groups: IndexMap<String, Vec<SourceItem>> = IndexMap::new();
-        let use_statements = self.collect_use_statements(items);
 
-        // Group type definitions with their use statements
-        self.group_type_definitions(&mut groups, items, &use_statements);
+        // Group type definitions, use statements are added per file when the
+        // content is built
+        self.group_type_definitions(&mut groups, items, config);
+
+        // Assign impl blocks to their corresponding types, or where the trait
+        // impl policy places them
+        self.assign_impl_blocks_to_types(&mut groups, items, config);
 
-        // Assign impl blocks to their corresponding types
-        self.assign_impl_blocks_to_types(&mut groups, items);
+        // Place constants, statics, foreign blocks, trait aliases and macro
+        // invocations with what they are attached to
+        self.assign_attached_items(&mut groups, items, config);
 
         // Clean up empty original file entries
         self.cleanup_empty_original_file_entry(&mut groups);
@@ -75,54 +119,54 @@ impl RustFileContent {
     ) -> Vec<String> {
         let mut module_names: Vec<String> = grouped_items
             .keys()
+            .filter(|name| !Self::is_nested_group(name))
             .map(|name| name.trim_end_matches(Self::RS_EXTENSION).to_string())
             .collect();
         module_names.sort();
         module_names
     }
 
-    /// Writes the mod.rs file content with module declarations and re-exports
+    /// Plans the mod.rs file content with module declarations and re-exports
     pub(super) fn write_mod_file_content(
         &self,
         mod_file_path: &Path,
         modules: Vec<String>,
-    ) -> Result<()> {
-        let mut content = String::new();
-
-        // Add mod declarations
-        for module in &modules {
-            content.push_str(&format!("mod {};\n", module));
-        }
-
-        content.push('\n');
-
-        // Add pub use re-exports
-        for module in &modules {
-            content.push_str(&format!("pub use {}::*;\n", module));
-        }
-
-        std::fs::write(mod_file_path, content).map_err(|e| {
-            Error::bail(format!(
-                "Failed to write mod.rs file {}: {}",
-                mod_file_path.display(),
-                e
-            ))
-        })
+        plan: &mut SplitPlan,
+    ) {
+        let modules: Vec<ModuleDeclaration> = modules
+            .into_iter()
+            .map(ModuleDeclaration::reexported)
+            .collect();
+        let content = ModuleDeclarationEdit::declarations_content(&modules);
+        plan.add_file(mod_file_path, content);
     }
 
-    /// Convert a SourceItem back to its string representation
-    pub(super) fn source_item_to_string(&self, item: &SourceItem) -> String {
-        let token_stream = self.convert_item_to_token_stream(item);
-        let formatted_code = self.format_token_stream(token_stream);
-
-        // Convert #[doc = "..."] attributes back to /// doc comments
-        let doc_converted = self.convert_doc_attributes_to_comments(formatted_code);
+    /// Convert a SourceItem back to its string representation.
+    ///
+    /// Items are taken verbatim from the original file when possible, so that
+    /// regular comments and formatting survive the split. Items which cannot
+    /// be found in the original file are re-printed, without
+    /// `visibility_changes` since these are located in the original file.
+    pub(super) fn source_item_to_string(
+        &self,
+        item: &SourceItem,
+        visibility_changes: &[(Range<usize>, String)],
+    ) -> String {
+        let code = self
+            .original_source_text(item, visibility_changes)
+            .unwrap_or_else(|| {
+                let token_stream = self.convert_item_to_token_stream(item);
+                let formatted_code = self.format_token_stream(token_stream);
+
+                // Convert #[doc = "..."] attributes back to /// doc comments
+                self.convert_doc_attributes_to_comments(formatted_code)
+            });
 
         // For impl blocks, ensure proper spacing between methods
         if matches!(item, SourceItem::Impl(_)) {
-            self.fix_impl_method_spacing(doc_converted)
+            self.fix_impl_method_spacing(code)
         } else {
-            doc_converted
+            code
         }
     }
 
@@ -166,19 +210,25 @@ impl RustFileContent {
             prev_line_was_method_end = brace_depth == 1 && trimmed == "}";
         }
 
-        output.join("\n")
+        let mut fixed = output.join("\n");
+        fixed.push('\n');
+        fixed
     }
 
-    /// Checks if an item is a type (struct, enum, trait, type alias, union, impl)
+    /// Checks if an item is a type (struct, enum, trait, type alias, union,
+    /// impl or a macro invocation declaring a type)
     pub(super) fn is_type_item(&self, item: &SourceItem) -> bool {
-        matches!(
-            item,
-            SourceItem::Struct(_)
-                | SourceItem::Enum(_)
-                | SourceItem::Trait(_)
-                | SourceItem::Type(_)
-                | SourceItem::Union(_)
-                | SourceItem::Impl(_)
-        )
+        match item {
+            SourceItem::MacroInvocation(invocation) => invocation.declared_type_name().is_some(),
+            _ => matches!(
+                item,
+                SourceItem::Struct(_)
+                    | SourceItem::Enum(_)
+                    | SourceItem::Trait(_)
+                    | SourceItem::Type(_)
+                    | SourceItem::Union(_)
+                    | SourceItem::Impl(_)
+            ),
+        }
     }
 }
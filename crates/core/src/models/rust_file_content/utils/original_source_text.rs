@@ -0,0 +1,391 @@
+use crate::prelude::*;
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use std::collections::HashSet;
+use std::ops::Range;
+
+impl RustFileContent {
+    /// Returns the text of `item` exactly as written in the original file,
+    /// including regular comments inside it, the comments directly above it
+    /// and a trailing comment on its last line.
+    ///
+    /// Returns `None` if the item does not originate from this file or has
+    /// been modified since it was parsed, in which case it must be re-printed.
+    /// `visibility_changes` are applied to the text, see
+    /// [`RustFileContent::visibility_changes`].
+    pub(super) fn original_source_text(
+        &self,
+        item: &SourceItem,
+        visibility_changes: &[(Range<usize>, String)],
+    ) -> Option<String> {
+        let range = self.original_source_range(item)?;
+        let mut text = self.dedent(&self.text_with_visibility_changes(
+            self.content().source(),
+            range,
+            visibility_changes,
+        ));
+        text.push('\n');
+        Some(text)
+    }
+
+    /// Returns the byte range of `item` in the original file, from its
+    /// leading comments to its trailing comment, see
+    /// [`RustFileContent::original_source_text`]
+    pub(crate) fn original_source_range(&self, item: &SourceItem) -> Option<Range<usize>> {
+        let source = self.content().source();
+        let tokens = self.convert_item_to_token_stream(item);
+        let range = self.token_stream_byte_range(&tokens)?;
+        let item_text = source.get(range.clone())?;
+
+        if !self.is_same_tokens(item_text, &tokens) {
+            // Span does not point at this item in this file
+            return None;
+        }
+
+        let start = self.leading_comments_start(source, range.start);
+        let end = self.trailing_comment_end(source, range.end);
+        Some(start..end)
+    }
+
+    /// Computes the byte range in the original file covered by the tokens,
+    /// from the start of the first token to the end of the last one
+    fn token_stream_byte_range(&self, tokens: &TokenStream) -> Option<Range<usize>> {
+        let first = tokens.clone().into_iter().next()?;
+        let last = tokens.clone().into_iter().last()?;
+        let range = first.span().byte_range().start..last.span().byte_range().end;
+
+        if range.is_empty() {
+            // Synthesized tokens without a location in the original file
+            return None;
+        }
+
+        Some(range)
+    }
+
+    /// Checks whether `text` tokenizes to exactly `tokens`, ignoring the
+    /// spacing of punctuation which differs between parsed and printed tokens
+    fn is_same_tokens(&self, text: &str, tokens: &TokenStream) -> bool {
+        text.parse::<TokenStream>()
+            .is_ok_and(|parsed| self.flatten_tokens(parsed) == self.flatten_tokens(tokens.clone()))
+    }
+
+    /// Flattens tokens into their textual representation, with groups
+    /// represented by their delimiters
+    fn flatten_tokens(&self, tokens: TokenStream) -> Vec<String> {
+        tokens
+            .into_iter()
+            .flat_map(|token| match token {
+                TokenTree::Group(group) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => ("", ""),
+                    };
+                    let mut flattened = vec![open.to_owned()];
+                    flattened.extend(self.flatten_tokens(group.stream()));
+                    flattened.push(close.to_owned());
+                    flattened
+                }
+                TokenTree::Punct(punct) => vec![punct.as_char().to_string()],
+                other => vec![other.to_string()],
+            })
+            .collect()
+    }
+
+    /// Finds where the comments directly above the item starting at
+    /// `item_start` begin, skipping blank lines between comments.
+    /// Returns the start of the item's line if there are no such comments.
+    fn leading_comments_start(&self, source: &str, item_start: usize) -> usize {
+        let line_start = source[..item_start].rfind('\n').map_or(0, |i| i + 1);
+        if !source[line_start..item_start].trim().is_empty() {
+            // Item shares its line with preceding code, keep only the item
+            return item_start;
+        }
+
+        let lines = self.lines_with_offsets(&source[..line_start]);
+        let mut comments_start = line_start;
+        let mut index = lines.len();
+
+        while index > 0 {
+            index -= 1;
+            let (offset, line) = lines[index];
+            let trimmed = line.trim();
+
+            if trimmed.is_empty() {
+                // Blank lines between comments are kept, but never lead the snippet
+                continue;
+            }
+
+            if self.is_regular_line_comment(trimmed) {
+                comments_start = offset;
+                continue;
+            }
+
+            let Some(block_start) = self.block_comment_start(&lines, index) else {
+                // Code or doc comment belonging to something else
+                break;
+            };
+
+            comments_start = lines[block_start].0;
+            index = block_start;
+        }
+
+        comments_start
+    }
+
+    /// Checks if a trimmed line is a `//` comment that is neither an outer
+    /// (`///`) nor an inner (`//!`) doc comment
+    fn is_regular_line_comment(&self, trimmed: &str) -> bool {
+        trimmed.starts_with("//") && !trimmed.starts_with("///") && !trimmed.starts_with("//!")
+    }
+
+    /// If the line at `end_index` closes a block comment that spans whole
+    /// lines, returns the index of the line opening it
+    fn block_comment_start(&self, lines: &[(usize, &str)], end_index: usize) -> Option<usize> {
+        if !lines[end_index].1.trim().ends_with("*/") {
+            // Not the end of a block comment
+            return None;
+        }
+
+        let opening_index = (0..=end_index)
+            .rev()
+            .find(|&index| lines[index].1.contains("/*"))?;
+        let opening = lines[opening_index].1.trim();
+
+        if !opening.starts_with("/*") || opening.starts_with("/**") || opening.starts_with("/*!") {
+            // Comment follows code on its line, or is a doc comment
+            return None;
+        }
+
+        Some(opening_index)
+    }
+
+    /// Extends `item_end` to include a `//` comment following the item on its
+    /// last line
+    fn trailing_comment_end(&self, source: &str, item_end: usize) -> usize {
+        let rest_of_line = source[item_end..].split('\n').next().unwrap_or_default();
+
+        if !rest_of_line.trim_start().starts_with("//") {
+            // No trailing comment
+            return item_end;
+        }
+
+        item_end + rest_of_line.trim_end().len()
+    }
+
+    /// Splits text into lines, each paired with the byte offset it starts at
+    fn lines_with_offsets<'a>(&self, text: &'a str) -> Vec<(usize, &'a str)> {
+        let mut offset = 0;
+        text.split_inclusive('\n')
+            .map(|line| {
+                let line_with_offset = (offset, line);
+                offset += line.len();
+                line_with_offset
+            })
+            .collect()
+    }
+
+    /// Removes the indentation common to all non-blank lines, so that items
+    /// taken from nested scopes start at column zero.
+    ///
+    /// Lines starting inside a multi-line string or character literal are
+    /// part of its value and kept as they are.
+    pub(crate) fn dedent(&self, text: &str) -> String {
+        let in_literal = self.literal_continuation_lines(text);
+        let lines: Vec<(usize, &str)> = text.lines().enumerate().collect();
+        let common_indentation = lines
+            .iter()
+            .filter(|(index, line)| !in_literal.contains(index) && !line.trim().is_empty())
+            .map(|(_, line)| &line[..line.len() - line.trim_start().len()])
+            .reduce(|common, indentation| {
+                let shared = common
+                    .chars()
+                    .zip(indentation.chars())
+                    .take_while(|(a, b)| a == b)
+                    .map(|(a, _)| a.len_utf8())
+                    .sum();
+                &common[..shared]
+            })
+            .unwrap_or_default();
+
+        lines
+            .into_iter()
+            .map(|(index, line)| {
+                if in_literal.contains(&index) {
+                    return line;
+                }
+                line.strip_prefix(common_indentation)
+                    .unwrap_or(line.trim_start())
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Indices of the lines of `text` which start inside a string or
+    /// character literal, found from the spans of its tokens. Empty if `text`
+    /// can not be tokenized.
+    fn literal_continuation_lines(&self, text: &str) -> HashSet<usize> {
+        let Ok(tokens) = text.parse::<TokenStream>() else {
+            // Not Rust, no literals to tell apart
+            return HashSet::new();
+        };
+        let mut literals = Vec::new();
+        Self::collect_literal_ranges(tokens, text, &mut literals);
+
+        self.lines_with_offsets(text)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, (offset, _))| {
+                literals
+                    .iter()
+                    .any(|range| range.start < *offset && *offset < range.end)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Collects the byte ranges of the literals in `tokens` of `text`,
+    /// recursively, leaving out doc comments, which are literals as well
+    fn collect_literal_ranges(tokens: TokenStream, text: &str, ranges: &mut Vec<Range<usize>>) {
+        for token in tokens {
+            match token {
+                TokenTree::Literal(literal) => {
+                    let range = literal.span().byte_range();
+                    if text.get(range.clone()).is_some_and(|written| {
+                        !written.starts_with("//") && !written.starts_with("/*")
+                    }) {
+                        ranges.push(range);
+                    }
+                }
+                TokenTree::Group(group) => {
+                    Self::collect_literal_ranges(group.stream(), text, ranges)
+                }
+                TokenTree::Ident(_) | TokenTree::Punct(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rust_file(source: &str) -> RustFileContent {
+        let items = syn::parse_file(source)
+            .unwrap()
+            .items
+            .into_iter()
+            .map(SourceItem::try_from)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        NodeContent::builder()
+            .name("test.rs".to_string())
+            .path(PathBuf::from("test.rs"))
+            .content(
+                NamedSourceItems::builder()
+                    .name("test.rs".to_string())
+                    .items(items)
+                    .source(source.to_string())
+                    .build(),
+            )
+            .build()
+    }
+
+    #[test]
+    fn test_keeps_comments_inside_and_above_item() {
+        let source = r#"use std::fmt;
+
+// Explains Foo
+/* and more */
+/// Doc of Foo
+pub struct Foo {
+    // TODO: more fields
+    field: u8, // trailing
+}
+
+fn bar() {
+    // important
+    let _ = 1;
+} // end of bar
+"#;
+        let file = rust_file(source);
+        let items = file.content().items();
+
+        assert_eq!(
+            file.original_source_text(&items[1], &[]).unwrap(),
+            "// Explains Foo\n/* and more */\n/// Doc of Foo\npub struct Foo {\n    // TODO: more fields\n    field: u8, // trailing\n}\n"
+        );
+        assert_eq!(
+            file.original_source_text(&items[2], &[]).unwrap(),
+            "fn bar() {\n    // important\n    let _ = 1;\n} // end of bar\n"
+        );
+    }
+
+    #[test]
+    fn test_modified_item_is_not_taken_from_source() {
+        let file = rust_file("struct Foo;\n");
+        let SourceItem::Struct(item) = &file.content().items()[0] else {
+            panic!("Expected struct");
+        };
+        let mut modified: syn::ItemStruct = (**item).clone();
+        modified.ident = syn::Ident::new("Bar", modified.ident.span());
+
+        assert_eq!(
+            file.original_source_text(&SourceItem::r#struct(modified), &[]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_dedent_nested_item() {
+        let file =
+            rust_file("mod m {\n    // Comment\n    struct Foo {\n        a: u8,\n    }\n}\n");
+        let SourceItem::Module(module) = &file.content().items()[0] else {
+            panic!("Expected module");
+        };
+        let nested = SourceItem::try_from(module.content.clone().unwrap().1.remove(0)).unwrap();
+
+        assert_eq!(
+            file.original_source_text(&nested, &[]).unwrap(),
+            "// Comment\nstruct Foo {\n    a: u8,\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_dedent_keeps_lines_inside_literals() {
+        let file = rust_file("");
+        assert_eq!(
+            file.dedent(
+                "    /// Docs\n    pub fn banner() -> &'static str {\n        \"first\n        second\"\n    }\n"
+            ),
+            "/// Docs\npub fn banner() -> &'static str {\n    \"first\n        second\"\n}"
+        );
+    }
+
+    #[test]
+    fn test_visibility_changes_keep_comments() {
+        let file = rust_file(
+            "/// Cached values\n#[derive(Default)]\nstruct Cache; // shared\n\nimpl Cache {\n    // Clears it\n    pub(super) fn clear(&self) {}\n}\n",
+        );
+        let items = file.content().items();
+        let mut siblings = SiblingItems::new("src/foo", "src/foo/mod.rs", false);
+        siblings.record_usage(
+            "src/foo/functions.rs",
+            ["Cache".to_owned()].into_iter().collect(),
+        );
+        let target_file = Path::new("src/foo/cache.rs");
+
+        let changes = file.visibility_changes(&items[0], target_file, &siblings);
+        assert_eq!(
+            file.original_source_text(&items[0], &changes).unwrap(),
+            "/// Cached values\n#[derive(Default)]\npub(super) struct Cache; // shared\n"
+        );
+
+        let changes = file.visibility_changes(&items[1], target_file, &siblings);
+        assert_eq!(
+            file.original_source_text(&items[1], &changes).unwrap(),
+            "impl Cache {\n    // Clears it\n    pub(in super::super) fn clear(&self) {}\n}\n"
+        );
+    }
+}
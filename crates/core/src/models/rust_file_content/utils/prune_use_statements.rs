@@ -0,0 +1,458 @@
+use crate::prelude::*;
+use proc_macro2::{TokenStream, TokenTree};
+use std::collections::HashSet;
+use syn::{UseGroup, UsePath, UseTree};
+
+impl RustFileContent {
+    /// Returns `items` preceded by the use statements of the original file
+    /// they need, with every use statement narrowed down to the names used.
+    ///
+    /// Private use statements of the original file are offered to every
+    /// generated file, while re-exports (`pub use`) stay where they were placed.
+    pub(super) fn with_needed_use_statements(&self, items: &[SourceItem]) -> Vec<SourceItem> {
+        let mut with_uses: Vec<SourceItem> = self
+            .collect_use_statements(self.content().items())
+            .into_iter()
+            .filter(|use_item| self.is_private_use(use_item) || self.contains_use(items, use_item))
+            .collect();
+        with_uses.extend(items.iter().filter(|item| !item.is_use()).cloned());
+
+        self.prune_unused_use_statements(with_uses)
+    }
+
+    /// Narrows the use statements among `items` down to the names used by the
+    /// other items, removing use statements which are not needed at all.
+    pub(crate) fn prune_unused_use_statements(
+        &self,
+        mut items: Vec<SourceItem>,
+    ) -> Vec<SourceItem> {
+        let used_identifiers = self.collect_needed_identifiers(&items);
+        items.retain_mut(|item| self.prune_use_item(item, &used_identifiers));
+        items
+    }
+
+    /// Collects the identifiers the imports of `items` are needed for: those
+    /// used by the non-use items, see
+    /// [`RustFileContent::collect_used_identifiers`], and the imports which
+    /// may be traits in scope for their methods if the items call any.
+    ///
+    /// An import of this file or of `items` may be such a trait if its name
+    /// is written like a type but never referenced in this file, as in
+    /// `use std::os::unix::fs::PermissionsExt;` for `permissions.mode()`.
+    pub(crate) fn collect_needed_identifiers(&self, items: &[SourceItem]) -> HashSet<String> {
+        let mut needed = self.collect_used_identifiers(items);
+        if !items
+            .iter()
+            .filter(|item| !item.is_use())
+            .any(|item| Self::calls_methods(self.convert_item_to_token_stream(item)))
+        {
+            // No method could need a trait in scope
+            return needed;
+        }
+
+        let referenced = self.collect_used_identifiers(self.content().items());
+        let mut imported = Vec::new();
+        for item in self.content().items().iter().chain(items) {
+            if let SourceItem::Use(use_item) = item {
+                Self::collect_imported_leaves(&use_item.tree, &mut imported);
+            }
+        }
+        needed.extend(imported.into_iter().filter(|name| {
+            let is_type_like =
+                name.starts_with(char::is_uppercase) && name.chars().any(char::is_lowercase);
+            is_type_like && !referenced.contains(name)
+        }));
+        needed
+    }
+
+    /// Checks if `tokens` call a method or associated function, as in
+    /// `permissions.mode()` or `Permissions::from_mode(0o644)`, or invoke a
+    /// macro, which may do so
+    fn calls_methods(tokens: TokenStream) -> bool {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        tokens.iter().enumerate().any(|(index, token)| match token {
+            TokenTree::Ident(_) => {
+                let is_punct = |offset: usize, char: char| {
+                    matches!(tokens.get(offset), Some(TokenTree::Punct(punct)) if punct.as_char() == char)
+                };
+                let is_called = matches!(
+                    tokens.get(index + 1),
+                    Some(TokenTree::Group(group)) if group.delimiter() == proc_macro2::Delimiter::Parenthesis
+                ) || is_punct(index + 1, ':');
+                let is_member = index > 0 && (is_punct(index - 1, '.') || is_punct(index - 1, ':'));
+                (is_member && is_called) || is_punct(index + 1, '!')
+            }
+            TokenTree::Group(group) => Self::calls_methods(group.stream()),
+            TokenTree::Punct(_) | TokenTree::Literal(_) => false,
+        })
+    }
+
+    /// Collects the names `tree` imports under their own name, renames and
+    /// globs left out
+    fn collect_imported_leaves(tree: &UseTree, names: &mut Vec<String>) {
+        match tree {
+            UseTree::Path(path) => Self::collect_imported_leaves(&path.tree, names),
+            UseTree::Name(name) => names.push(name.ident.to_string()),
+            UseTree::Group(group) => group
+                .items
+                .iter()
+                .for_each(|tree| Self::collect_imported_leaves(tree, names)),
+            UseTree::Rename(_) | UseTree::Glob(_) => {}
+        }
+    }
+
+    /// Checks if an item is a use statement without visibility, i.e. not a re-export
+    pub(crate) fn is_private_use(&self, item: &SourceItem) -> bool {
+        matches!(item, SourceItem::Use(use_item) if matches!(use_item.vis, syn::Visibility::Inherited))
+    }
+
+    /// Checks if `items` contains the use statement `use_item`
+    fn contains_use(&self, items: &[SourceItem], use_item: &SourceItem) -> bool {
+        let SourceItem::Use(use_item) = use_item else {
+            // Not a use statement
+            return false;
+        };
+
+        items
+            .iter()
+            .any(|item| matches!(item, SourceItem::Use(other) if **other == **use_item))
+    }
+
+    /// Narrows a single use item in place, returns `false` if nothing of it is
+    /// used and it should be removed. Non-use items are always kept.
+    pub(crate) fn prune_use_item(
+        &self,
+        item: &mut SourceItem,
+        used_identifiers: &HashSet<String>,
+    ) -> bool {
+        if !self.is_private_use(item) {
+            // Not a use statement, or a re-export which is part of the public API
+            return true;
+        }
+
+        let SourceItem::Use(use_item) = item else {
+            unreachable!("Checked to be a use statement above");
+        };
+
+        let Some(tree) = self.narrow_use_tree(&use_item.tree, None, used_identifiers) else {
+            debug!("Pruning unused use statement");
+            return false;
+        };
+
+        if tree != use_item.tree {
+            let mut narrowed: ItemUse = (**use_item).clone();
+            narrowed.tree = tree;
+            *item = SourceItem::r#use(narrowed);
+        }
+        true
+    }
+
+    /// Collects every identifier referenced by the non-use items.
+    ///
+    /// Identifiers are collected from tokens rather than from the syntax tree
+    /// so that identifiers inside macro invocations are found as well.
+    /// Declarations of inline modules moved out of the file count as
+    /// referencing the identifiers used inside them, see
+    /// [`NamedSourceItems::moved_modules`].
+    pub(crate) fn collect_used_identifiers(&self, items: &[SourceItem]) -> HashSet<String> {
+        self.collect_identifiers(items, false)
+    }
+
+    /// Collects the identifiers the non-use items reference by name, which
+    /// they may need to import, like [`RustFileContent::collect_used_identifiers`].
+    /// Members, as in `circle.radius`, and associated items or variants of a
+    /// type, as in `Role::User`, are skipped.
+    pub(crate) fn collect_imported_identifiers(&self, items: &[SourceItem]) -> HashSet<String> {
+        self.collect_identifiers(items, true)
+    }
+
+    /// Collects the identifiers of the non-use items and of the inline
+    /// modules moved out of the file, only those referenced by name if
+    /// `by_name_only` is set
+    fn collect_identifiers(&self, items: &[SourceItem], by_name_only: bool) -> HashSet<String> {
+        let mut identifiers = HashSet::new();
+        for item in items.iter().filter(|item| !item.is_use()) {
+            self.collect_identifiers_from_tokens(
+                self.convert_item_to_token_stream(item),
+                &mut identifiers,
+                by_name_only,
+            );
+            if let SourceItem::Unsplittable(syn::Item::Mod(module)) = item
+                && let Some(moved) = self
+                    .content()
+                    .moved_modules()
+                    .get(&module.ident.to_string())
+            {
+                identifiers.extend(moved.iter().cloned());
+            }
+        }
+        identifiers
+    }
+
+    /// Recursively collects identifiers from a token stream, only those
+    /// referenced by name if `by_name_only` is set
+    fn collect_identifiers_from_tokens(
+        &self,
+        tokens: TokenStream,
+        identifiers: &mut HashSet<String>,
+        by_name_only: bool,
+    ) {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        for (index, token) in tokens.iter().enumerate() {
+            match token {
+                TokenTree::Ident(_)
+                    if by_name_only && Self::is_member_or_associated(&tokens[..index]) => {}
+                TokenTree::Ident(ident) => {
+                    identifiers.insert(ident.to_string());
+                }
+                TokenTree::Group(group) => {
+                    self.collect_identifiers_from_tokens(group.stream(), identifiers, by_name_only)
+                }
+                TokenTree::Punct(_) | TokenTree::Literal(_) => {}
+            }
+        }
+    }
+
+    /// Checks if the tokens `preceding` an identifier make it a member
+    /// access, `.name`, or a path segment after a type, `Type::name` or
+    /// `<T as Trait>::name`
+    fn is_member_or_associated(preceding: &[TokenTree]) -> bool {
+        let is_punct = |token: Option<&TokenTree>, char: char| matches!(token, Some(TokenTree::Punct(punct)) if punct.as_char() == char);
+
+        match preceding {
+            // A single dot, not the end of a range `..`
+            [.., before, dot] if is_punct(Some(dot), '.') => !is_punct(Some(before), '.'),
+            [dot] => is_punct(Some(dot), '.'),
+            [.., segment, first, second]
+                if is_punct(Some(first), ':') && is_punct(Some(second), ':') =>
+            {
+                match segment {
+                    TokenTree::Ident(ident) => ident.to_string().starts_with(char::is_uppercase),
+                    TokenTree::Punct(punct) => punct.as_char() == '>',
+                    TokenTree::Group(_) | TokenTree::Literal(_) => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Narrows a use tree down to the names in `used_identifiers`.
+    /// `parent` is the last path segment before `tree`, needed for `self`
+    /// imports like `use std::fmt::{self, Display}`.
+    /// Returns `None` if nothing in the tree is used.
+    fn narrow_use_tree(
+        &self,
+        tree: &UseTree,
+        parent: Option<&syn::Ident>,
+        used_identifiers: &HashSet<String>,
+    ) -> Option<UseTree> {
+        match tree {
+            UseTree::Path(path) => {
+                let narrowed =
+                    self.narrow_use_tree(&path.tree, Some(&path.ident), used_identifiers)?;
+                if self.is_self_only_group(&narrowed) {
+                    // `use std::fmt::{self}` is written `use std::fmt`
+                    return Some(UseTree::Name(syn::UseName {
+                        ident: path.ident.clone(),
+                    }));
+                }
+                Some(UseTree::Path(UsePath {
+                    tree: Box::new(narrowed),
+                    ..path.clone()
+                }))
+            }
+            UseTree::Name(name) => {
+                let imported = if name.ident == "self" {
+                    parent?
+                } else {
+                    &name.ident
+                };
+                self.is_import_needed(imported, used_identifiers)
+                    .then(|| tree.clone())
+            }
+            UseTree::Rename(rename) => (rename.rename == "_"
+                || self.is_import_needed(&rename.rename, used_identifiers))
+            .then(|| tree.clone()),
+            // Can't know which names a glob import provides, keep it
+            UseTree::Glob(_) => Some(tree.clone()),
+            UseTree::Group(group) => self.narrow_use_group(group, parent, used_identifiers),
+        }
+    }
+
+    /// Narrows every tree of a use group, unwrapping the group if only a
+    /// single tree remains
+    fn narrow_use_group(
+        &self,
+        group: &UseGroup,
+        parent: Option<&syn::Ident>,
+        used_identifiers: &HashSet<String>,
+    ) -> Option<UseTree> {
+        let mut narrowed: Vec<UseTree> = group
+            .items
+            .iter()
+            .filter_map(|tree| self.narrow_use_tree(tree, parent, used_identifiers))
+            .collect();
+
+        match narrowed.len() {
+            0 => None,
+            1 if !matches!(narrowed[0], UseTree::Name(ref name) if name.ident == "self") => {
+                narrowed.pop()
+            }
+            _ => Some(UseTree::Group(UseGroup {
+                items: narrowed.into_iter().collect(),
+                ..group.clone()
+            })),
+        }
+    }
+
+    /// Checks if a use tree is a group containing nothing but `self`
+    fn is_self_only_group(&self, tree: &UseTree) -> bool {
+        let UseTree::Group(group) = tree else {
+            // Not a group
+            return false;
+        };
+
+        group.items.len() == 1
+            && matches!(group.items.first(), Some(UseTree::Name(name)) if name.ident == "self")
+    }
+
+    /// Checks whether an imported name is among the `used_identifiers`, see
+    /// [`RustFileContent::collect_needed_identifiers`]
+    fn is_import_needed(&self, imported: &syn::Ident, used_identifiers: &HashSet<String>) -> bool {
+        used_identifiers.contains(&imported.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::ToTokens;
+
+    fn parse(source: &str) -> (RustFileContent, Vec<SourceItem>) {
+        let items = syn::parse_file(source)
+            .unwrap()
+            .items
+            .into_iter()
+            .map(SourceItem::try_from)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let file: RustFileContent = NodeContent::builder()
+            .name("test.rs".to_string())
+            .path(PathBuf::from("test.rs"))
+            .content(
+                NamedSourceItems::builder()
+                    .name("test.rs".to_string())
+                    .items(vec![])
+                    .build(),
+            )
+            .build();
+        (file, items)
+    }
+
+    fn prune(source: &str) -> Vec<String> {
+        let (file, items) = parse(source);
+        file.prune_unused_use_statements(items)
+            .into_iter()
+            .filter_map(|item| item.try_unwrap_use().ok())
+            .map(|use_item| use_item.to_token_stream().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_unused_use_statement_is_removed() {
+        assert_eq!(
+            prune(
+                "use std::marker::PhantomData;\nuse std::fmt::Debug;\nstruct Foo(Box<dyn Debug>);"
+            ),
+            vec!["use std :: fmt :: Debug ;"]
+        );
+    }
+
+    #[test]
+    fn test_group_is_narrowed() {
+        assert_eq!(
+            prune(
+                "use std::collections::{HashMap, HashSet, BTreeMap};\nstruct Foo(HashMap<u8, u8>, BTreeMap<u8, u8>);"
+            ),
+            vec!["use std :: collections :: { HashMap , BTreeMap } ;"]
+        );
+        assert_eq!(
+            prune(
+                "use std::{fmt::{self, Display}, io};\nstruct Foo;\nimpl fmt::Debug for Foo { fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { Ok(()) } }"
+            ),
+            vec!["use std :: fmt ;"]
+        );
+    }
+
+    #[test]
+    fn test_identifiers_in_macros_are_used() {
+        assert_eq!(
+            prune("use log::info;\nfn foo() { my_macro!(info); }"),
+            vec!["use log :: info ;"]
+        );
+    }
+
+    #[test]
+    fn test_globs_renames_and_re_exports_are_kept() {
+        assert_eq!(
+            prune(
+                "use crate::prelude::*;\nuse std::io::Write;\nuse std::fmt::Write as _;\npub use std::fmt::Display;\nuse std::fmt::Debug as Dbg;\nfn foo() {}"
+            ),
+            vec![
+                "use crate :: prelude :: * ;",
+                "use std :: fmt :: Write as _ ;",
+                "pub use std :: fmt :: Display ;",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_possible_traits_are_kept_where_methods_are_called() {
+        let source = "use std::fs::Permissions;\nuse std::os::unix::fs::PermissionsExt;\nuse itertools::Itertools;\nuse std::marker::PhantomData;\nuse std::io::Write;\n";
+        assert_eq!(
+            prune(&format!(
+                "{source}fn mode(permissions: &Permissions) -> u32 {{ permissions.mode() }}"
+            )),
+            vec![
+                "use std :: fs :: Permissions ;",
+                "use std :: os :: unix :: fs :: PermissionsExt ;",
+                "use itertools :: Itertools ;",
+                "use std :: marker :: PhantomData ;",
+                "use std :: io :: Write ;",
+            ]
+        );
+        assert_eq!(
+            prune(&format!(
+                "{source}fn new() -> Permissions {{ Permissions::from_mode(0o644) }}"
+            ))
+            .len(),
+            5
+        );
+        assert!(
+            prune(&format!(
+                "{source}fn log(out: &mut Vec<u8>) {{ writeln!(out).ok(); }}"
+            ))
+            .contains(&"use std :: io :: Write ;".to_owned())
+        );
+        assert!(prune(&format!("{source}struct Mode(u32);")).is_empty());
+    }
+
+    #[test]
+    fn test_members_and_associated_items_are_not_imported_by_name() {
+        let (file, items) = parse(
+            "fn foo(role: Role, circle: &Circle) -> f64 { if role == Role::User { circle.radius } else { <Circle as Shape>::area(circle) + (0..LIMIT).len() as f64 } }",
+        );
+        let imported = file.collect_imported_identifiers(&items);
+
+        for name in ["Role", "Circle", "Shape", "LIMIT"] {
+            assert!(imported.contains(name), "{name} is referenced by name");
+        }
+        for name in ["User", "radius", "area", "len"] {
+            assert!(
+                !imported.contains(name),
+                "{name} is a member or associated item"
+            );
+        }
+        assert!(file.collect_used_identifiers(&items).contains("radius"));
+    }
+}
@@ -0,0 +1,186 @@
+use crate::prelude::*;
+use proc_macro2::Span;
+use std::collections::HashSet;
+use syn::UseTree;
+
+impl RustFileContent {
+    /// Records the items of every group as planned to the file named by its
+    /// key in `dir`
+    pub(crate) fn add_sibling_groups(
+        &self,
+        siblings: &mut SiblingItems,
+        dir: &Path,
+        grouped_items: &IndexMap<String, Vec<SourceItem>>,
+    ) {
+        for (file_name, items) in grouped_items {
+            self.add_sibling_items(siblings, &dir.join(file_name), items);
+        }
+    }
+
+    /// Records the items planned to `file` and the identifiers they reference
+    pub(crate) fn add_sibling_items(
+        &self,
+        siblings: &mut SiblingItems,
+        file: &Path,
+        items: &[SourceItem],
+    ) {
+        items
+            .iter()
+            .flat_map(|item| self.defined_item_names(item))
+            .for_each(|name| siblings.insert(name, file));
+        siblings.record_usage(file, self.collect_used_identifiers(items));
+    }
+
+    /// Records the functions planned to the functions file in `dir`. Macros
+    /// need no imports, the modules defining them are declared with
+    /// `#[macro_use]` ahead of the others.
+    pub(crate) fn add_sibling_logic_items(
+        &self,
+        siblings: &mut SiblingItems,
+        dir: &Path,
+        logic_items: &[SourceItem],
+        config: &Config,
+        plan: &SplitPlan,
+    ) {
+        let functions_file =
+            dir.join(plan.renamed_file_name(self.path(), dir, &config.functions_file_name()));
+
+        let functions: Vec<SourceItem> = logic_items
+            .iter()
+            .filter(|item| !item.is_macro_rules())
+            .cloned()
+            .collect();
+        if !functions.is_empty() {
+            self.add_sibling_items(siblings, &functions_file, &functions);
+        }
+    }
+
+    /// Builds explicit imports of the sibling items of other files `items`
+    /// reference, empty if they reference none
+    pub(super) fn sibling_imports(
+        &self,
+        items: &[SourceItem],
+        target_file: &Path,
+        siblings: &SiblingItems,
+    ) -> Vec<ItemUse> {
+        let used_identifiers = self.collect_imported_identifiers(items);
+        let defined_names: HashSet<String> = items
+            .iter()
+            .flat_map(|item| self.defined_item_names(item))
+            .collect();
+
+        siblings.use_statements(target_file, &used_identifiers, &defined_names)
+    }
+
+    /// Inserts `imports` after the use statements among `items`, for crates
+    /// without a prelude re-exporting the sibling items
+    pub(super) fn insert_sibling_imports(
+        &self,
+        items: &mut Vec<SourceItem>,
+        imports: Vec<ItemUse>,
+    ) {
+        let position = items
+            .iter()
+            .position(|item| !item.is_use())
+            .unwrap_or(items.len());
+        items.splice(
+            position..position,
+            imports.into_iter().map(SourceItem::r#use),
+        );
+    }
+
+    /// Rewrites the `self::` and `super::` paths of the use statements among
+    /// `items`, which were written for the original file, so that they keep
+    /// pointing at the same modules from `target_file` when it is nested
+    /// deeper in the module tree. Paths from the crate root start at
+    /// `crate::` instead.
+    pub(super) fn deepen_relative_imports(
+        &self,
+        items: &mut [SourceItem],
+        target_file: &Path,
+        siblings: &SiblingItems,
+    ) {
+        let levels = siblings.levels_deeper(target_file);
+        if levels == 0 {
+            // Same module depth, relative paths still resolve
+            return;
+        }
+
+        let from_crate_root = *siblings.is_crate_root() && siblings.original_module().is_empty();
+        for item in items.iter_mut() {
+            let SourceItem::Use(use_item) = item else {
+                // Not a use statement
+                continue;
+            };
+
+            let UseTree::Path(path) = &use_item.tree else {
+                // Not a relative path
+                continue;
+            };
+
+            let tree = match path.ident.to_string().as_str() {
+                "self" if from_crate_root => {
+                    Self::prefixed_use_tree((*path.tree).clone(), "crate", 1)
+                }
+                "self" => Self::prefixed_use_tree((*path.tree).clone(), "super", levels),
+                "super" => Self::prefixed_use_tree(use_item.tree.clone(), "super", levels),
+                _ => continue,
+            };
+
+            let mut deepened: ItemUse = (**use_item).clone();
+            deepened.tree = tree;
+            *item = SourceItem::r#use(deepened);
+        }
+    }
+
+    /// Prefixes `tree` with `count` times the path segment `segment`
+    pub(crate) fn prefixed_use_tree(tree: UseTree, segment: &str, count: usize) -> UseTree {
+        (0..count).fold(tree, |tree, _| {
+            UseTree::Path(syn::UsePath {
+                ident: syn::Ident::new(segment, Span::call_site()),
+                colon2_token: Default::default(),
+                tree: Box::new(tree),
+            })
+        })
+    }
+
+    /// Returns the name an item defines in its module, if it can be imported
+    pub(crate) fn defined_item_name(&self, item: &SourceItem) -> Option<String> {
+        if let Some(type_name) = self.extract_type_name_from_item(item) {
+            return Some(type_name);
+        }
+
+        match item {
+            SourceItem::Function(function) => Some(function.sig.ident.to_string()),
+            SourceItem::Const(item) => Some(item.ident.to_string()),
+            SourceItem::Static(item) => Some(item.ident.to_string()),
+            SourceItem::TraitAlias(item) => Some(item.ident.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Returns the names an item defines in its module: the name of
+    /// [`RustFileContent::defined_item_name`], the functions, statics and
+    /// types a foreign block declares, or the items declared in the input of
+    /// a macro invocation
+    pub(crate) fn defined_item_names(&self, item: &SourceItem) -> Vec<String> {
+        if let SourceItem::MacroInvocation(invocation) = item {
+            return invocation.declared_item_names();
+        }
+
+        let SourceItem::ForeignMod(foreign_mod) = item else {
+            return self.defined_item_name(item).into_iter().collect();
+        };
+
+        foreign_mod
+            .items
+            .iter()
+            .filter_map(|foreign_item| match foreign_item {
+                syn::ForeignItem::Fn(function) => Some(function.sig.ident.to_string()),
+                syn::ForeignItem::Static(item) => Some(item.ident.to_string()),
+                syn::ForeignItem::Type(item) => Some(item.ident.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+}
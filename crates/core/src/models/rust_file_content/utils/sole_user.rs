@@ -0,0 +1,61 @@
+use crate::prelude::*;
+use std::collections::HashSet;
+
+impl RustFileContent {
+    /// The file the only item of this file using the names `item` defines is
+    /// split into, see [`RustFileContent::user_file_name`].
+    ///
+    /// `None` if the names are used by items going to several files, by none
+    /// or by items staying in the original file.
+    pub(super) fn sole_user_file_name(&self, item: &SourceItem, config: &Config) -> Option<String> {
+        let names: HashSet<String> = self.defined_item_names(item).into_iter().collect();
+        let users: HashSet<Option<String>> = self
+            .content()
+            .items()
+            .iter()
+            .filter(|user| !user.is_use())
+            .filter(|user| {
+                // An item defining one of the names does not use it
+                self.defined_item_names(user)
+                    .iter()
+                    .all(|defined| !names.contains(defined))
+            })
+            .filter(|user| {
+                !self
+                    .collect_used_identifiers(std::slice::from_ref(user))
+                    .is_disjoint(&names)
+            })
+            .map(|user| self.user_file_name(user, config))
+            .collect();
+
+        if users.len() != 1 {
+            // Shared by several items, or unused
+            return None;
+        }
+        users.into_iter().next().flatten()
+    }
+
+    /// The file `user`, an item using a constant or foreign item, is split
+    /// into: the file of its type for types and their impls, the functions
+    /// file for functions. `None` for items staying in the original file,
+    /// like `main`, constants or impls of foreign types.
+    fn user_file_name(&self, user: &SourceItem, config: &Config) -> Option<String> {
+        let type_name = match user {
+            SourceItem::Function(function)
+                if self.content().name() == Self::MAIN_RS
+                    && function.sig.ident == Self::MAIN_FUNCTION =>
+            {
+                // Stays in main.rs
+                return None;
+            }
+            SourceItem::Function(_) => return Some(config.functions_file_name()),
+            SourceItem::Impl(impl_block) => self.resolve_impl_target_type(impl_block)?,
+            _ => self.extract_type_name_from_item(user)?,
+        };
+        Some(format!(
+            "{}{}",
+            self.to_snake_case(&type_name),
+            Self::RS_EXTENSION
+        ))
+    }
+}
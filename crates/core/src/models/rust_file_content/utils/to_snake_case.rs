@@ -1,39 +1,73 @@
 use crate::prelude::*;
+use heck::ToSnakeCase;
+
+/// The strict and reserved keywords of every edition, which can not name a
+/// module: those of 2015, then `async`, `await`, `dyn` and `try` of 2018 and
+/// `gen` of 2024. Weak keywords like `union` or `raw` can.
+const KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "abstract", "become", "box", "do", "final", "macro", "override", "priv", "typeof",
+    "unsized", "virtual", "yield", "async", "await", "dyn", "try", "gen",
+];
 
 impl RustFileContent {
-    /// Convert PascalCase to snake_case
+    /// Convert a PascalCase name, like the name of a type or trait, to the
+    /// snake_case name of its file and module.
+    ///
+    /// Words are split like rustc's `non_snake_case` lint does, so acronyms
+    /// stay together (`HTTPServer` becomes `http_server`) and digits stay
+    /// with the word before them (`Sha256Hasher` becomes `sha256_hasher`).
+    /// The `r#` of raw identifiers is dropped, and names which would be a
+    /// keyword in any edition, and thus not a valid module name, get a
+    /// trailing underscore.
     pub(super) fn to_snake_case(&self, input: &str) -> String {
-        let mut result = String::new();
-        let mut prev_was_lowercase = false;
-
-        for ch in input.chars() {
-            self.process_snake_case_character(ch, &mut result, &mut prev_was_lowercase);
+        let snake_case = input.trim_start_matches("r#").to_snake_case();
+        if KEYWORDS.contains(&snake_case.as_str()) {
+            // `mod type;` does not compile
+            return format!("{}_", snake_case);
         }
-
-        result
+        snake_case
     }
+}
 
-    /// Processes a single character for snake_case conversion
-    fn process_snake_case_character(
-        &self,
-        ch: char,
-        result: &mut String,
-        prev_was_lowercase: &mut bool,
-    ) {
-        if ch.is_uppercase() {
-            self.handle_uppercase_character(ch, result, *prev_was_lowercase);
-            *prev_was_lowercase = false;
-        } else {
-            result.push(ch);
-            *prev_was_lowercase = ch.is_lowercase();
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_snake_case_splits_words_like_rustc() {
+        let file = RustFileContent::parsed(PathBuf::from("lib.rs"), String::new(), IndexMap::new())
+            .unwrap();
+        let cases = [
+            ("Circle", "circle"),
+            ("ShapeKind", "shape_kind"),
+            ("HTTPServer", "http_server"),
+            ("IOError", "io_error"),
+            ("ParseURL", "parse_url"),
+            ("AbAStructA", "ab_a_struct_a"),
+            ("Sha256Hasher", "sha256_hasher"),
+            ("Vec3", "vec3"),
+            ("U8Wrapper", "u8_wrapper"),
+            ("already_snake", "already_snake"),
+            ("r#Type", "type_"),
+            ("r#foo", "foo"),
+            ("Match", "match_"),
+            ("Self_", "self_"),
+            ("Crate", "crate_"),
+            ("Union", "union"),
+            ("Gen", "gen_"),
+            ("Try", "try_"),
+            ("Async", "async_"),
+            ("Dyn", "dyn_"),
+            ("Abstract", "abstract_"),
+            ("Yield", "yield_"),
+            ("Raw", "raw"),
+        ];
 
-    /// Handles uppercase characters in snake_case conversion
-    fn handle_uppercase_character(&self, ch: char, result: &mut String, prev_was_lowercase: bool) {
-        if prev_was_lowercase && !result.is_empty() {
-            result.push('_'); // Add underscore before uppercase letter
+        for (input, expected) in cases {
+            assert_eq!(file.to_snake_case(input), expected, "for {}", input);
         }
-        result.push(ch.to_lowercase().next().unwrap());
     }
 }
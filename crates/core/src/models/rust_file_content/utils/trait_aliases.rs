@@ -0,0 +1,20 @@
+use crate::prelude::*;
+
+impl RustFileContent {
+    /// The file of the trait `alias` is an alias of, its first bound naming a
+    /// trait of this file. `None` if it only names foreign traits, in which
+    /// case it stays in the original file.
+    pub(super) fn trait_alias_file_name(&self, alias: &syn::ItemTraitAlias) -> Option<String> {
+        let local_types = self.local_type_names();
+        let trait_name = alias.bounds.iter().find_map(|bound| match bound {
+            syn::TypeParamBound::Trait(bound) => self.local_path_name(&bound.path, &local_types),
+            _ => None,
+        })?;
+
+        Some(format!(
+            "{}{}",
+            self.to_snake_case(&trait_name),
+            Self::RS_EXTENSION
+        ))
+    }
+}
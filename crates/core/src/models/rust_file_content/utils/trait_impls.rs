@@ -0,0 +1,119 @@
+use crate::prelude::*;
+
+impl RustFileContent {
+    /// Folder, next to the split files, trait impls placed in a dedicated
+    /// file are written to
+    pub const IMPLS_FOLDER: &'static str = "impls";
+
+    /// Assigns a trait impl to the group of the trait or to a dedicated
+    /// file, if the trait impl policy of `config` places it there.
+    ///
+    /// Returns `false` if the impl is placed like an inherent impl: an impl
+    /// placed with its type, with a trait which is not defined in this file,
+    /// or a blanket impl which has no type to name its dedicated file after.
+    pub(super) fn assign_trait_impl_by_policy(
+        &self,
+        groups: &mut IndexMap<String, Vec<SourceItem>>,
+        impl_item: &SourceItem,
+        impl_block: &syn::ItemImpl,
+        config: &Config,
+    ) -> bool {
+        let Some((_, trait_path, _)) = &impl_block.trait_ else {
+            // Inherent impl
+            return false;
+        };
+        let Some(trait_name) = trait_path
+            .segments
+            .last()
+            .map(|last| last.ident.to_string())
+        else {
+            // Empty path
+            return false;
+        };
+
+        match config.trait_impls().placement(&trait_name) {
+            TraitImplPlacement::WithType => false,
+            TraitImplPlacement::WithTrait => {
+                let local_types = self.local_type_names();
+                let Some(trait_name) = self.local_path_name(trait_path, &local_types) else {
+                    // Foreign trait, placed with the type instead
+                    return false;
+                };
+                let file_name =
+                    format!("{}{}", self.to_snake_case(&trait_name), Self::RS_EXTENSION);
+                self.try_add_impl_to_type_file(groups, &file_name, impl_item);
+                true
+            }
+            TraitImplPlacement::Dedicated => {
+                let Some(file_name) = self.dedicated_impl_file_name(impl_block, &trait_name) else {
+                    // Blanket impl
+                    return false;
+                };
+                self.add_item_to_group(groups, file_name, impl_item);
+                true
+            }
+        }
+    }
+
+    /// The group key of the dedicated file of a trait impl,
+    /// `impls/<trait>_for_<type>.rs`, named after the type the impl belongs
+    /// with, see [`RustFileContent::resolve_impl_target_type`], or else the
+    /// outermost type of its self type
+    fn dedicated_impl_file_name(
+        &self,
+        impl_block: &syn::ItemImpl,
+        trait_name: &str,
+    ) -> Option<String> {
+        let local_types = self.local_type_names();
+        let paths = self.self_type_paths(impl_block);
+        let type_path = paths
+            .iter()
+            .find(|path| self.local_path_name(path, &local_types).is_some())
+            .or(paths.first())?;
+        let type_name = type_path.segments.last()?.ident.to_string();
+
+        Some(format!(
+            "{}/{}_for_{}{}",
+            Self::IMPLS_FOLDER,
+            self.to_snake_case(trait_name),
+            self.to_snake_case(&type_name),
+            Self::RS_EXTENSION
+        ))
+    }
+
+    /// Checks if a group key names a file in a folder below the directory of
+    /// the split, like the dedicated files of trait impls, rather than a
+    /// module of its own
+    pub(crate) fn is_nested_group(file_name: &str) -> bool {
+        file_name.contains('/')
+    }
+
+    /// Declares the folders of nested groups, see
+    /// [`RustFileContent::is_nested_group`], in `module_file` declaring the
+    /// modules of `dir`, and their files in the `mod.rs` of each folder.
+    ///
+    /// Trait impls define nothing to import, so nothing is re-exported.
+    pub(crate) fn declare_nested_group_modules(
+        &self,
+        module_file: &Path,
+        dir: &Path,
+        grouped_items: &IndexMap<String, Vec<SourceItem>>,
+        plan: &mut SplitPlan,
+    ) {
+        for file_name in grouped_items.keys() {
+            let Some((folder, file_name)) = file_name.split_once('/') else {
+                // A module of its own
+                continue;
+            };
+
+            plan.edit_module_declarations(module_file)
+                .add(ModuleDeclaration::builder().name(folder).build());
+            plan.edit_module_declarations(dir.join(folder).join(Self::MOD_RS))
+                .add(
+                    ModuleDeclaration::builder()
+                        .name(file_name.trim_end_matches(Self::RS_EXTENSION))
+                        .build(),
+                );
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::prelude::*;
+
+/// A file or directory which could not be scanned and was skipped, see
+/// `ScanReport`
+#[derive(Clone, Debug, PartialEq, Eq, Getters, Builder)]
+pub struct ScanFailure {
+    /// The file or directory which was skipped
+    #[builder(into)]
+    #[getset(get = "pub")]
+    path: PathBuf,
+    /// The line the failure points at, starting at 1, if it has a location
+    #[getset(get = "pub")]
+    line: Option<usize>,
+    /// The column the failure points at, starting at 1, if it has a location
+    #[getset(get = "pub")]
+    column: Option<usize>,
+    /// What went wrong, without location
+    #[builder(into)]
+    #[getset(get = "pub")]
+    message: String,
+}
+
+impl ScanFailure {
+    /// The failure to scan `path` because of `error`, pointing at the
+    /// location of the error if it has one
+    pub fn from_error(path: impl AsRef<Path>, error: &Error) -> Self {
+        let builder = Self::builder().path(path.as_ref());
+        match error {
+            Error::Parse {
+                line,
+                column,
+                message,
+                ..
+            } => builder.line(*line).column(*column).message(message).build(),
+            Error::UnsupportedItem {
+                line, column, kind, ..
+            } => builder
+                .line(*line)
+                .column(*column)
+                .message(format!("unsupported item `{}`", kind))
+                .build(),
+            other => builder.message(Self::message_chain(other)).build(),
+        }
+    }
+
+    /// The message of `error` followed by the messages of its sources
+    fn message_chain(error: &Error) -> String {
+        let mut message = error.to_string();
+        let mut source = std::error::Error::source(error);
+        while let Some(cause) = source {
+            message.push_str(&format!(": {}", cause));
+            source = cause.source();
+        }
+        message
+    }
+}
+
+impl std::fmt::Display for ScanFailure {
+    /// Formats the failure like rustc does, `path:line:column: message`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, ":{}:{}", line, column)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failures_point_at_the_location_of_their_error() {
+        let syntax_error = syn::parse_file("struct Foo;\nfn bar( {}\n").unwrap_err();
+        let failure =
+            ScanFailure::from_error("src/foo.rs", &Error::parse("src/foo.rs", &syntax_error));
+
+        assert_eq!(failure.line(), &Some(2));
+        assert!(
+            failure.to_string().starts_with("src/foo.rs:2:"),
+            "{}",
+            failure
+        );
+
+        let io_error = Error::io(
+            IoAction::Read,
+            "src/bar.rs",
+            std::io::Error::other("permission denied"),
+        );
+        assert_eq!(
+            ScanFailure::from_error("src/bar.rs", &io_error).to_string(),
+            "src/bar.rs: Failed to read src/bar.rs: permission denied"
+        );
+    }
+}
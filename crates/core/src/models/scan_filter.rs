@@ -0,0 +1,78 @@
+use crate::prelude::*;
+use ignore::{WalkBuilder, overrides::OverrideBuilder};
+use std::collections::HashSet;
+
+/// The paths below a scanned directory which are split.
+///
+/// Hidden files and directories, cargo `target/` directories, paths ignored
+/// by `.gitignore` and `.ignore` files and paths matching one of the exclude
+/// globs are skipped, so that build artifacts and generated or vendored code
+/// are never rewritten.
+#[derive(Clone, Debug, Default, Getters)]
+pub struct ScanFilter {
+    /// Every file and directory below the root which is not skipped
+    #[getset(get = "pub")]
+    included: HashSet<PathBuf>,
+}
+
+impl ScanFilter {
+    /// Name of the directory cargo writes build artifacts to
+    pub const TARGET_DIR: &'static str = "target";
+    /// File cargo marks its target directory with
+    pub const CACHEDIR_TAG: &'static str = "CACHEDIR.TAG";
+
+    /// Walks `root` once to collect the paths which are not skipped, with
+    /// `exclude` holding globs relative to `root`, like `generated/**`
+    pub fn new(root: impl AsRef<Path>, exclude: &[String]) -> Result<Self> {
+        let root = root.as_ref();
+        let mut overrides = OverrideBuilder::new(root);
+        for glob in exclude {
+            overrides
+                .add(&format!("!{}", glob))
+                .map_err(|source| Error::ExcludeGlob {
+                    glob: glob.clone(),
+                    source,
+                })?;
+        }
+        let overrides = overrides.build().map_err(|source| Error::ExcludeGlob {
+            glob: exclude.join(", "),
+            source,
+        })?;
+
+        let walk = WalkBuilder::new(root)
+            .require_git(false)
+            .overrides(overrides)
+            .filter_entry(|entry| !Self::is_target_dir(entry.path()))
+            .build();
+
+        let mut included = HashSet::new();
+        for entry in walk {
+            match entry {
+                Ok(entry) => {
+                    included.insert(entry.into_path());
+                }
+                Err(e) => warn!("Skipping a path which can not be scanned: {}", e),
+            }
+        }
+        Ok(Self { included })
+    }
+
+    /// Checks if `path` is split
+    pub fn includes(&self, path: &Path) -> bool {
+        self.included.contains(path)
+    }
+
+    /// Checks if `path` is a cargo target directory: a `target` directory
+    /// next to a `Cargo.toml` or marked with a `CACHEDIR.TAG`
+    fn is_target_dir(path: &Path) -> bool {
+        if path.file_name().is_none_or(|name| name != Self::TARGET_DIR) || !path.is_dir() {
+            // Not named like one
+            return false;
+        }
+
+        path.join(Self::CACHEDIR_TAG).exists()
+            || path
+                .parent()
+                .is_some_and(|parent| parent.join("Cargo.toml").exists())
+    }
+}
@@ -0,0 +1,41 @@
+use crate::prelude::*;
+
+/// The files and directories skipped while scanning the source, because they
+/// could not be read or parsed
+#[derive(Clone, Debug, Default, PartialEq, Eq, Getters)]
+pub struct ScanReport {
+    /// Every failure, in the order the paths were scanned
+    #[getset(get = "pub")]
+    failures: Vec<ScanFailure>,
+}
+
+impl ScanReport {
+    /// Records that a path was skipped
+    pub fn push(&mut self, failure: ScanFailure) {
+        self.failures.push(failure);
+    }
+
+    /// Checks if every scanned file was parsed
+    pub fn is_clean(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl std::fmt::Display for ScanReport {
+    /// Lists the skipped paths, nothing if none was
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_clean() {
+            return Ok(());
+        }
+
+        writeln!(
+            f,
+            "{} path(s) could not be parsed and were skipped:",
+            self.failures.len()
+        )?;
+        for failure in &self.failures {
+            writeln!(f, "  {}", failure)?;
+        }
+        Ok(())
+    }
+}
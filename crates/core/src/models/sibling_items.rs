@@ -0,0 +1,324 @@
+use crate::prelude::*;
+use std::collections::HashSet;
+
+/// The items split out of a single source file, the files they are planned
+/// to and the names each of these files references.
+///
+/// Used to import sibling items explicitly when the crate has no prelude, and
+/// to widen the visibility of items which other files of the split need.
+#[derive(Clone, Debug, Default, Getters)]
+pub struct SiblingItems {
+    /// Directory of the module the split files belong to, paths of the
+    /// imports are relative to it
+    #[getset(get = "pub")]
+    root_dir: PathBuf,
+    /// Module path of the source file relative to `root_dir`, empty if it is
+    /// the module root
+    #[getset(get = "pub")]
+    original_module: Vec<String>,
+    /// Whether `root_dir` holds the crate root, in which case imports
+    /// reaching back to it start at `crate::` instead of `super::super::`
+    #[getset(get = "pub")]
+    is_crate_root: bool,
+    /// Item names and the file each of them is planned to
+    #[getset(get = "pub")]
+    files: IndexMap<String, PathBuf>,
+    /// Identifiers referenced by each planned file
+    #[getset(get = "pub")]
+    usages: IndexMap<PathBuf, HashSet<String>>,
+}
+
+impl SiblingItems {
+    /// Creates an empty set of items for splitting `original_file` into
+    /// `root_dir`
+    pub fn new(
+        root_dir: impl AsRef<Path>,
+        original_file: impl AsRef<Path>,
+        is_crate_root: bool,
+    ) -> Self {
+        let mut siblings = Self {
+            root_dir: root_dir.as_ref().to_path_buf(),
+            is_crate_root,
+            ..Self::default()
+        };
+        siblings.original_module = siblings
+            .module_path(original_file.as_ref())
+            .unwrap_or_default();
+        siblings
+    }
+
+    /// Records that the item `name` is planned to `file`
+    pub fn insert(&mut self, name: impl Into<String>, file: impl AsRef<Path>) {
+        self.files.insert(name.into(), file.as_ref().to_path_buf());
+    }
+
+    /// Records that `file` references `identifiers`
+    pub fn record_usage(&mut self, file: impl AsRef<Path>, identifiers: HashSet<String>) {
+        self.usages
+            .entry(file.as_ref().to_path_buf())
+            .or_default()
+            .extend(identifiers);
+    }
+
+    /// Returns the visibility an item or method named `name` with the
+    /// visibility `visibility` must get when planned to `file`, so that it
+    /// stays visible where it was visible in the original file. `None` if it
+    /// can keep its visibility.
+    ///
+    /// Private items are widened to the parent module (the crate when split
+    /// out of a crate root), but only if another file of the split references
+    /// them. `pub(super)` items moved deeper into the module tree are
+    /// restricted to the module they were visible in before.
+    pub fn changed_visibility(
+        &self,
+        name: &str,
+        file: &Path,
+        visibility: &syn::Visibility,
+    ) -> Option<String> {
+        let module = self.module_path(file)?;
+
+        match visibility {
+            syn::Visibility::Inherited => {
+                if module.is_empty() && self.original_module.is_empty() {
+                    // Stays in the module root, visible to all its children
+                    return None;
+                }
+
+                if !self.is_used_outside(name, file) {
+                    // Only needed where it is
+                    return None;
+                }
+
+                Some(if self.is_crate_root && self.original_module.is_empty() {
+                    "pub(crate)".to_owned()
+                } else {
+                    "pub(super)".to_owned()
+                })
+            }
+            syn::Visibility::Restricted(restricted)
+                if restricted.in_token.is_none() && restricted.path.is_ident("super") =>
+            {
+                let levels_deeper = module.len().checked_sub(self.original_module.len())?;
+                if levels_deeper == 0 || self.is_crate_root {
+                    // Same parent as before, or invalid in a crate root anyway
+                    return None;
+                }
+
+                Some(format!(
+                    "pub(in {})",
+                    vec!["super"; levels_deeper + 1].join("::")
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns how many levels deeper in the module tree `file` is than the
+    /// original file, `0` if it is not deeper or outside of the split
+    pub fn levels_deeper(&self, file: &Path) -> usize {
+        self.module_path(file).map_or(0, |module| {
+            module.len().saturating_sub(self.original_module.len())
+        })
+    }
+
+    /// Checks if any planned file other than `file` references `name`
+    fn is_used_outside(&self, name: &str, file: &Path) -> bool {
+        self.usages
+            .iter()
+            .any(|(other, identifiers)| other != file && identifiers.contains(name))
+    }
+
+    /// Checks if a planned file other than `file` reaches any of `names`
+    /// through the glob re-exports of the module root: the module root file
+    /// itself, which imports nothing explicitly, or with a prelude any file
+    pub fn is_reached_through_root(
+        &self,
+        names: &[String],
+        file: &Path,
+        through_prelude: bool,
+    ) -> bool {
+        self.usages.iter().any(|(other, identifiers)| {
+            other != file
+                && names.iter().any(|name| identifiers.contains(name))
+                && (through_prelude
+                    || self
+                        .module_path(other)
+                        .is_some_and(|module| module.is_empty()))
+        })
+    }
+
+    /// Builds the use statements `file` needs for the sibling items among
+    /// `used_identifiers`, skipping the items it defines itself.
+    ///
+    /// Module roots need no imports, they glob re-export their children.
+    pub fn use_statements(
+        &self,
+        file: &Path,
+        used_identifiers: &HashSet<String>,
+        defined_names: &HashSet<String>,
+    ) -> Vec<ItemUse> {
+        let Some(importer) = self.module_path(file).filter(|path| !path.is_empty()) else {
+            // Module root or outside of the split
+            return Vec::new();
+        };
+
+        self.files
+            .iter()
+            .filter_map(|(name, item_file)| {
+                let module = self.module_path(item_file)?;
+                (module != importer).then(|| self.relative_path(&importer, &module, name))
+            })
+            .filter(|(name, _)| used_identifiers.contains(*name) && !defined_names.contains(*name))
+            .filter_map(|(_, path)| syn::parse_str(&format!("use {};", path)).ok())
+            .collect()
+    }
+
+    /// Builds the path from the module `importer` to the item `name` in
+    /// `module`, both given relative to `root_dir`
+    fn relative_path<'a>(
+        &self,
+        importer: &[String],
+        module: &[String],
+        name: &'a String,
+    ) -> (&'a String, String) {
+        let common = importer
+            .iter()
+            .zip(module)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let levels_up = importer.len() - common;
+
+        let mut segments: Vec<&str> = if common == 0 && levels_up > 1 && self.is_crate_root {
+            vec!["crate"]
+        } else if levels_up == 0 {
+            vec!["self"]
+        } else {
+            vec!["super"; levels_up]
+        };
+        segments.extend(module[common..].iter().map(String::as_str));
+        segments.push(name);
+
+        (name, segments.join("::"))
+    }
+
+    /// Returns the module path of `file` relative to `root_dir`, empty for
+    /// the module root itself, or `None` if the file is outside of it
+    fn module_path(&self, file: &Path) -> Option<Vec<String>> {
+        let relative = file.strip_prefix(&self.root_dir).ok()?;
+        let mut path: Vec<String> = relative
+            .iter()
+            .map(|segment| segment.to_string_lossy().into_owned())
+            .collect();
+
+        let file_name = path.pop()?;
+        let is_module_root = [
+            RustFileContent::MOD_RS,
+            RustFileContent::LIB_RS,
+            RustFileContent::MAIN_RS,
+        ]
+        .contains(&file_name.as_str());
+
+        if is_module_root && (path.is_empty() || file_name == RustFileContent::MOD_RS) {
+            // Root of the directory module
+            return Some(path);
+        }
+
+        path.push(
+            file_name
+                .trim_end_matches(RustFileContent::RS_EXTENSION)
+                .to_string(),
+        );
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::ToTokens;
+
+    fn imports(siblings: &SiblingItems, file: &str, used: &[&str]) -> Vec<String> {
+        let used = used.iter().map(|name| name.to_string()).collect();
+        siblings
+            .use_statements(&siblings.root_dir().join(file), &used, &HashSet::new())
+            .into_iter()
+            .map(|use_item| use_item.to_token_stream().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_siblings_are_imported_through_super() {
+        let mut siblings = SiblingItems::new("src/foo", "src/foo/mod.rs", false);
+        siblings.insert("Alpha", "src/foo/alpha.rs");
+        siblings.insert("helper", "src/foo/functions.rs");
+        siblings.insert("LIMIT", "src/foo/mod.rs");
+
+        assert_eq!(
+            imports(
+                &siblings,
+                "functions.rs",
+                &["Alpha", "helper", "LIMIT", "x"]
+            ),
+            vec!["use super :: alpha :: Alpha ;", "use super :: LIMIT ;"]
+        );
+        assert_eq!(
+            imports(&siblings, "alpha.rs", &["helper"]),
+            vec!["use super :: functions :: helper ;"]
+        );
+        assert!(imports(&siblings, "mod.rs", &["Alpha"]).is_empty());
+    }
+
+    #[test]
+    fn test_folders_of_crate_root_are_imported_through_crate() {
+        let mut siblings = SiblingItems::new("src", "src/lib.rs", true);
+        siblings.insert("User", "src/types/user.rs");
+        siblings.insert("Role", "src/types/role.rs");
+
+        assert_eq!(
+            imports(&siblings, "types/user.rs", &["Role"]),
+            vec!["use super :: role :: Role ;"]
+        );
+        assert_eq!(
+            imports(&siblings, "logic/functions.rs", &["User"]),
+            vec!["use crate :: types :: user :: User ;"]
+        );
+    }
+
+    #[test]
+    fn test_private_items_used_elsewhere_are_widened() {
+        let mut siblings = SiblingItems::new("src", "src/shapes.rs", false);
+        siblings.insert("Cache", "src/cache.rs");
+        siblings.record_usage("src/cache.rs", HashSet::from(["Cache".to_owned()]));
+        siblings.record_usage("src/functions.rs", HashSet::from(["Cache".to_owned()]));
+        let private = syn::Visibility::Inherited;
+        let public: syn::Visibility = syn::parse_quote!(pub);
+
+        assert_eq!(
+            siblings.changed_visibility("Cache", Path::new("src/cache.rs"), &private),
+            Some("pub(super)".to_owned())
+        );
+        assert_eq!(
+            siblings.changed_visibility("helper", Path::new("src/functions.rs"), &private),
+            None
+        );
+        assert_eq!(
+            siblings.changed_visibility("Cache", Path::new("src/cache.rs"), &public),
+            None
+        );
+    }
+
+    #[test]
+    fn test_pub_super_keeps_its_scope_when_moved_deeper() {
+        let siblings = SiblingItems::new("src/foo", "src/foo/mod.rs", false);
+        let visibility: syn::Visibility = syn::parse_quote!(pub(super));
+
+        assert_eq!(
+            siblings.changed_visibility("Foo", Path::new("src/foo/foo.rs"), &visibility),
+            Some("pub(in super::super)".to_owned())
+        );
+        assert_eq!(
+            siblings.changed_visibility("Foo", Path::new("src/foo/mod.rs"), &visibility),
+            None
+        );
+    }
+}
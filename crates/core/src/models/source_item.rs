@@ -2,9 +2,11 @@ use crate::prelude::*;
 use derive_more::{IsVariant, TryUnwrap};
 use proc_macro2::TokenStream;
 use syn::Item;
+use syn::spanned::Spanned;
 
-/// A Rust type, struct, enum, typealias, function, macro or implementation of
-/// struct or enum.
+/// A Rust type, struct, enum, typealias, trait alias, function, constant,
+/// static, foreign block, extern crate, macro definition, macro invocation or
+/// implementation of struct or enum.
 #[derive(Clone, Debug, TryUnwrap, IsVariant)]
 pub enum SourceItem {
     Enum(Enum),
@@ -13,9 +15,23 @@ pub enum SourceItem {
     Type(Type),
     Union(Union),
     Function(Function),
+    Const(Const),
+    Static(Static),
+    /// An `extern "C" { ... }` block
+    ForeignMod(ForeignMod),
+    /// An `extern crate`, kept where it is, which is the crate root
+    ExternCrate(ExternCrate),
+    /// A `trait Alias = Bound;`
+    TraitAlias(TraitAlias),
+    /// A `macro_rules!` definition
     MacroRules(MacroRules),
+    /// Any other macro invoked at item position, e.g. `bitflags! { ... }`
+    MacroInvocation(MacroInvocation),
     Impl(Implementation),
     Use(Use),
+    /// An inline module, `mod foo { ... }`, split into a directory of its own.
+    /// Module declarations without a body are `Unsplittable`.
+    Module(Module),
     /// Item for which klyv is unable to determine corresponding type
     Unsplittable(syn::Item),
     /// Item for which klyv - and even `syn` crate is unable to determine corresponding type
@@ -26,33 +42,46 @@ impl TryFrom<syn::Item> for SourceItem {
     type Error = Error;
     fn try_from(value: syn::Item) -> Result<Self> {
         match value {
-            syn::Item::Const(item) => Ok(SourceItem::unsplittable(item)),
+            syn::Item::Const(item) => Ok(SourceItem::r#const(item)),
             syn::Item::Enum(item) => Ok(SourceItem::r#enum(item)),
-            syn::Item::ExternCrate(item) => Ok(SourceItem::unsplittable(item)),
+            syn::Item::ExternCrate(item) => Ok(SourceItem::extern_crate(item)),
             syn::Item::Fn(item) => Ok(SourceItem::function(item)),
-            syn::Item::ForeignMod(item) => Ok(SourceItem::unsplittable(item)),
+            syn::Item::ForeignMod(item) => Ok(SourceItem::foreign_mod(item)),
             syn::Item::Impl(item) => Ok(SourceItem::r#impl(item)),
-            syn::Item::Macro(item) => Ok(SourceItem::r#macro(item)),
+            syn::Item::Macro(item) if item.mac.path.is_ident("macro_rules") => {
+                Ok(SourceItem::r#macro(item))
+            }
+            syn::Item::Macro(item) => Ok(SourceItem::macro_invocation(item)),
+            syn::Item::Mod(item) if item.content.is_some() => Ok(SourceItem::module(item)),
             syn::Item::Mod(item) => Ok(SourceItem::unsplittable(item)),
-            syn::Item::Static(item) => Ok(SourceItem::unsplittable(item)),
+            syn::Item::Static(item) => Ok(SourceItem::r#static(item)),
             syn::Item::Struct(item) => Ok(SourceItem::r#struct(item)),
             syn::Item::Trait(item) => Ok(SourceItem::r#trait(item)),
-            syn::Item::TraitAlias(item) => Ok(SourceItem::unsplittable(item)),
+            syn::Item::TraitAlias(item) => Ok(SourceItem::trait_alias(item)),
             syn::Item::Type(item) => Ok(SourceItem::r#type(item)),
             syn::Item::Union(item) => Ok(SourceItem::r#union(item)),
             syn::Item::Use(item) => Ok(SourceItem::r#use(item)),
             syn::Item::Verbatim(tokens) => Ok(SourceItem::Verbatim(tokens)),
-            _ => Self::handle_unsupported_item(),
+            item => Self::handle_unsupported_item(&item),
         }
     }
 }
 
 impl SourceItem {
-    /// Handles unsupported item types
-    fn handle_unsupported_item() -> Result<Self> {
-        Err(Error::bail(
-            "new unsupported item type, please file an issue at https://github.com/Sajjon/klyv/issues/new",
-        ))
+    /// Handles unsupported item types, pointing at where the item starts
+    fn handle_unsupported_item(item: &syn::Item) -> Result<Self> {
+        let start = item.span().start();
+        let debug = format!("{:?}", item);
+        let kind = debug
+            .split(|c: char| c == '(' || c.is_whitespace())
+            .next()
+            .unwrap_or_default();
+        Err(Error::UnsupportedItem {
+            path: None,
+            line: start.line,
+            column: start.column + 1,
+            kind: kind.to_owned(),
+        })
     }
     pub fn r#enum(item: impl Into<Enum>) -> Self {
         Self::Enum(item.into())
@@ -72,15 +101,36 @@ impl SourceItem {
     pub fn function(item: impl Into<Function>) -> Self {
         Self::Function(item.into())
     }
+    pub fn r#const(item: impl Into<Const>) -> Self {
+        Self::Const(item.into())
+    }
+    pub fn r#static(item: impl Into<Static>) -> Self {
+        Self::Static(item.into())
+    }
+    pub fn foreign_mod(item: impl Into<ForeignMod>) -> Self {
+        Self::ForeignMod(item.into())
+    }
+    pub fn extern_crate(item: impl Into<ExternCrate>) -> Self {
+        Self::ExternCrate(item.into())
+    }
+    pub fn trait_alias(item: impl Into<TraitAlias>) -> Self {
+        Self::TraitAlias(item.into())
+    }
     pub fn r#macro(item: impl Into<MacroRules>) -> Self {
         Self::MacroRules(item.into())
     }
+    pub fn macro_invocation(item: impl Into<MacroInvocation>) -> Self {
+        Self::MacroInvocation(item.into())
+    }
     pub fn r#impl(item: impl Into<Implementation>) -> Self {
         Self::Impl(item.into())
     }
     pub fn r#use(item: impl Into<Use>) -> Self {
         Self::Use(item.into())
     }
+    pub fn module(item: impl Into<Module>) -> Self {
+        Self::Module(item.into())
+    }
     pub fn unsplittable(item: impl Into<Item>) -> Self {
         Self::Unsplittable(item.into())
     }
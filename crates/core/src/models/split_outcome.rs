@@ -0,0 +1,24 @@
+use crate::prelude::*;
+
+/// The result of a split: the parsed source tree and the files planned for it
+#[derive(Clone, Debug, Getters, Builder)]
+pub struct SplitOutcome {
+    /// The source tree that was parsed
+    #[getset(get = "pub")]
+    node: FileSystemNode,
+    /// Where the split files are written to
+    #[builder(into)]
+    #[getset(get = "pub")]
+    out: PathBuf,
+    /// The paths skipped while scanning the source tree
+    #[builder(default)]
+    #[getset(get = "pub")]
+    scan_report: ScanReport,
+    /// The files planned for the split, executed unless running a dry run
+    #[getset(get = "pub")]
+    plan: SplitPlan,
+    /// Whether the package still compiles after the split, only when
+    /// verifying, see `Input::verify`
+    #[getset(get = "pub")]
+    verification: Option<Verification>,
+}
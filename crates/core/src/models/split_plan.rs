@@ -0,0 +1,517 @@
+use crate::prelude::*;
+use std::collections::HashSet;
+
+/// The complete set of files a split will write, built in memory before
+/// anything touches the disk.
+///
+/// Writers record files into the plan instead of calling `std::fs::write`
+/// directly, which allows a split to be reviewed (`--dry-run`) before it is
+/// executed.
+#[derive(Clone, Debug, Default, Getters)]
+pub struct SplitPlan {
+    /// Planned files keyed by their path, in the order they were first planned
+    #[getset(get = "pub")]
+    files: IndexMap<PathBuf, PlannedFile>,
+    /// Pending edits of module declarations keyed by the module file they
+    /// apply to, see [`SplitPlan::apply_module_edits`]
+    #[getset(get = "pub")]
+    module_edits: IndexMap<PathBuf, ModuleDeclarationEdit>,
+    /// Output files renamed to avoid name collisions, in the order they were
+    /// decided
+    #[getset(get = "pub")]
+    renames: Vec<FileRename>,
+    /// Problems the split may cause which do not stop it, like code which
+    /// may no longer compile, in the order they were found
+    #[getset(get = "pub")]
+    warnings: Vec<String>,
+    /// Identifiers each source file references by name, see
+    /// [`SplitPlan::record_references`]
+    #[getset(get = "pub")]
+    references: IndexMap<PathBuf, HashSet<String>>,
+    /// Whether some files of the crate could not be read for their
+    /// references, so that any split item may be referenced elsewhere
+    #[getset(get = "pub")]
+    has_unknown_references: bool,
+    /// Module files of inline modules moved out of their file which are not
+    /// reachable from outside the crate, see
+    /// [`SplitPlan::record_inline_module`]
+    #[getset(get = "pub")]
+    crate_private_modules: HashSet<PathBuf>,
+}
+
+impl SplitPlan {
+    /// Plans writing `content` to `path`.
+    ///
+    /// If the same path is planned more than once the last content wins, but
+    /// the action is determined by the state of the disk when the path was
+    /// first planned.
+    pub fn add_file(&mut self, path: impl AsRef<Path>, content: impl Into<String>) {
+        let path = path.as_ref().to_path_buf();
+        let content = content.into();
+
+        if let Some(existing) = self.files.get_mut(&path) {
+            debug!("Replacing planned content of {}", path.display());
+            existing.set_content(content);
+            if existing.action() == &FileAction::Delete {
+                existing.set_action(FileAction::Overwrite);
+            }
+            return;
+        }
+
+        let planned_file = PlannedFile::builder()
+            .action(FileAction::for_path(&path))
+            .path(path.clone())
+            .content(content)
+            .build();
+        self.files.insert(path, planned_file);
+    }
+
+    /// Plans deleting `path`, replacing anything planned for it.
+    ///
+    /// Paths which do not exist on disk are simply no longer planned.
+    pub fn remove_file(&mut self, path: impl AsRef<Path>) {
+        let path = path.as_ref().to_path_buf();
+
+        if !path.exists() {
+            // Nothing on disk to delete
+            self.files.shift_remove(&path);
+            return;
+        }
+
+        let planned_file = PlannedFile::builder()
+            .action(FileAction::Delete)
+            .path(path.clone())
+            .content(String::new())
+            .build();
+        self.files.insert(path, planned_file);
+    }
+
+    /// Returns the pending edit of the module declarations in `module_file`,
+    /// creating an empty one if there is none yet
+    pub fn edit_module_declarations(
+        &mut self,
+        module_file: impl AsRef<Path>,
+    ) -> &mut ModuleDeclarationEdit {
+        self.module_edits
+            .entry(module_file.as_ref().to_path_buf())
+            .or_default()
+    }
+
+    /// Applies all pending module declaration edits to the content their
+    /// module files will have, planning the edited files.
+    ///
+    /// Must be called once every file has been planned, so that edits apply
+    /// on top of the final content regardless of planning order.
+    pub fn apply_module_edits(&mut self) -> Result<()> {
+        let module_edits = std::mem::take(&mut self.module_edits);
+
+        for (module_file, edit) in module_edits {
+            let content = self.current_content(&module_file)?.unwrap_or_default();
+            let edited = edit.apply_to(&content);
+
+            if edited == content && !self.files.contains_key(&module_file) {
+                // Module file is already up to date on disk
+                continue;
+            }
+
+            debug!("Editing module declarations of {}", module_file.display());
+            self.add_file(module_file, edited);
+        }
+
+        Ok(())
+    }
+
+    /// Records the identifiers every Rust file of `node` and of the rest of
+    /// its crate references by name, to tell which items split out of a file
+    /// are still reached through the module it was declared in.
+    ///
+    /// Files of the crate outside of `node`, like those excluded from the
+    /// split, are read from the directory of the crate root, see
+    /// [`find_crate_root`]. If there is no crate root or a file can not be
+    /// read, any item is taken to be referenced elsewhere.
+    pub fn record_references(&mut self, node: &FileSystemNode) {
+        for file in node.rust_file_contents() {
+            let identifiers = file.collect_imported_identifiers(file.content().items());
+            self.references.insert(file.path().clone(), identifiers);
+        }
+
+        let Some(crate_dir) = find_crate_root(node.path())
+            .as_deref()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+        else {
+            warn!(
+                "No crate root found for {}, split items stay re-exported",
+                node.path().display()
+            );
+            self.has_unknown_references = true;
+            return;
+        };
+
+        let walk = ignore::WalkBuilder::new(&crate_dir)
+            .standard_filters(false)
+            .filter_entry(|entry| entry.file_name() != "target")
+            .build();
+        for entry in walk {
+            let Ok(entry) = entry else {
+                // Unreadable directory entry
+                self.has_unknown_references = true;
+                continue;
+            };
+            let path = entry.path();
+            if path.extension().is_none_or(|extension| extension != "rs")
+                || self.references.contains_key(path)
+            {
+                // Not a Rust file, or part of the split
+                continue;
+            }
+
+            let file = fs::read_to_string(path).ok().and_then(|source| {
+                RustFileContent::parsed(path.to_path_buf(), source, IndexMap::new()).ok()
+            });
+            let Some(file) = file else {
+                debug!("Could not read the references of {}", path.display());
+                self.has_unknown_references = true;
+                continue;
+            };
+            let identifiers = file.collect_imported_identifiers(file.content().items());
+            self.references.insert(path.to_path_buf(), identifiers);
+        }
+    }
+
+    /// Replaces the identifiers recorded for `source` with `identifiers`, for
+    /// a file parts of which move elsewhere
+    pub fn record_references_of(&mut self, source: &Path, identifiers: HashSet<String>) {
+        self.references.insert(source.to_path_buf(), identifiers);
+    }
+
+    /// Records that the inline module `module` of `source` moves to
+    /// `module_file`, along with the identifiers its items reference, which
+    /// `source` no longer does. The module is private to the crate if it is
+    /// not declared `pub` or `source` is.
+    pub fn record_inline_module(
+        &mut self,
+        source: &Path,
+        module: &syn::ItemMod,
+        module_file: &Path,
+        identifiers: HashSet<String>,
+    ) {
+        if !matches!(module.vis, syn::Visibility::Public(_))
+            || self.crate_private_modules.contains(source)
+        {
+            self.crate_private_modules.insert(module_file.to_path_buf());
+        }
+        self.record_references_of(module_file, identifiers);
+    }
+
+    /// Checks if a source file other than `source` references any of `names`,
+    /// which is assumed if the references of some file are unknown
+    pub fn is_referenced_outside(&self, source: &Path, names: &[String]) -> bool {
+        if self.has_unknown_references {
+            // Any of them may be referenced
+            return true;
+        }
+
+        self.references
+            .iter()
+            .filter(|(file, _)| file.as_path() != source)
+            .any(|(_, identifiers)| names.iter().any(|name| identifiers.contains(name)))
+    }
+
+    /// Records that `rename.original_name()` produced from `rename.source()`
+    /// must be written as `rename.renamed()` instead
+    pub fn add_rename(&mut self, rename: FileRename) {
+        debug!("Planning rename {}", rename);
+        self.renames.push(rename);
+    }
+
+    /// Records and logs `warning` about the split
+    pub fn add_warning(&mut self, warning: impl Into<String>) {
+        let warning = warning.into();
+        warn!("{}", warning);
+        self.warnings.push(warning);
+    }
+
+    /// Returns the name a file named `file_name` split out of `source` into
+    /// `directory` must be written as, which is `file_name` unless renamed
+    pub fn renamed_file_name(&self, source: &Path, directory: &Path, file_name: &str) -> String {
+        self.renames
+            .iter()
+            .find(|rename| {
+                rename.source() == source
+                    && rename.directory() == directory
+                    && rename.original_name() == file_name
+            })
+            .map_or_else(|| file_name.to_owned(), |rename| rename.renamed().clone())
+    }
+
+    /// Returns the content `path` will have once the plan is executed, falling
+    /// back to its current content on disk, or `None` if it exists in neither
+    /// or is planned to be deleted.
+    pub fn current_content(&self, path: impl AsRef<Path>) -> Result<Option<String>> {
+        let path = path.as_ref();
+        if let Some(planned_file) = self.files.get(path) {
+            if planned_file.action() == &FileAction::Delete {
+                // Will not exist once the plan is executed
+                return Ok(None);
+            }
+            return Ok(Some(planned_file.content().clone()));
+        }
+
+        if !path.exists() {
+            // Neither planned nor on disk
+            return Ok(None);
+        }
+
+        fs::read_to_string(path)
+            .map(Some)
+            .map_err(|e| Error::io(IoAction::Read, path, e))
+    }
+
+    /// Returns `true` if no files are planned
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Returns the number of planned files
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Writes every planned file to disk, creating parent directories as
+    /// needed, and deletes the files planned for deletion
+    pub fn execute(&self) -> Result<()> {
+        self.files.values().try_for_each(Self::write_planned_file)
+    }
+
+    /// Records the current state of the disk for every planned file, so that
+    /// executing the plan can be rolled back with [`PlanBackup::restore`]
+    pub fn backup(&self) -> Result<PlanBackup> {
+        let mut backup = PlanBackup::default();
+        for path in self.files.keys() {
+            let content = if path.exists() {
+                Some(fs::read_to_string(path).map_err(|e| Error::io(IoAction::BackUp, path, e))?)
+            } else {
+                None
+            };
+            backup.insert_file(path, content);
+
+            let Some(parent) = path.parent() else {
+                // Nothing to create
+                continue;
+            };
+            let mut missing: Vec<&Path> = parent
+                .ancestors()
+                .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+                .collect();
+            missing.reverse();
+            missing
+                .into_iter()
+                .for_each(|dir| backup.insert_created_dir(dir));
+        }
+        Ok(backup)
+    }
+
+    /// Writes a single planned file to disk, or deletes it
+    fn write_planned_file(planned_file: &PlannedFile) -> Result<()> {
+        let path = planned_file.path();
+        if planned_file.action() == &FileAction::Delete {
+            debug!("Deleting {}", path.display());
+            return fs::remove_file(path).map_err(|e| Error::io(IoAction::Delete, path, e));
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| Error::io(IoAction::CreateDirectory, parent, e))?;
+        }
+
+        debug!("Writing ({}) {}", planned_file.action(), path.display());
+        fs::write(path, planned_file.content()).map_err(|e| Error::io(IoAction::Write, path, e))
+    }
+}
+
+impl std::fmt::Display for SplitPlan {
+    /// Lists every planned file with its action, followed by its full content
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Planned {} file(s):", self.len())?;
+        for planned_file in self.files.values() {
+            writeln!(
+                f,
+                "  {:<9} {}",
+                planned_file.action(),
+                planned_file.path().display()
+            )?;
+        }
+
+        if !self.renames.is_empty() {
+            writeln!(
+                f,
+                "Renamed {} file(s) to avoid name collisions:",
+                self.renames.len()
+            )?;
+            for rename in &self.renames {
+                writeln!(f, "  {}", rename)?;
+            }
+        }
+        if !self.warnings.is_empty() {
+            writeln!(f, "{} warning(s):", self.warnings.len())?;
+            for warning in &self.warnings {
+                writeln!(f, "  {}", warning)?;
+            }
+        }
+
+        for planned_file in self.files.values() {
+            if planned_file.action() == &FileAction::Delete {
+                // No content to show
+                continue;
+            }
+            writeln!(
+                f,
+                "\n==== {} {} ====",
+                planned_file.action(),
+                planned_file.path().display()
+            )?;
+            write!(f, "{}", planned_file.content())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_add_file_determines_action_from_disk() {
+        let dir = tempdir().unwrap();
+        let existing = dir.path().join("existing.rs");
+        fs::write(&existing, "struct Old;").unwrap();
+        let new = dir.path().join("new.rs");
+
+        let mut plan = SplitPlan::default();
+        plan.add_file(&existing, "struct A;");
+        plan.add_file(&new, "struct B;");
+
+        assert_eq!(plan.files()[&existing].action(), &FileAction::Overwrite);
+        assert_eq!(plan.files()[&new].action(), &FileAction::Create);
+    }
+
+    #[test]
+    fn test_add_file_twice_keeps_last_content() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.rs");
+
+        let mut plan = SplitPlan::default();
+        plan.add_file(&path, "first");
+        plan.add_file(&path, "second");
+
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan.files()[&path].content(), "second");
+        assert_eq!(plan.files()[&path].action(), &FileAction::Create);
+    }
+
+    #[test]
+    fn test_current_content_prefers_planned_content() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("mod.rs");
+        fs::write(&path, "mod on_disk;").unwrap();
+
+        let mut plan = SplitPlan::default();
+        assert_eq!(
+            plan.current_content(&path).unwrap().as_deref(),
+            Some("mod on_disk;")
+        );
+
+        plan.add_file(&path, "mod planned;");
+        assert_eq!(
+            plan.current_content(&path).unwrap().as_deref(),
+            Some("mod planned;")
+        );
+        assert_eq!(
+            plan.current_content(dir.path().join("missing.rs")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_execute_does_not_touch_disk_until_called() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("nested/a.rs");
+
+        let mut plan = SplitPlan::default();
+        plan.add_file(&path, "struct A;\n");
+        assert!(!path.exists());
+
+        plan.execute().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "struct A;\n");
+    }
+
+    #[test]
+    fn test_backup_restores_the_disk() {
+        let dir = tempdir().unwrap();
+        let existing = dir.path().join("foo.rs");
+        let deleted = dir.path().join("bar.rs");
+        let created = dir.path().join("nested/baz.rs");
+        fs::write(&existing, "struct Foo;").unwrap();
+        fs::write(&deleted, "struct Bar;").unwrap();
+
+        let mut plan = SplitPlan::default();
+        plan.add_file(&existing, "mod baz;");
+        plan.add_file(&created, "struct Baz;");
+        plan.remove_file(&deleted);
+
+        let backup = plan.backup().unwrap();
+        plan.execute().unwrap();
+        assert!(created.exists());
+        assert!(!deleted.exists());
+
+        backup.restore().unwrap();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "struct Foo;");
+        assert_eq!(fs::read_to_string(&deleted).unwrap(), "struct Bar;");
+        assert!(!dir.path().join("nested").exists());
+    }
+
+    #[test]
+    fn test_remove_file() {
+        let dir = tempdir().unwrap();
+        let existing = dir.path().join("foo.rs");
+        fs::write(&existing, "struct Foo;").unwrap();
+        let only_planned = dir.path().join("bar.rs");
+
+        let mut plan = SplitPlan::default();
+        plan.add_file(&only_planned, "struct Bar;");
+        plan.remove_file(&existing);
+        plan.remove_file(&only_planned);
+
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan.files()[&existing].action(), &FileAction::Delete);
+        assert_eq!(plan.current_content(&existing).unwrap(), None);
+
+        plan.execute().unwrap();
+        assert!(!existing.exists());
+    }
+
+    #[test]
+    fn test_module_edits_apply_to_final_content() {
+        let dir = tempdir().unwrap();
+        let mod_rs = dir.path().join("mod.rs");
+        fs::write(&mod_rs, "mod foo;\n\npub use foo::*;\n").unwrap();
+
+        let mut plan = SplitPlan::default();
+        plan.edit_module_declarations(&mod_rs).remove("foo");
+        plan.edit_module_declarations(&mod_rs)
+            .add(ModuleDeclaration::reexported("bar"));
+        plan.add_file(
+            &mod_rs,
+            "mod foo;\nmod baz;\n\npub use foo::*;\npub use baz::*;\n",
+        );
+        plan.apply_module_edits().unwrap();
+
+        assert_eq!(
+            plan.files()[&mod_rs].content(),
+            "mod bar;\nmod baz;\n\npub use bar::*;\npub use baz::*;\n"
+        );
+        assert!(plan.module_edits().is_empty());
+    }
+}
@@ -0,0 +1,203 @@
+use crate::prelude::*;
+use std::collections::HashSet;
+
+/// A machine readable summary of a split: the files it touches, the items it
+/// moves and everything klyv warned about
+#[derive(Clone, Debug, PartialEq, Eq, Getters, serde::Serialize)]
+pub struct SplitReport {
+    /// Every created, overwritten and deleted file, in planning order
+    #[getset(get = "pub")]
+    files: Vec<ReportedFile>,
+    /// Every named item which ends up in another file than it was written in
+    #[getset(get = "pub")]
+    moved_items: Vec<MovedItem>,
+    /// Renamed files, warnings of the plan, skipped paths and compile errors
+    /// the split introduced
+    #[getset(get = "pub")]
+    warnings: Vec<String>,
+}
+
+impl SplitReport {
+    /// Summarizes `outcome`
+    pub fn new(outcome: &SplitOutcome) -> Self {
+        let plan = outcome.plan();
+        let files = plan
+            .files()
+            .values()
+            .map(|planned_file| {
+                ReportedFile::builder()
+                    .path(planned_file.path().clone())
+                    .action(*planned_file.action())
+                    .build()
+            })
+            .collect();
+
+        Self {
+            files,
+            moved_items: Self::find_moved_items(outcome),
+            warnings: Self::collect_warnings(outcome),
+        }
+    }
+
+    /// The report as pretty printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a report only holds strings and paths")
+    }
+
+    /// Finds the items of every source file which are no longer defined in
+    /// the file at the same place in the output directory, but in a planned
+    /// file. If several planned files define an item with the same name, the
+    /// one closest to the source file is taken.
+    fn find_moved_items(outcome: &SplitOutcome) -> Vec<MovedItem> {
+        let node = outcome.node();
+        let source_root = match node {
+            FileSystemNode::Directory(_) => node.path().clone(),
+            FileSystemNode::RustFile(_) => {
+                node.path().parent().unwrap_or(Path::new("")).to_path_buf()
+            }
+        };
+        let out_root = if outcome.out().extension().is_some() {
+            outcome
+                .out()
+                .parent()
+                .unwrap_or(Path::new(""))
+                .to_path_buf()
+        } else {
+            outcome.out().clone()
+        };
+
+        let planned: Vec<(&PathBuf, HashSet<String>)> = outcome
+            .plan()
+            .files()
+            .values()
+            .filter(|planned_file| planned_file.action() != &FileAction::Delete)
+            .map(|planned_file| {
+                (
+                    planned_file.path(),
+                    Self::defined_names(planned_file.content()),
+                )
+            })
+            .collect();
+
+        let mut moved = Vec::new();
+        for file in node.rust_file_contents() {
+            let from = file.path();
+            let Ok(relative) = from.strip_prefix(&source_root) else {
+                // Not below the source root
+                continue;
+            };
+            let written_to = out_root.join(relative);
+
+            let kept: HashSet<String> = match planned.iter().find(|(path, _)| **path == written_to)
+            {
+                Some((_, names)) => names.clone(),
+                // Not rewritten, unless it is deleted its items stay
+                None if Self::is_deleted(outcome.plan(), &written_to) => HashSet::new(),
+                None => continue,
+            };
+
+            let mut names: Vec<String> = Self::defined_names(file.content().source())
+                .into_iter()
+                .filter(|name| !kept.contains(name))
+                .collect();
+            names.sort();
+
+            for name in names {
+                let Some((to, _)) = planned
+                    .iter()
+                    .filter(|(path, names)| **path != written_to && names.contains(&name))
+                    .max_by_key(|(path, _)| Self::common_components(path, &written_to))
+                else {
+                    // Removed, e.g. a use statement or an item with a collision
+                    continue;
+                };
+                moved.push(
+                    MovedItem::builder()
+                        .name(name)
+                        .from(from.clone())
+                        .to((*to).clone())
+                        .build(),
+                );
+            }
+        }
+        moved
+    }
+
+    /// Checks if `path` is planned to be deleted
+    fn is_deleted(plan: &SplitPlan, path: &Path) -> bool {
+        plan.files()
+            .get(path)
+            .is_some_and(|planned_file| planned_file.action() == &FileAction::Delete)
+    }
+
+    /// Names of the top level items `source` defines, empty if it does not
+    /// parse
+    fn defined_names(source: &str) -> HashSet<String> {
+        let Ok(file) = syn::parse_file(source) else {
+            // Nothing to compare with
+            return HashSet::new();
+        };
+
+        file.items
+            .iter()
+            .filter_map(|item| {
+                let ident = match item {
+                    syn::Item::Const(item) => &item.ident,
+                    syn::Item::Enum(item) => &item.ident,
+                    syn::Item::Fn(item) => &item.sig.ident,
+                    syn::Item::Macro(item) => item.ident.as_ref()?,
+                    syn::Item::Static(item) => &item.ident,
+                    syn::Item::Struct(item) => &item.ident,
+                    syn::Item::Trait(item) => &item.ident,
+                    syn::Item::TraitAlias(item) => &item.ident,
+                    syn::Item::Type(item) => &item.ident,
+                    syn::Item::Union(item) => &item.ident,
+                    _ => return None,
+                };
+                Some(ident.to_string())
+            })
+            .collect()
+    }
+
+    /// Counts the leading path components `a` and `b` share
+    fn common_components(a: &Path, b: &Path) -> usize {
+        a.components()
+            .zip(b.components())
+            .take_while(|(a, b)| a == b)
+            .count()
+    }
+
+    /// Everything klyv warns about while splitting
+    fn collect_warnings(outcome: &SplitOutcome) -> Vec<String> {
+        let renames = outcome
+            .plan()
+            .renames()
+            .iter()
+            .map(|rename| format!("Renamed to avoid a name collision: {}", rename));
+        let planned = outcome.plan().warnings().iter().cloned();
+        let skipped = outcome
+            .scan_report()
+            .failures()
+            .iter()
+            .map(|failure| format!("Skipped {}", failure));
+        let introduced = outcome.verification().iter().flat_map(|verification| {
+            let rolled_back = verification
+                .rolled_back()
+                .then(|| "The split introduced compile errors and was rolled back".to_owned());
+            verification
+                .after()
+                .new_diagnostics(verification.baseline())
+                .into_iter()
+                .filter(|diagnostic| diagnostic.level() == &DiagnosticLevel::Error)
+                .map(|diagnostic| format!("Introduced {}", diagnostic))
+                .chain(rolled_back)
+                .collect::<Vec<_>>()
+        });
+
+        renames
+            .chain(planned)
+            .chain(skipped)
+            .chain(introduced)
+            .collect()
+    }
+}
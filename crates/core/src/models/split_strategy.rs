@@ -0,0 +1,14 @@
+/// How klyv organizes the files it splits out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, derive_more::Display)]
+#[serde(rename_all = "kebab-case")]
+pub enum SplitStrategy {
+    /// `lib.rs` and `main.rs` are split into a types and a logic folder,
+    /// every other file into files next to it
+    #[default]
+    #[display("organized")]
+    Organized,
+    /// Every file, `lib.rs` and `main.rs` included, is split into files next
+    /// to it
+    #[display("flat")]
+    Flat,
+}
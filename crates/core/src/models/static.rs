@@ -0,0 +1,23 @@
+use crate::prelude::*;
+use syn::Ident;
+
+#[derive(Clone, Deref, From)]
+pub struct Static(ItemStatic);
+
+impl std::fmt::Debug for Static {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Static")
+            .field(&self.vis)
+            .field(&self.mutability)
+            .field(&self.ident)
+            .field(&self.attrs)
+            .field(&self.ty)
+            .finish()
+    }
+}
+
+impl Identifiable for Static {
+    fn ident(&self) -> &Ident {
+        &self.ident
+    }
+}
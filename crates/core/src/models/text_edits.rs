@@ -0,0 +1,84 @@
+use crate::prelude::*;
+use std::ops::Range;
+
+/// Replacements of byte ranges of a text, all referring to the original text
+/// and applied at once
+#[derive(Clone, Debug, Default, Getters)]
+pub struct TextEdits {
+    /// Replaced ranges and their replacements, in the order they were made
+    #[getset(get = "pub")]
+    edits: Vec<(Range<usize>, String)>,
+}
+
+impl TextEdits {
+    /// Replaces the text of `range` by `text`
+    pub fn replace(&mut self, range: Range<usize>, text: impl Into<String>) {
+        self.edits.push((range, text.into()));
+    }
+
+    /// Inserts `text` at `position`, after earlier insertions at the same
+    /// position
+    pub fn insert(&mut self, position: usize, text: impl Into<String>) {
+        self.replace(position..position, text);
+    }
+
+    /// Removes the text of `range`
+    pub fn remove(&mut self, range: Range<usize>) {
+        self.replace(range, String::new());
+    }
+
+    /// Adds all edits of `other`
+    pub fn extend(&mut self, other: TextEdits) {
+        self.edits.extend(other.edits);
+    }
+
+    /// Returns `true` if there are no edits
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Applies every edit to `text`, the original text the ranges refer to.
+    /// Overlapping removals are merged, other edits overlapping an earlier
+    /// one are skipped.
+    pub fn apply_to(&self, text: &str) -> String {
+        let mut edits: Vec<&(Range<usize>, String)> = self.edits.iter().collect();
+        edits.sort_by_key(|(range, _)| range.start);
+
+        let mut edited = String::new();
+        let mut position = 0;
+        for (range, replacement) in edits {
+            if range.start < position && replacement.is_empty() {
+                // Removes what is left of an earlier removal
+                position = position.max(range.end);
+                continue;
+            }
+            if range.start < position {
+                warn!("Skipping edit of {:?} overlapping an earlier edit", range);
+                continue;
+            }
+            edited.push_str(&text[position..range.start]);
+            edited.push_str(replacement);
+            position = range.end;
+        }
+        edited.push_str(&text[position..]);
+        edited
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edits_refer_to_the_original_text() {
+        let mut edits = TextEdits::default();
+        edits.insert(11, "!");
+        edits.remove(0..6);
+        edits.replace(6..11, "there");
+        edits.insert(11, "?");
+        edits.replace(2..8, "overlapping");
+        edits.remove(3..6);
+
+        assert_eq!(edits.apply_to("hello world"), "there!?");
+    }
+}
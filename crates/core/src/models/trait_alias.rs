@@ -0,0 +1,23 @@
+use crate::prelude::*;
+use syn::Ident;
+
+#[derive(Clone, Deref, From)]
+pub struct TraitAlias(ItemTraitAlias);
+
+impl std::fmt::Debug for TraitAlias {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TraitAlias")
+            .field(&self.vis)
+            .field(&self.ident)
+            .field(&self.generics)
+            .field(&self.attrs)
+            .field(&self.bounds)
+            .finish()
+    }
+}
+
+impl Identifiable for TraitAlias {
+    fn ident(&self) -> &Ident {
+        &self.ident
+    }
+}
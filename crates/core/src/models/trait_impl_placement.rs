@@ -0,0 +1,16 @@
+/// Where the impl of a trait for a type is placed when splitting
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, derive_more::Display)]
+#[serde(rename_all = "kebab-case")]
+pub enum TraitImplPlacement {
+    /// In the file of the type
+    #[default]
+    #[display("with-type")]
+    WithType,
+    /// In the file of the trait if it is defined in the same file, otherwise
+    /// in the file of the type
+    #[display("with-trait")]
+    WithTrait,
+    /// In a file of its own, `impls/<trait>_for_<type>.rs`
+    #[display("dedicated")]
+    Dedicated,
+}
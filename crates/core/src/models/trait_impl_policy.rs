@@ -0,0 +1,35 @@
+use crate::prelude::*;
+
+/// Where trait impls are placed, by default and for specific traits, read
+/// from the `[trait-impls]` table of the configuration.
+///
+/// ```toml
+/// [trait-impls]
+/// default = "with-type"
+/// traits = { Display = "dedicated", Shape = "with-trait" }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Getters, Builder, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct TraitImplPolicy {
+    /// Placement of the impls of traits not listed in `traits`
+    #[builder(default)]
+    #[getset(get = "pub")]
+    default: TraitImplPlacement,
+    /// Placement of the impls of each trait, by the name of the trait
+    #[builder(default)]
+    #[getset(get = "pub")]
+    traits: IndexMap<String, TraitImplPlacement>,
+}
+
+impl TraitImplPolicy {
+    /// The placement of the impls of the trait named `trait_name`
+    pub fn placement(&self, trait_name: &str) -> TraitImplPlacement {
+        self.traits.get(trait_name).copied().unwrap_or(self.default)
+    }
+}
+
+impl Default for TraitImplPolicy {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
@@ -0,0 +1,151 @@
+use crate::prelude::*;
+
+/// The types defined in every file of a tree and the module paths of the
+/// files, used to find the file defining the type an impl block written in
+/// another file is for, see `gather_impls`
+#[derive(Clone, Debug, Default, Getters)]
+pub struct TypeIndex {
+    /// Files defining a type, trait or type alias of each name
+    #[getset(get = "pub")]
+    definitions: IndexMap<String, Vec<PathBuf>>,
+    /// Module path from the crate root of every file which is part of a crate
+    #[getset(get = "pub")]
+    modules: IndexMap<PathBuf, Vec<String>>,
+}
+
+impl TypeIndex {
+    /// Indexes the types defined at the top level of every Rust file in `node`
+    pub fn new(node: &FileSystemNode) -> Self {
+        let mut index = Self::default();
+        for file in node.rust_file_contents() {
+            if let Some(module) = file.crate_module_path() {
+                index.insert_module(file.path(), module);
+            }
+            file.content()
+                .items()
+                .iter()
+                .filter_map(|item| file.extract_type_name_from_item(item))
+                .for_each(|name| index.insert(name, file.path()));
+        }
+        index
+    }
+
+    /// Records that the type `name` is defined in `file`
+    pub fn insert(&mut self, name: impl Into<String>, file: impl AsRef<Path>) {
+        self.definitions
+            .entry(name.into())
+            .or_default()
+            .push(file.as_ref().to_path_buf());
+    }
+
+    /// Records that `file` is the module `module` of its crate
+    pub fn insert_module(&mut self, file: impl AsRef<Path>, module: Vec<String>) {
+        self.modules.insert(file.as_ref().to_path_buf(), module);
+    }
+
+    /// Resolves `path`, written in `file`, to the file defining the type it
+    /// names.
+    ///
+    /// Paths through `crate::`, `self::` and `super::` and paths into child
+    /// modules are resolved through the module paths of the files. A single
+    /// name refers to the definition in `file` itself, or else to the only
+    /// definition of that name in the tree, since klyv can not tell which of
+    /// several ones is imported. Paths into other crates resolve to nothing.
+    pub fn resolve(&self, path: &syn::Path, file: &Path) -> Option<&PathBuf> {
+        let mut segments: Vec<String> = path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect();
+        let name = segments.pop()?;
+        let files = self.definitions.get(&name)?;
+
+        if segments.is_empty() {
+            // A name in scope
+            return files
+                .iter()
+                .find(|defining| *defining == file)
+                .or_else(|| (files.len() == 1).then(|| &files[0]));
+        }
+
+        let importer = self.modules.get(file);
+        let module: Vec<String> = match segments[0].as_str() {
+            "crate" => segments[1..].to_vec(),
+            "self" => importer?.iter().chain(&segments[1..]).cloned().collect(),
+            "super" => {
+                let levels = segments
+                    .iter()
+                    .take_while(|segment| *segment == "super")
+                    .count();
+                let importer = importer?;
+                let parent = &importer[..importer.len().checked_sub(levels)?];
+                parent.iter().chain(&segments[levels..]).cloned().collect()
+            }
+            _ => importer?.iter().chain(&segments).cloned().collect(),
+        };
+
+        files
+            .iter()
+            .find(|defining| self.modules.get(*defining) == Some(&module))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> TypeIndex {
+        let mut index = TypeIndex::default();
+        index.insert_module("src/lib.rs", vec![]);
+        index.insert_module("src/shapes.rs", vec!["shapes".to_owned()]);
+        index.insert_module(
+            "src/shapes/round.rs",
+            vec!["shapes".to_owned(), "round".to_owned()],
+        );
+        index.insert_module("src/units.rs", vec!["units".to_owned()]);
+        index.insert("Circle", "src/shapes/round.rs");
+        index.insert("Meter", "src/units.rs");
+        index.insert("Meter", "src/shapes.rs");
+        index
+    }
+
+    fn resolve(index: &TypeIndex, path: &str, file: &str) -> Option<PathBuf> {
+        index
+            .resolve(&syn::parse_str(path).unwrap(), Path::new(file))
+            .cloned()
+    }
+
+    #[test]
+    fn test_paths_resolve_through_module_paths() {
+        let index = index();
+        let round = Some(PathBuf::from("src/shapes/round.rs"));
+
+        assert_eq!(resolve(&index, "Circle", "src/units.rs"), round);
+        assert_eq!(
+            resolve(&index, "crate::shapes::round::Circle", "src/units.rs"),
+            round
+        );
+        assert_eq!(resolve(&index, "round::Circle", "src/shapes.rs"), round);
+        assert_eq!(
+            resolve(&index, "super::shapes::round::Circle", "src/units.rs"),
+            round
+        );
+        assert_eq!(resolve(&index, "other::Circle", "src/units.rs"), None);
+        assert_eq!(resolve(&index, "crate::units::Circle", "src/lib.rs"), None);
+    }
+
+    #[test]
+    fn test_ambiguous_names_resolve_only_in_their_own_file() {
+        let index = index();
+
+        assert_eq!(
+            resolve(&index, "Meter", "src/units.rs"),
+            Some(PathBuf::from("src/units.rs"))
+        );
+        assert_eq!(resolve(&index, "Meter", "src/lib.rs"), None);
+        assert_eq!(
+            resolve(&index, "crate::units::Meter", "src/lib.rs"),
+            Some(PathBuf::from("src/units.rs"))
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use crate::prelude::*;
+
+/// The result of checking that a package still compiles after a split, see
+/// `Input::verify`
+#[derive(Clone, Debug, PartialEq, Eq, Getters, Builder)]
+pub struct Verification {
+    /// Directory of the package that was checked
+    #[getset(get = "pub")]
+    package_dir: PathBuf,
+    /// Diagnostics of the package before the split
+    #[getset(get = "pub")]
+    baseline: CompileReport,
+    /// Diagnostics of the package after the split
+    #[getset(get = "pub")]
+    after: CompileReport,
+    /// Whether the split was rolled back because it introduced errors
+    #[getset(get = "pub")]
+    rolled_back: bool,
+}
+
+impl Verification {
+    /// Returns the diagnostics the split introduced, grouped by the file they
+    /// point at. Diagnostics without location are grouped under `None`.
+    pub fn introduced_diagnostics(&self) -> IndexMap<Option<PathBuf>, Vec<&Diagnostic>> {
+        let mut by_file: IndexMap<Option<PathBuf>, Vec<&Diagnostic>> = IndexMap::new();
+        for diagnostic in self.after.new_diagnostics(&self.baseline) {
+            by_file
+                .entry(diagnostic.file().clone())
+                .or_default()
+                .push(diagnostic);
+        }
+        by_file
+    }
+}
+
+impl std::fmt::Display for Verification {
+    /// Summarizes the error counts and lists the introduced diagnostics per
+    /// file
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "cargo check of {}: {} error(s) before the split, {} after{}",
+            self.package_dir.display(),
+            self.baseline.error_count(),
+            self.after.error_count(),
+            if self.rolled_back {
+                ", rolled back"
+            } else {
+                ""
+            }
+        )?;
+
+        for (file, diagnostics) in self.introduced_diagnostics() {
+            match file {
+                Some(file) => writeln!(f, "  {}", file.display())?,
+                None => writeln!(f, "  (no location)")?,
+            }
+            for diagnostic in diagnostics {
+                match diagnostic.line() {
+                    Some(line) => writeln!(f, "    line {}: {}", line, diagnostic.problem())?,
+                    None => writeln!(f, "    {}", diagnostic.problem())?,
+                }
+            }
+        }
+        Ok(())
+    }
+}